@@ -0,0 +1,38 @@
+//! Lex arbitrary UTF-8 under every lexer option and check the invariants the
+//! server relies on. Run with `cargo fuzz run lexer` from the repository root.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rs_ts_lsp_server::jsx::LanguageVariant;
+use rs_ts_lsp_server::lexer::{Lexer, LexerOptions};
+use rs_ts_lsp_server::token_kind::TokenKind;
+
+fuzz_target!(|input: &str| {
+    for language_variant in [LanguageVariant::Standard, LanguageVariant::Jsx] {
+        for nested_comments in [false, true] {
+            let options = LexerOptions {
+                nested_comments,
+                language_variant,
+            };
+            let (tokens, diagnostics) = Lexer::with_options(input, options).lex_with_diagnostics();
+
+            // Tokens tile the input and only the final Eof is empty.
+            let mut offset = 0;
+            let mut position = (1, 1);
+            for token in &tokens {
+                assert_eq!(token.start, offset);
+                assert!(token.start < token.end || token.kind == TokenKind::Eof);
+                assert!((token.line, token.column) >= position);
+                position = (token.line, token.column);
+                offset = token.end;
+            }
+            assert_eq!(tokens.last().map(|token| token.kind), Some(TokenKind::Eof));
+            assert_eq!(offset, input.len());
+
+            for diagnostic in &diagnostics {
+                assert!(diagnostic.start <= diagnostic.end && diagnostic.end <= input.len());
+            }
+        }
+    }
+});
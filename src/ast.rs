@@ -1,11 +1,668 @@
-/// AST module - Contains TypeScript AST structures.
-pub struct AST {
-    // TODO: Implement AST structures
+//! AST module — typed views over the concrete syntax tree.
+//!
+//! Each AST node wraps a [`SyntaxNode`] of one [`NodeKind`] and adds
+//! accessors for its parts. The wrappers own nothing: the syntax tree stays
+//! the single, lossless source of truth, and converting back is free.
+
+use crate::syntax::{NodeKind, SyntaxNode, SyntaxToken};
+use crate::token_kind::TokenKind;
+
+/// A typed wrapper around a syntax node of a particular kind.
+pub trait AstNode: Sized {
+    fn can_cast(kind: NodeKind) -> bool;
+
+    /// Wrap `node` if it has the right kind.
+    fn cast(node: SyntaxNode) -> Option<Self>;
+
+    fn syntax(&self) -> &SyntaxNode;
+}
+
+/// Declare AST wrappers named after the node kind they wrap.
+macro_rules! ast_nodes {
+    ($($(#[$attr:meta])* $name:ident;)*) => {
+        $(
+            $(#[$attr])*
+            #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+            pub struct $name(SyntaxNode);
+
+            impl AstNode for $name {
+                fn can_cast(kind: NodeKind) -> bool {
+                    kind == NodeKind::$name
+                }
+
+                fn cast(node: SyntaxNode) -> Option<Self> {
+                    Self::can_cast(node.kind()).then(|| Self(node))
+                }
+
+                fn syntax(&self) -> &SyntaxNode {
+                    &self.0
+                }
+            }
+        )*
+    };
+}
+
+/// Declare an enum over AST wrappers, for positions where any of several
+/// node kinds can appear.
+macro_rules! ast_enum {
+    ($(#[$attr:meta])* $name:ident { $($variant:ident,)* }) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($variant($variant),)*
+        }
+
+        impl AstNode for $name {
+            fn can_cast(kind: NodeKind) -> bool {
+                matches!(kind, $(NodeKind::$variant)|*)
+            }
+
+            fn cast(node: SyntaxNode) -> Option<Self> {
+                match node.kind() {
+                    $(NodeKind::$variant => Some(Self::$variant($variant(node))),)*
+                    _ => None,
+                }
+            }
+
+            fn syntax(&self) -> &SyntaxNode {
+                match self {
+                    $(Self::$variant(node) => node.syntax(),)*
+                }
+            }
+        }
+    };
+}
+
+ast_nodes! {
+    /// The root of a document.
+    SourceFile;
+    ExpressionStatement;
+    Block;
+    EmptyStatement;
+    VariableStatement;
+    VariableDeclarationList;
+    VariableDeclaration;
+    IfStatement;
+    DoStatement;
+    WhileStatement;
+    ForStatement;
+    ForInStatement;
+    ForOfStatement;
+    ContinueStatement;
+    BreakStatement;
+    ReturnStatement;
+    WithStatement;
+    SwitchStatement;
+    LabeledStatement;
+    ThrowStatement;
+    TryStatement;
+    CatchClause;
+    DebuggerStatement;
+    FunctionDeclaration;
+    ClassDeclaration;
+    ImportDeclaration;
+    ImportEqualsDeclaration;
+    ExportDeclaration;
+    ExportAssignment;
+
+    IdentifierExpression;
+    ThisExpression;
+    SuperExpression;
+    LiteralExpression;
+    TemplateExpression;
+    ArrayLiteralExpression;
+    ObjectLiteralExpression;
+    ParenthesizedExpression;
+    PropertyAccessExpression;
+    ElementAccessExpression;
+    CallExpression;
+    NewExpression;
+    MetaProperty;
+    ImportExpression;
+    TaggedTemplateExpression;
+    ExpressionWithTypeArguments;
+    NonNullExpression;
+    PrefixUnaryExpression;
+    PostfixUnaryExpression;
+    DeleteExpression;
+    TypeOfExpression;
+    VoidExpression;
+    AwaitExpression;
+    YieldExpression;
+    BinaryExpression;
+    ConditionalExpression;
+    AsExpression;
+    SatisfiesExpression;
+    TypeAssertionExpression;
+    SpreadElement;
+    ArrowFunction;
+    FunctionExpression;
+    ClassExpression;
+
+    ArgumentList;
+    ParameterList;
+    Parameter;
+    TypeArgumentList;
+}
+
+ast_enum! {
+    /// Any expression.
+    Expression {
+        IdentifierExpression,
+        ThisExpression,
+        SuperExpression,
+        LiteralExpression,
+        TemplateExpression,
+        ArrayLiteralExpression,
+        ObjectLiteralExpression,
+        ParenthesizedExpression,
+        PropertyAccessExpression,
+        ElementAccessExpression,
+        CallExpression,
+        NewExpression,
+        MetaProperty,
+        ImportExpression,
+        TaggedTemplateExpression,
+        ExpressionWithTypeArguments,
+        NonNullExpression,
+        PrefixUnaryExpression,
+        PostfixUnaryExpression,
+        DeleteExpression,
+        TypeOfExpression,
+        VoidExpression,
+        AwaitExpression,
+        YieldExpression,
+        BinaryExpression,
+        ConditionalExpression,
+        AsExpression,
+        SatisfiesExpression,
+        TypeAssertionExpression,
+        ArrowFunction,
+        FunctionExpression,
+        ClassExpression,
+    }
+}
+
+ast_enum! {
+    /// Any statement or declaration.
+    Statement {
+        ExpressionStatement,
+        Block,
+        EmptyStatement,
+        VariableStatement,
+        IfStatement,
+        DoStatement,
+        WhileStatement,
+        ForStatement,
+        ForInStatement,
+        ForOfStatement,
+        ContinueStatement,
+        BreakStatement,
+        ReturnStatement,
+        WithStatement,
+        SwitchStatement,
+        LabeledStatement,
+        ThrowStatement,
+        TryStatement,
+        DebuggerStatement,
+        FunctionDeclaration,
+        ClassDeclaration,
+        ImportDeclaration,
+        ImportEqualsDeclaration,
+        ExportDeclaration,
+        ExportAssignment,
+    }
+}
+
+impl SourceFile {
+    /// The empty `Eof` token that ends the file.
+    pub fn end_of_file_token(&self) -> Option<SyntaxToken> {
+        token(self.syntax(), TokenKind::Eof)
+    }
+
+    pub fn statements(&self) -> impl Iterator<Item = Statement> + '_ {
+        children(self.syntax())
+    }
+}
+
+impl Block {
+    pub fn statements(&self) -> impl Iterator<Item = Statement> + '_ {
+        children(self.syntax())
+    }
+}
+
+impl VariableStatement {
+    pub fn declaration_list(&self) -> Option<VariableDeclarationList> {
+        child(self.syntax())
+    }
+}
+
+impl VariableDeclarationList {
+    /// The `var`, `let`, `const` or `using` keyword; `await using` has the
+    /// `await` first.
+    pub fn keyword_token(&self) -> Option<SyntaxToken> {
+        first_token(self.syntax())
+    }
+
+    pub fn declarations(&self) -> impl Iterator<Item = VariableDeclaration> + '_ {
+        children(self.syntax())
+    }
+}
+
+impl VariableDeclaration {
+    /// The declared name, or `None` for a destructuring pattern.
+    pub fn name_token(&self) -> Option<SyntaxToken> {
+        first_token(self.syntax()).filter(|token| is_name(token.kind()))
+    }
+
+    pub fn initializer(&self) -> Option<Expression> {
+        child(self.syntax())
+    }
+}
+
+impl IfStatement {
+    pub fn condition(&self) -> Option<Expression> {
+        child(self.syntax())
+    }
+
+    pub fn then_statement(&self) -> Option<Statement> {
+        child(self.syntax())
+    }
+
+    pub fn else_statement(&self) -> Option<Statement> {
+        children(self.syntax()).nth(1)
+    }
+}
+
+impl ReturnStatement {
+    pub fn expression(&self) -> Option<Expression> {
+        child(self.syntax())
+    }
+}
+
+impl FunctionDeclaration {
+    pub fn name_token(&self) -> Option<SyntaxToken> {
+        tokens(self.syntax())
+            .find(|token| {
+                !matches!(
+                    token.kind(),
+                    TokenKind::Export
+                        | TokenKind::Default
+                        | TokenKind::Async
+                        | TokenKind::Function
+                        | TokenKind::Asterisk
+                )
+            })
+            .filter(|token| is_name(token.kind()))
+    }
+
+    pub fn parameter_list(&self) -> Option<ParameterList> {
+        child(self.syntax())
+    }
+
+    /// `None` for an overload signature.
+    pub fn body(&self) -> Option<Block> {
+        child(self.syntax())
+    }
+}
+
+impl ClassDeclaration {
+    pub fn name_token(&self) -> Option<SyntaxToken> {
+        tokens(self.syntax())
+            .find(|token| {
+                !matches!(
+                    token.kind(),
+                    TokenKind::Export | TokenKind::Default | TokenKind::Abstract | TokenKind::Class
+                )
+            })
+            .filter(|token| is_name(token.kind()) && token.kind() != TokenKind::Implements)
+    }
+}
+
+impl ImportDeclaration {
+    /// The string naming the imported module.
+    pub fn module_specifier_token(&self) -> Option<SyntaxToken> {
+        token(self.syntax(), TokenKind::StringLiteral)
+    }
+}
+
+impl ExportDeclaration {
+    /// The string naming the re-exported module, or `None` for a local
+    /// export list.
+    pub fn module_specifier_token(&self) -> Option<SyntaxToken> {
+        token(self.syntax(), TokenKind::StringLiteral)
+    }
+}
+
+impl ExportAssignment {
+    pub fn expression(&self) -> Option<Expression> {
+        child(self.syntax())
+    }
+}
+
+impl ExpressionStatement {
+    pub fn expression(&self) -> Option<Expression> {
+        child(self.syntax())
+    }
+}
+
+impl IdentifierExpression {
+    pub fn name_token(&self) -> Option<SyntaxToken> {
+        first_token(self.syntax())
+    }
 }
 
-impl AST {
-    pub fn new() -> Self {
-        AST {}
+impl LiteralExpression {
+    pub fn literal_token(&self) -> Option<SyntaxToken> {
+        first_token(self.syntax())
     }
 }
 
+impl ParenthesizedExpression {
+    pub fn expression(&self) -> Option<Expression> {
+        child(self.syntax())
+    }
+}
+
+impl PropertyAccessExpression {
+    pub fn expression(&self) -> Option<Expression> {
+        child(self.syntax())
+    }
+
+    /// The `?.` of an optional property access.
+    pub fn question_dot_token(&self) -> Option<SyntaxToken> {
+        token(self.syntax(), TokenKind::QuestionDot)
+    }
+
+    /// The property name, which may be a keyword or a private name.
+    pub fn name_token(&self) -> Option<SyntaxToken> {
+        last_token(self.syntax())
+            .filter(|token| !matches!(token.kind(), TokenKind::Dot | TokenKind::QuestionDot))
+    }
+}
+
+impl ElementAccessExpression {
+    pub fn expression(&self) -> Option<Expression> {
+        child(self.syntax())
+    }
+
+    pub fn argument_expression(&self) -> Option<Expression> {
+        children(self.syntax()).nth(1)
+    }
+}
+
+impl CallExpression {
+    pub fn expression(&self) -> Option<Expression> {
+        child(self.syntax())
+    }
+
+    pub fn type_arguments(&self) -> Option<TypeArgumentList> {
+        child(self.syntax())
+    }
+
+    pub fn arguments(&self) -> Option<ArgumentList> {
+        child(self.syntax())
+    }
+}
+
+impl NewExpression {
+    pub fn expression(&self) -> Option<Expression> {
+        child(self.syntax())
+    }
+
+    pub fn type_arguments(&self) -> Option<TypeArgumentList> {
+        child(self.syntax())
+    }
+
+    /// `None` for `new X` without parentheses.
+    pub fn arguments(&self) -> Option<ArgumentList> {
+        child(self.syntax())
+    }
+}
+
+impl ArgumentList {
+    /// The arguments, each an expression or a [`SpreadElement`].
+    pub fn arguments(&self) -> impl Iterator<Item = SyntaxNode> + '_ {
+        self.syntax().children().filter(|node| {
+            Expression::can_cast(node.kind()) || SpreadElement::can_cast(node.kind())
+        })
+    }
+}
+
+impl PrefixUnaryExpression {
+    pub fn operator_token(&self) -> Option<SyntaxToken> {
+        first_token(self.syntax())
+    }
+
+    pub fn operand(&self) -> Option<Expression> {
+        child(self.syntax())
+    }
+}
+
+impl PostfixUnaryExpression {
+    pub fn operand(&self) -> Option<Expression> {
+        child(self.syntax())
+    }
+
+    pub fn operator_token(&self) -> Option<SyntaxToken> {
+        last_token(self.syntax())
+    }
+}
+
+impl AwaitExpression {
+    pub fn expression(&self) -> Option<Expression> {
+        child(self.syntax())
+    }
+}
+
+impl BinaryExpression {
+    pub fn left(&self) -> Option<Expression> {
+        child(self.syntax())
+    }
+
+    /// The operator, which is the first token directly under the node.
+    pub fn operator_token(&self) -> Option<SyntaxToken> {
+        first_token(self.syntax())
+    }
+
+    pub fn right(&self) -> Option<Expression> {
+        children(self.syntax()).nth(1)
+    }
+}
+
+impl ConditionalExpression {
+    pub fn condition(&self) -> Option<Expression> {
+        child(self.syntax())
+    }
+
+    pub fn when_true(&self) -> Option<Expression> {
+        children(self.syntax()).nth(1)
+    }
+
+    pub fn when_false(&self) -> Option<Expression> {
+        children(self.syntax()).nth(2)
+    }
+}
+
+impl ArrowFunction {
+    pub fn is_async(&self) -> bool {
+        token(self.syntax(), TokenKind::Async).is_some()
+    }
+
+    /// The parameter list, or `None` for a single unparenthesized
+    /// parameter.
+    pub fn parameter_list(&self) -> Option<ParameterList> {
+        child(self.syntax())
+    }
+
+    /// The parameters, with or without parentheses.
+    pub fn parameters(&self) -> Vec<Parameter> {
+        match self.parameter_list() {
+            Some(list) => list.parameters().collect(),
+            None => children(self.syntax()).collect(),
+        }
+    }
+
+    /// The body when it is an expression rather than a block.
+    pub fn expression_body(&self) -> Option<Expression> {
+        child(self.syntax())
+    }
+
+    pub fn block_body(&self) -> Option<Block> {
+        child(self.syntax())
+    }
+}
+
+impl ParameterList {
+    pub fn parameters(&self) -> impl Iterator<Item = Parameter> + '_ {
+        children(self.syntax())
+    }
+}
+
+/// Whether a token of `kind` can be a declared name.
+fn is_name(kind: TokenKind) -> bool {
+    kind == TokenKind::Identifier || (kind.is_keyword() && !kind.is_reserved_word())
+}
+
+/// The first child of `parent` of type `N`.
+fn child<N: AstNode>(parent: &SyntaxNode) -> Option<N> {
+    children(parent).next()
+}
+
+/// The children of `parent` of type `N`.
+fn children<'a, N: AstNode + 'a>(parent: &'a SyntaxNode) -> impl Iterator<Item = N> + 'a {
+    parent.children().filter_map(N::cast)
+}
+
+/// The first token of `kind` directly under `parent`.
+fn token(parent: &SyntaxNode, kind: TokenKind) -> Option<SyntaxToken> {
+    tokens(parent).find(|token| token.kind() == kind)
+}
+
+/// The first non-trivia token directly under `parent`.
+fn first_token(parent: &SyntaxNode) -> Option<SyntaxToken> {
+    tokens(parent).next()
+}
+
+/// The last non-trivia token directly under `parent`.
+fn last_token(parent: &SyntaxNode) -> Option<SyntaxToken> {
+    tokens(parent).last()
+}
+
+fn tokens(parent: &SyntaxNode) -> impl Iterator<Item = SyntaxToken> + '_ {
+    parent
+        .children_with_tokens()
+        .filter_map(|element| element.into_token())
+        .filter(|token| !token.kind().is_trivia())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+    use crate::syntax::{GreenNodeBuilder, GreenToken};
+
+    #[test]
+    fn casts_by_kind() {
+        let source: Arc<str> = " ".into();
+        let mut builder = GreenNodeBuilder::new();
+        builder.start_node(NodeKind::SourceFile);
+        builder.start_node(NodeKind::Error);
+        builder.finish_node();
+        for token in &Lexer::new(Arc::clone(&source)).lex() {
+            builder.token(GreenToken::from_spanned(token, &source));
+        }
+        builder.finish_node();
+        let root = SyntaxNode::new_root(builder.finish());
+
+        let file = SourceFile::cast(root.clone()).unwrap();
+        assert_eq!(file.syntax(), &root);
+        assert_eq!(file.end_of_file_token().unwrap().start(), 1);
+        assert!(SourceFile::cast(root.children().next().unwrap()).is_none());
+    }
+
+    #[test]
+    fn expression_accessors() {
+        let parse = Parser::new("a?.b(c, ...d) * -e; async x => x").parse();
+        let root = parse.syntax();
+        let mut statements = root.children().filter_map(ExpressionStatement::cast);
+
+        let Some(Expression::BinaryExpression(binary)) = statements.next().unwrap().expression()
+        else {
+            panic!("expected a binary expression");
+        };
+        assert_eq!(binary.operator_token().unwrap().text(), "*");
+        let Some(Expression::CallExpression(call)) = binary.left() else {
+            panic!("expected a call");
+        };
+        assert_eq!(call.arguments().unwrap().arguments().count(), 2);
+        let Some(Expression::PropertyAccessExpression(access)) = call.expression() else {
+            panic!("expected a property access");
+        };
+        assert!(access.question_dot_token().is_some());
+        assert_eq!(access.name_token().unwrap().text(), "b");
+        let Some(Expression::PrefixUnaryExpression(negation)) = binary.right() else {
+            panic!("expected a prefix unary expression");
+        };
+        assert_eq!(negation.operand().unwrap().syntax().text(), "e");
+
+        let Some(Expression::ArrowFunction(arrow)) = statements.next().unwrap().expression() else {
+            panic!("expected an arrow function");
+        };
+        assert!(arrow.is_async());
+        assert_eq!(arrow.parameters().len(), 1);
+        assert!(arrow.block_body().is_none());
+        assert_eq!(arrow.expression_body().unwrap().syntax().text(), "x");
+    }
+
+    #[test]
+    fn statement_accessors() {
+        let parse = Parser::new(
+            "let a = 1, b
+if (a) return
+function get() {}
+export default class {}
+import x from \"m\"",
+        )
+        .parse();
+        let statements: Vec<Statement> = parse.tree().statements().collect();
+
+        let Statement::VariableStatement(variables) = &statements[0] else {
+            panic!("expected a variable statement");
+        };
+        let list = variables.declaration_list().unwrap();
+        assert_eq!(list.keyword_token().unwrap().text(), "let");
+        let declarations: Vec<VariableDeclaration> = list.declarations().collect();
+        assert_eq!(declarations[0].name_token().unwrap().text(), "a");
+        assert_eq!(declarations[0].initializer().unwrap().syntax().text(), "1");
+        assert!(declarations[1].initializer().is_none());
+
+        let Statement::IfStatement(if_statement) = &statements[1] else {
+            panic!("expected an if statement");
+        };
+        assert!(matches!(
+            if_statement.then_statement(),
+            Some(Statement::ReturnStatement(_))
+        ));
+        assert!(if_statement.else_statement().is_none());
+
+        let Statement::FunctionDeclaration(function) = &statements[2] else {
+            panic!("expected a function declaration");
+        };
+        assert_eq!(function.name_token().unwrap().text(), "get");
+        assert!(function.body().is_some());
+
+        let Statement::ClassDeclaration(class) = &statements[3] else {
+            panic!("expected a class declaration");
+        };
+        assert!(class.name_token().is_none());
+
+        let Statement::ImportDeclaration(import) = &statements[4] else {
+            panic!("expected an import declaration");
+        };
+        assert_eq!(import.module_specifier_token().unwrap().text(), "\"m\"");
+    }
+}
@@ -0,0 +1,32 @@
+//! Diagnostic module — structured errors reported while processing a document.
+
+/// How serious a diagnostic is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem found in the source, located by a byte range. `code` follows
+/// TypeScript's diagnostic numbering (e.g. `1125` for "Hexadecimal digit
+/// expected.") wherever tsc has an equivalent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub code: u32,
+    pub message: String,
+    pub start: usize,
+    pub end: usize,
+    pub severity: Severity,
+}
+
+impl Diagnostic {
+    pub fn error<S: Into<String>>(code: u32, message: S, start: usize, end: usize) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            start,
+            end,
+            severity: Severity::Error,
+        }
+    }
+}
@@ -0,0 +1,783 @@
+//! JSDoc module — parses `/** ... */` comments into a description and tags.
+//!
+//! Every piece keeps the byte range it came from in the document, so hover,
+//! deprecation markers and `.js` type information can point back at the
+//! source. Text is cleaned up on the way: the leading `*` of each line and
+//! the space after it are removed, and lines are joined with `\n`.
+
+use crate::token::{
+    SpannedToken, TokenPayload, is_identifier_part, is_identifier_start, is_line_terminator,
+};
+use crate::token_kind::TokenKind;
+
+/// A parsed JSDoc comment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsDoc {
+    /// The text before the first tag, if there is any.
+    pub description: Option<JsDocComment>,
+    pub tags: Vec<JsDocTag>,
+    /// Byte range of the whole comment, `/**` and `*/` included.
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A piece of cleaned-up comment text and the byte range it spans in the
+/// document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsDocText {
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Free-form text: a description, or the comment that follows a tag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsDocComment {
+    pub text: String,
+    /// The `{@link ...}` references within the text.
+    pub links: Vec<JsDocLink>,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Which inline tag introduced a link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsDocLinkKind {
+    /// `{@link ...}`
+    Link,
+    /// `{@linkcode ...}`
+    LinkCode,
+    /// `{@linkplain ...}`
+    LinkPlain,
+}
+
+/// An inline `{@link Target text}` or `{@link Target | text}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsDocLink {
+    pub kind: JsDocLinkKind,
+    pub target: Option<JsDocText>,
+    pub text: Option<JsDocText>,
+    /// Byte range of the link, braces included.
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A block tag such as `@param {string} name The name.`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsDocTag {
+    /// The tag name, without the `@`.
+    pub name: JsDocText,
+    pub kind: JsDocTagKind,
+    /// The text after the tag's own parts.
+    pub comment: Option<JsDocComment>,
+    /// Byte range from the `@` to the end of the tag's text.
+    pub start: usize,
+    pub end: usize,
+}
+
+/// What a tag says, for the tags that have structure beyond a comment. Type
+/// expressions are the text between the braces, without the braces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsDocTagKind {
+    /// `@param {T} name`, `@param {T} [name=default]`; also `@arg` and
+    /// `@argument`.
+    Param {
+        type_expression: Option<JsDocText>,
+        name: Option<JsDocText>,
+        /// The name was written in square brackets, making it optional.
+        is_bracketed: bool,
+        default: Option<JsDocText>,
+    },
+    /// `@returns {T}`, or `@return {T}`.
+    Returns { type_expression: Option<JsDocText> },
+    /// `@deprecated`
+    Deprecated,
+    /// `@example`. The comment keeps the indentation of the code.
+    Example,
+    /// `@see Name` or `@see {@link Name}`. A link stays in the comment and
+    /// `name` is only set for a bare reference.
+    See { name: Option<JsDocText> },
+    /// `@template {Constraint} T, U`
+    Template {
+        constraint: Option<JsDocText>,
+        type_parameters: Vec<JsDocText>,
+    },
+    /// `@type {T}`
+    Type { type_expression: Option<JsDocText> },
+    /// Any other tag; all of its text is the comment.
+    Unknown,
+}
+
+impl JsDoc {
+    /// Parse `comment`, the full text of a `/** ... */` comment that starts
+    /// at byte `offset` of the document. Returns `None` for comments that are
+    /// not JSDoc, including `/* ... */` and the empty `/**/`. An unterminated
+    /// comment is parsed up to the end of its text.
+    pub fn parse(comment: &str, offset: usize) -> Option<Self> {
+        if !comment.starts_with("/**") || comment.starts_with("/**/") {
+            return None;
+        }
+
+        let content = Content::new(comment, offset);
+        let parser = Parser { content: &content };
+        Some(Self {
+            description: parser.description(),
+            tags: parser.tags(),
+            start: offset,
+            end: offset + comment.len(),
+        })
+    }
+
+    /// Parse `token` if it is a JSDoc comment.
+    pub fn from_token(token: &SpannedToken) -> Option<Self> {
+        match (token.kind, &token.payload) {
+            (TokenKind::MultiLineCommentTrivia, TokenPayload::Text(text)) => {
+                Self::parse(text, token.start)
+            }
+            _ => None,
+        }
+    }
+
+    /// The JSDoc comment that documents the token `trivia` leads, which is
+    /// the last one in it.
+    pub fn from_leading_trivia(trivia: &[SpannedToken]) -> Option<Self> {
+        trivia.iter().rev().find_map(Self::from_token)
+    }
+
+    /// The tags named `name`, e.g. `"param"`.
+    pub fn tags_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a JsDocTag> {
+        self.tags.iter().filter(move |tag| tag.name.text == name)
+    }
+
+    pub fn is_deprecated(&self) -> bool {
+        self.tags
+            .iter()
+            .any(|tag| tag.kind == JsDocTagKind::Deprecated)
+    }
+
+    /// The `@param` tag for the parameter `name`.
+    pub fn param(&self, name: &str) -> Option<&JsDocTag> {
+        self.tags.iter().find(|tag| {
+            matches!(
+                &tag.kind,
+                JsDocTagKind::Param { name: Some(param), .. } if param.text == name
+            )
+        })
+    }
+}
+
+/// The cleaned-up text of a comment, with a map back to document offsets.
+struct Content {
+    text: String,
+    /// Where each line starts, as `(offset in text, offset in document)`.
+    /// Within a line the two advance together.
+    lines: Vec<(usize, usize)>,
+}
+
+impl Content {
+    fn new(comment: &str, offset: usize) -> Self {
+        let body_end = if comment.len() >= 5 && comment.ends_with("*/") {
+            comment.len() - 2
+        } else {
+            comment.len()
+        };
+
+        let mut text = String::new();
+        let mut lines = Vec::new();
+        let mut line_start = 3;
+        loop {
+            let line_end = comment[line_start..body_end]
+                .find(is_line_terminator)
+                .map_or(body_end, |index| line_start + index);
+            let line = &comment[line_start..line_end];
+            let content_start = if line_start == 3 {
+                line_start
+            } else {
+                line_start + margin(line)
+            };
+
+            if !lines.is_empty() {
+                text.push('\n');
+            }
+            lines.push((text.len(), offset + content_start));
+            text.push_str(&comment[content_start..line_end]);
+
+            let Some(terminator) = comment[line_end..body_end].chars().next() else {
+                break;
+            };
+            line_start = line_end + terminator.len_utf8();
+            if comment[line_start..].starts_with('\n') && terminator == '\r' {
+                line_start += 1;
+            }
+        }
+
+        Self { text, lines }
+    }
+
+    /// The document offset of byte `index` of the cleaned text. The `\n`
+    /// joining two lines maps to the end of the first.
+    fn offset(&self, index: usize) -> usize {
+        let line = self.lines.partition_point(|&(start, _)| start <= index) - 1;
+        let (text_start, source_start) = self.lines[line];
+        source_start + (index - text_start)
+    }
+
+    fn text(&self, start: usize, end: usize) -> JsDocText {
+        JsDocText {
+            text: self.text[start..end].to_string(),
+            start: self.offset(start),
+            end: self.offset(end),
+        }
+    }
+}
+
+/// The length of the leading `*` and whitespace that start a comment line:
+/// any whitespace, then one `*` and a single space after it if present.
+fn margin(line: &str) -> usize {
+    let indent = line.len() - line.trim_start_matches([' ', '\t']).len();
+    match line[indent..].strip_prefix('*') {
+        Some(rest) if rest.starts_with([' ', '\t']) => indent + 2,
+        Some(_) => indent + 1,
+        None => indent,
+    }
+}
+
+struct Parser<'a> {
+    content: &'a Content,
+}
+
+impl Parser<'_> {
+    fn description(&self) -> Option<JsDocComment> {
+        let end = self
+            .tag_starts()
+            .first()
+            .copied()
+            .unwrap_or(self.content.text.len());
+        self.comment(0, end, false)
+    }
+
+    fn tags(&self) -> Vec<JsDocTag> {
+        let starts = self.tag_starts();
+        starts
+            .iter()
+            .enumerate()
+            .map(|(index, &start)| {
+                let end = starts
+                    .get(index + 1)
+                    .copied()
+                    .unwrap_or(self.content.text.len());
+                self.tag(start, end)
+            })
+            .collect()
+    }
+
+    /// Offsets of the `@` of each block tag. A tag starts a line; an `@`
+    /// elsewhere is part of the text.
+    fn tag_starts(&self) -> Vec<usize> {
+        let text = &self.content.text;
+        let mut starts = Vec::new();
+        let mut line_start = 0;
+        for line in text.split('\n') {
+            let indent = line.len() - line.trim_start().len();
+            let mut rest = line[indent..].chars();
+            if rest.next() == Some('@') && rest.next().is_some_and(is_identifier_start) {
+                starts.push(line_start + indent);
+            }
+            line_start += line.len() + 1;
+        }
+        starts
+    }
+
+    /// Parse the tag whose `@` is at `start` and whose text runs to `end`.
+    fn tag(&self, start: usize, end: usize) -> JsDocTag {
+        let text = &self.content.text;
+        let name_end = start + 1 + identifier_len(&text[start + 1..end]);
+        let name = self.content.text(start + 1, name_end);
+        let mut cursor = Cursor {
+            text: &text[..end],
+            pos: name_end,
+        };
+
+        let kind = match name.text.as_str() {
+            "param" | "arg" | "argument" => {
+                let mut type_expression = self.type_expression(&mut cursor);
+                let (name, is_bracketed, default) = self.parameter_name(&mut cursor);
+                if type_expression.is_none() {
+                    type_expression = self.type_expression(&mut cursor);
+                }
+                cursor.skip_whitespace();
+                if let Some(rest) = cursor.rest().strip_prefix('-')
+                    && rest.starts_with(char::is_whitespace)
+                {
+                    cursor.pos += 1;
+                }
+                JsDocTagKind::Param {
+                    type_expression,
+                    name,
+                    is_bracketed,
+                    default,
+                }
+            }
+            "returns" | "return" => JsDocTagKind::Returns {
+                type_expression: self.type_expression(&mut cursor),
+            },
+            "deprecated" => JsDocTagKind::Deprecated,
+            "example" => JsDocTagKind::Example,
+            "see" => {
+                cursor.skip_whitespace();
+                let name = if cursor.rest().starts_with("{@link") {
+                    None
+                } else {
+                    let start = cursor.pos;
+                    cursor.pos += cursor
+                        .rest()
+                        .find(char::is_whitespace)
+                        .unwrap_or(cursor.rest().len());
+                    (cursor.pos > start).then(|| self.content.text(start, cursor.pos))
+                };
+                JsDocTagKind::See { name }
+            }
+            "template" => {
+                let constraint = self.type_expression(&mut cursor);
+                let mut type_parameters = Vec::new();
+                loop {
+                    cursor.skip_whitespace();
+                    let start = cursor.pos;
+                    cursor.pos += identifier_len(cursor.rest());
+                    if cursor.pos == start {
+                        break;
+                    }
+                    type_parameters.push(self.content.text(start, cursor.pos));
+                    cursor.skip_whitespace();
+                    if !cursor.eat(',') {
+                        break;
+                    }
+                }
+                JsDocTagKind::Template {
+                    constraint,
+                    type_parameters,
+                }
+            }
+            "type" => JsDocTagKind::Type {
+                type_expression: self.type_expression(&mut cursor),
+            },
+            _ => JsDocTagKind::Unknown,
+        };
+
+        let preserve_indent = kind == JsDocTagKind::Example;
+        let comment = self.comment(cursor.pos, end, preserve_indent);
+        JsDocTag {
+            name,
+            kind,
+            comment,
+            start: self.content.offset(start),
+            end: self
+                .content
+                .offset(start + text[start..end].trim_end().len()),
+        }
+    }
+
+    /// A `{...}` type expression, braces balanced. An unclosed one runs to
+    /// the end of the tag; an empty one is `None`.
+    fn type_expression(&self, cursor: &mut Cursor) -> Option<JsDocText> {
+        cursor.skip_whitespace();
+        if !cursor.eat('{') {
+            return None;
+        }
+        let start = cursor.pos;
+        let mut depth = 1;
+        let mut end = cursor.text.len();
+        for (index, c) in cursor.rest().char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        end = start + index;
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        cursor.pos = end;
+        cursor.eat('}');
+
+        self.trimmed(start, end)
+    }
+
+    /// `name`, or `[name]` / `[name=default]` for an optional parameter.
+    fn parameter_name(&self, cursor: &mut Cursor) -> (Option<JsDocText>, bool, Option<JsDocText>) {
+        cursor.skip_whitespace();
+        if cursor.eat('[') {
+            let start = cursor.pos;
+            let end = cursor
+                .rest()
+                .find([']', '\n'])
+                .map_or(cursor.text.len(), |index| start + index);
+            cursor.pos = end;
+            cursor.eat(']');
+
+            let (name_end, default) = match cursor.text[start..end].find('=') {
+                Some(index) => (start + index, self.trimmed(start + index + 1, end)),
+                None => (end, None),
+            };
+            return (self.trimmed(start, name_end), true, default);
+        }
+
+        let start = cursor.pos;
+        cursor.pos += cursor
+            .rest()
+            .find(|c: char| c.is_whitespace() || c == '{')
+            .unwrap_or(cursor.rest().len());
+        (
+            (cursor.pos > start).then(|| self.content.text(start, cursor.pos)),
+            false,
+            None,
+        )
+    }
+
+    fn trimmed(&self, start: usize, end: usize) -> Option<JsDocText> {
+        let text = &self.content.text[start..end];
+        let leading = text.len() - text.trim_start().len();
+        let text = text.trim();
+        (!text.is_empty()).then(|| {
+            self.content
+                .text(start + leading, start + leading + text.len())
+        })
+    }
+
+    /// The text between `start` and `end`, trimmed. With `preserve_indent`,
+    /// only a blank first line is dropped, so indented code keeps its shape.
+    fn comment(&self, start: usize, end: usize, preserve_indent: bool) -> Option<JsDocComment> {
+        let text = &self.content.text[start..end];
+        let leading = if preserve_indent {
+            let blank = text.len() - text.trim_start_matches([' ', '\t']).len();
+            if text[blank..].starts_with('\n') {
+                blank + 1
+            } else {
+                blank
+            }
+        } else {
+            text.len() - text.trim_start().len()
+        };
+        let start = start + leading;
+        let end = start + text[leading..].trim_end().len();
+        if start == end {
+            return None;
+        }
+
+        let text = self.content.text[start..end].to_string();
+        Some(JsDocComment {
+            links: self.links(start, end),
+            text,
+            start: self.content.offset(start),
+            end: self.content.offset(end),
+        })
+    }
+
+    /// The inline links between `start` and `end`.
+    fn links(&self, start: usize, end: usize) -> Vec<JsDocLink> {
+        let text = &self.content.text[..end];
+        let mut links = Vec::new();
+        let mut pos = start;
+        while let Some(index) = text[pos..].find("{@link") {
+            let link_start = pos + index;
+            let mut cursor = Cursor {
+                text,
+                pos: link_start + "{@link".len(),
+            };
+            let kind = if cursor.eat_str("code") {
+                JsDocLinkKind::LinkCode
+            } else if cursor.eat_str("plain") {
+                JsDocLinkKind::LinkPlain
+            } else {
+                JsDocLinkKind::Link
+            };
+            if cursor.rest().starts_with(is_identifier_part) {
+                // `{@linkfoo}` is not a link
+                pos = cursor.pos;
+                continue;
+            }
+
+            let close = cursor
+                .rest()
+                .find('}')
+                .map_or(text.len(), |index| cursor.pos + index);
+            cursor.text = &text[..close];
+            cursor.skip_whitespace();
+            let target_start = cursor.pos;
+            cursor.pos += cursor
+                .rest()
+                .find(|c: char| c.is_whitespace() || c == '|')
+                .unwrap_or(cursor.rest().len());
+            let target =
+                (cursor.pos > target_start).then(|| self.content.text(target_start, cursor.pos));
+            cursor.skip_whitespace();
+            cursor.eat('|');
+            let link_text = self.trimmed(cursor.pos, close);
+
+            let link_end = (close + 1).min(text.len());
+            links.push(JsDocLink {
+                kind,
+                target,
+                text: link_text,
+                start: self.content.offset(link_start),
+                end: self.content.offset(link_end),
+            });
+            pos = link_end;
+        }
+        links
+    }
+}
+
+/// A position within the cleaned text of one tag.
+struct Cursor<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl Cursor<'_> {
+    fn rest(&self) -> &str {
+        &self.text[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.pos = self.text.len() - self.rest().trim_start().len();
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let eaten = self.rest().starts_with(c);
+        if eaten {
+            self.pos += c.len_utf8();
+        }
+        eaten
+    }
+
+    fn eat_str(&mut self, s: &str) -> bool {
+        let eaten = self.rest().starts_with(s);
+        if eaten {
+            self.pos += s.len();
+        }
+        eaten
+    }
+}
+
+/// The byte length of the identifier at the start of `text`.
+fn identifier_len(text: &str) -> usize {
+    match text.chars().next() {
+        Some(c) if is_identifier_start(c) => text
+            .find(|c: char| !is_identifier_part(c))
+            .unwrap_or(text.len()),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::trivia::TokenList;
+
+    fn parse(comment: &str) -> JsDoc {
+        JsDoc::parse(comment, 0).expect("a JSDoc comment")
+    }
+
+    /// The source text a range covers.
+    fn source(comment: &str, start: usize, end: usize) -> &str {
+        &comment[start..end]
+    }
+
+    #[test]
+    fn parses_description_and_param_tags() {
+        let comment = "/**\n * Adds two numbers.\n * Really.\n *\n * @param {number} a - The first.\n * @param {number} [b=1] The second,\n *   continued.\n * @returns {number} The sum.\n */";
+        let doc = parse(comment);
+
+        let description = doc.description.as_ref().unwrap();
+        assert_eq!(description.text, "Adds two numbers.\nReally.");
+        assert_eq!(
+            source(comment, description.start, description.end),
+            "Adds two numbers.\n * Really."
+        );
+
+        assert_eq!(doc.tags.len(), 3);
+        let a = doc.param("a").unwrap();
+        let JsDocTagKind::Param {
+            type_expression,
+            is_bracketed,
+            ..
+        } = &a.kind
+        else {
+            panic!("expected a param tag");
+        };
+        assert_eq!(type_expression.as_ref().unwrap().text, "number");
+        assert!(!is_bracketed);
+        assert_eq!(a.comment.as_ref().unwrap().text, "The first.");
+        assert_eq!(
+            source(comment, a.start, a.end),
+            "@param {number} a - The first."
+        );
+
+        let b = doc.param("b").unwrap();
+        let JsDocTagKind::Param {
+            name,
+            is_bracketed,
+            default,
+            ..
+        } = &b.kind
+        else {
+            panic!("expected a param tag");
+        };
+        let name = name.as_ref().unwrap();
+        assert_eq!(source(comment, name.start, name.end), "b");
+        assert!(is_bracketed);
+        assert_eq!(default.as_ref().unwrap().text, "1");
+        assert_eq!(
+            b.comment.as_ref().unwrap().text,
+            "The second,\n  continued."
+        );
+
+        let returns = &doc.tags[2];
+        assert_eq!(returns.name.text, "returns");
+        let JsDocTagKind::Returns { type_expression } = &returns.kind else {
+            panic!("expected a returns tag");
+        };
+        let type_expression = type_expression.as_ref().unwrap();
+        assert_eq!(
+            source(comment, type_expression.start, type_expression.end),
+            "number"
+        );
+    }
+
+    #[test]
+    fn parses_structured_tags() {
+        let comment = "/** @type {Map<string, {a: number}>} */";
+        let doc = parse(comment);
+        assert_eq!(doc.description, None);
+        let JsDocTagKind::Type { type_expression } = &doc.tags[0].kind else {
+            panic!("expected a type tag");
+        };
+        assert_eq!(
+            type_expression.as_ref().unwrap().text,
+            "Map<string, {a: number}>"
+        );
+
+        let doc = parse(
+            "/**\n * @template {string} K, V Keys and values.\n * @deprecated Use `other`.\n */",
+        );
+        let JsDocTagKind::Template {
+            constraint,
+            type_parameters,
+        } = &doc.tags[0].kind
+        else {
+            panic!("expected a template tag");
+        };
+        assert_eq!(constraint.as_ref().unwrap().text, "string");
+        let names: Vec<&str> = type_parameters
+            .iter()
+            .map(|name| name.text.as_str())
+            .collect();
+        assert_eq!(names, ["K", "V"]);
+        assert_eq!(
+            doc.tags[0].comment.as_ref().unwrap().text,
+            "Keys and values."
+        );
+        assert!(doc.is_deprecated());
+        assert_eq!(doc.tags[1].comment.as_ref().unwrap().text, "Use `other`.");
+    }
+
+    #[test]
+    fn parses_links_and_see_tags() {
+        let comment = "/**\n * See {@link Foo.bar | the bar} and {@linkcode Baz}.\n * @see {@link Qux}\n * @see Other for more\n */";
+        let doc = parse(comment);
+
+        let links = &doc.description.as_ref().unwrap().links;
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].kind, JsDocLinkKind::Link);
+        assert_eq!(links[0].target.as_ref().unwrap().text, "Foo.bar");
+        assert_eq!(links[0].text.as_ref().unwrap().text, "the bar");
+        assert_eq!(
+            source(comment, links[0].start, links[0].end),
+            "{@link Foo.bar | the bar}"
+        );
+        assert_eq!(links[1].kind, JsDocLinkKind::LinkCode);
+        assert_eq!(links[1].text, None);
+
+        assert_eq!(doc.tags[0].kind, JsDocTagKind::See { name: None });
+        let comment_links = &doc.tags[0].comment.as_ref().unwrap().links;
+        assert_eq!(comment_links[0].target.as_ref().unwrap().text, "Qux");
+
+        let JsDocTagKind::See { name } = &doc.tags[1].kind else {
+            panic!("expected a see tag");
+        };
+        assert_eq!(name.as_ref().unwrap().text, "Other");
+        assert_eq!(doc.tags[1].comment.as_ref().unwrap().text, "for more");
+    }
+
+    #[test]
+    fn example_keeps_indentation() {
+        let doc = parse("/**\r\n * @example\r\n * if (x) {\r\n *     run();\r\n * }\r\n */");
+        assert_eq!(doc.tags[0].kind, JsDocTagKind::Example);
+        assert_eq!(
+            doc.tags[0].comment.as_ref().unwrap().text,
+            "if (x) {\n    run();\n}"
+        );
+    }
+
+    #[test]
+    fn ignores_other_comments_and_stray_at_signs() {
+        assert_eq!(JsDoc::parse("/* @param x */", 0), None);
+        assert_eq!(JsDoc::parse("/**/", 0), None);
+
+        let doc = parse("/** Mail me@example.com */");
+        assert!(doc.tags.is_empty());
+        assert_eq!(doc.description.unwrap().text, "Mail me@example.com");
+
+        let doc = parse("/** Unterminated @param");
+        assert_eq!(doc.description.unwrap().text, "Unterminated @param");
+    }
+
+    #[test]
+    fn finds_doc_comment_in_leading_trivia() {
+        let input = "/** Old. */\n/** Doubles `x`. */\nfunction double(x) {}";
+        let list = TokenList::new(Lexer::new(input).lex());
+        let function = &list.significant()[0];
+
+        let doc = JsDoc::from_leading_trivia(list.leading_trivia(function)).unwrap();
+        assert_eq!(doc.description.unwrap().text, "Doubles `x`.");
+        assert_eq!((doc.start, doc.end), (12, 31));
+    }
+
+    proptest! {
+        #[test]
+        fn ranges_stay_within_any_comment(
+            body in "(@param|@see|@template|@type|@example|\\{@link|[{}\\[\\]=|*@ ]\
+                |\r\n|\n|\u{2028}|\\PC){0,40}",
+            terminated in any::<bool>(),
+        ) {
+            let comment = format!("/**{body}{}", if terminated { "*/" } else { "" });
+            let Some(doc) = JsDoc::parse(&comment, 0) else {
+                return Ok(());
+            };
+            let mut ranges = vec![(doc.start, doc.end)];
+            let comments = doc
+                .description
+                .iter()
+                .chain(doc.tags.iter().filter_map(|tag| tag.comment.as_ref()));
+            for text in comments {
+                ranges.push((text.start, text.end));
+                ranges.extend(text.links.iter().map(|link| (link.start, link.end)));
+            }
+            for tag in &doc.tags {
+                ranges.push((tag.start, tag.end));
+                ranges.push((tag.name.start, tag.name.end));
+            }
+            for (start, end) in ranges {
+                prop_assert!(start <= end && end <= comment.len());
+                prop_assert!(comment.is_char_boundary(start) && comment.is_char_boundary(end));
+            }
+        }
+    }
+}
@@ -1,433 +1,3462 @@
 //! Lexer module — converts source code into tokens.
 
-use crate::token::{SpannedToken, Token};
+use std::borrow::Cow;
+use std::sync::Arc;
 
-/// A very small, byte-oriented lexer suitable for ASCII-oriented languages.
-/// For simplicity, we treat input as bytes and only support ASCII. You can
-/// later switch to a UTF-8 character iterator if you need full Unicode.
+use crate::diagnostic::Diagnostic;
+use crate::jsx::{LanguageVariant, decode_entities};
+use crate::symbol::Interner;
+use crate::text::Text;
+use crate::token::{
+    LineTerminator, SpannedToken, Token, TokenPayload, is_identifier_part, is_identifier_start,
+    is_line_terminator,
+};
+use crate::token_kind::TokenKind;
+
+/// The flags a regular expression literal may carry.
+const REGEX_FLAGS: &str = "dgimsuvy";
+
+/// What the lexer is currently inside. The context stack decides what a `}`
+/// closes and, in JSX files, whether input is scanned as code, as the inside
+/// of a tag, or as text children.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LexContext {
+    /// A `{` opened in ordinary code.
+    Block,
+    /// A `${` in a template; the matching `}` resumes the template.
+    TemplateSubstitution,
+    /// A `{` in a JSX tag or among children; the matching `}` returns to JSX.
+    JsxExpression,
+    /// Between the `<` and `>` of an opening tag. `self_closing` is set once
+    /// the `/` of `/>` has been seen.
+    JsxOpeningTag { self_closing: bool },
+    /// Between the `</` and `>` of a closing tag.
+    JsxClosingTag,
+    /// Between an opening tag's `>` and the matching `</`.
+    JsxChildren,
+}
+
+/// What a `<` right after the previous significant token would open in a
+/// JSX file, as far as the scanner can tell without a parser.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum AnglePosition {
+    /// An expression, where `<` starts a JSX element unless it reads as type
+    /// parameters.
+    #[default]
+    Expression,
+    /// A type annotation or an object literal value, after a `:` that does
+    /// not belong to a conditional. `<T>(` there starts a generic function
+    /// type.
+    AfterColon,
+    /// A type, after the `=` of a `type` alias, where `<` never starts JSX.
+    Type,
+}
+
+/// How far the scanner has got through the head of a `type Name<...> =`
+/// declaration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum TypeAliasHead {
+    #[default]
+    None,
+    Keyword,
+    Name,
+    /// Inside the type parameters, with this many `<` still open.
+    Parameters(usize),
+}
+
+/// What the scanner remembers of the tokens before a `<` to keep type
+/// parameters in types from being read as JSX. Only kept in JSX files. At
+/// a `;` outside any braces it is always back to its default.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct TypeContext {
+    angle_position: AnglePosition,
+    alias: TypeAliasHead,
+    /// The context stack depths of the `?`s of conditional expressions
+    /// still waiting for their `:`.
+    conditionals: Vec<usize>,
+    /// Whether the previous token was a `?`, which is not a conditional
+    /// after all if the next token ends an optional name (`x?: T`).
+    after_question: bool,
+}
+
+impl TypeContext {
+    /// Update the context for the significant `token` spelled `text`, with
+    /// `depth` entries left on the scanner's context stack.
+    fn advance(&mut self, token: &Token, text: &str, depth: usize) {
+        if self.after_question
+            && matches!(
+                token,
+                Token::Colon | Token::CloseParen | Token::Comma | Token::Equals | Token::Semicolon
+            )
+        {
+            self.conditionals.pop();
+        }
+        self.after_question = *token == Token::Question;
+        self.conditionals.retain(|&open| open <= depth);
+
+        self.angle_position = AnglePosition::Expression;
+        match token {
+            Token::Question => self.conditionals.push(depth),
+            Token::Colon if self.conditionals.last() == Some(&depth) => {
+                self.conditionals.pop();
+            }
+            Token::Colon => self.angle_position = AnglePosition::AfterColon,
+            Token::Semicolon => self.conditionals.retain(|&open| open < depth),
+            _ => {}
+        }
+
+        self.alias = match (self.alias, token) {
+            (_, Token::Semicolon) => TypeAliasHead::None,
+            (TypeAliasHead::Parameters(open), _) => {
+                let open = open + usize::from(*token == Token::LessThan);
+                let closed = text.chars().take_while(|&c| c == '>').count();
+                match open.checked_sub(closed) {
+                    // `type A<T>= B` lexes the `=` into the `>=`
+                    Some(0) if closed > 0 && text.ends_with('=') => {
+                        self.angle_position = AnglePosition::Type;
+                        TypeAliasHead::None
+                    }
+                    Some(0) => TypeAliasHead::Name,
+                    Some(open) => TypeAliasHead::Parameters(open),
+                    None => TypeAliasHead::None,
+                }
+            }
+            (_, Token::Type) => TypeAliasHead::Keyword,
+            (TypeAliasHead::Keyword, Token::Identifier(_)) => TypeAliasHead::Name,
+            (TypeAliasHead::Name, Token::LessThan) => TypeAliasHead::Parameters(1),
+            (TypeAliasHead::Name, Token::Equals) => {
+                self.angle_position = AnglePosition::Type;
+                TypeAliasHead::None
+            }
+            _ => TypeAliasHead::None,
+        };
+    }
+}
+
+/// Knobs that change how the lexer reads its input. The defaults follow
+/// ECMAScript.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LexerOptions {
+    /// Let `/* ... */` comments nest, so each `/*` needs its own `*/`. This is
+    /// not valid JavaScript; it exists for dialects that allow it.
+    pub nested_comments: bool,
+    /// Whether to recognise JSX elements, as in `.tsx` and `.jsx` files.
+    pub language_variant: LanguageVariant,
+}
+
+/// A single replacement in a document: bytes `start..old_end` of the old
+/// text were replaced, and the new text occupies `start..new_end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextEdit {
+    pub start: usize,
+    pub old_end: usize,
+    pub new_end: usize,
+}
+
+/// Convenience wrapper that lexes a whole document into a token vector.
+/// Consumers that need to pull tokens on demand, look ahead, or re-scan a
+/// token in a different context use [`Scanner`] directly.
 pub struct Lexer {
-    source: String,
+    scanner: Scanner,
 }
 
 impl Lexer {
     /// Create a new lexer from a source string.
-    pub fn new<S: Into<String>>(source: S) -> Self {
+    pub fn new<S: Into<Arc<str>>>(source: S) -> Self {
+        Self::with_options(source, LexerOptions::default())
+    }
+
+    /// Create a new lexer that reads `source` according to `options`.
+    pub fn with_options<S: Into<Arc<str>>>(source: S, options: LexerOptions) -> Self {
         Self {
-            source: source.into(),
+            scanner: Scanner::with_options(source, options),
         }
     }
 
+    /// Lex the whole source, trivia included, ending with an `Eof` token.
     pub fn lex(&mut self) -> Vec<SpannedToken> {
-        let mut tokens = Vec::new();
-        let mut chars = self.source.chars().peekable();
-        let mut line = 1;
-        let mut column = 1;
+        self.scanner.reset();
+        self.scanner.by_ref().collect()
+    }
 
-        while let Some(&ch) = chars.peek() {
-            let start_line = line;
-            let start_column = column;
-
-            // Handle whitespace and newlines (emit as trivia)
-            if ch.is_whitespace() {
-                if ch == '\n' {
-                    // Emit newline trivia
-                    tokens.push(SpannedToken {
-                        value: Token::NewLineTrivia,
-                        line: start_line,
-                        column: start_column,
-                    });
-                    chars.next();
-                    line += 1;
-                    column = 1;
-                    continue;
-                } else {
-                    // Collect consecutive whitespace (spaces, tabs, etc.)
-                    let mut whitespace = String::new();
-                    while let Some(&c) = chars.peek() {
-                        if c == '\n' || !c.is_whitespace() {
-                            break;
-                        }
-                        whitespace.push(c);
-                        chars.next();
-                        column += 1;
-                    }
-                    tokens.push(SpannedToken {
-                        value: Token::WhitespaceTrivia(whitespace),
-                        line: start_line,
-                        column: start_column,
-                    });
-                    continue;
-                }
-            }
+    /// Intern identifier names into `interner`; see [`Scanner::with_interner`].
+    pub fn with_interner(self, interner: Arc<Interner>) -> Self {
+        Self {
+            scanner: self.scanner.with_interner(interner),
+        }
+    }
 
-            // Handle comments
-            if ch == '/' {
-                let next = chars.clone().nth(1);
-                if next == Some('/') {
-                    // Single-line comment - store full comment including "//" marker
-                    let mut comment = String::from("//");
-                    chars.next(); // consume first '/'
-                    chars.next(); // consume second '/'
-                    column += 2;
-                    while let Some(&c) = chars.peek() {
-                        if c == '\n' {
-                            break;
-                        }
-                        comment.push(c);
-                        chars.next();
-                        column += 1;
-                    }
-                    tokens.push(SpannedToken {
-                        value: Token::SingleLineCommentTrivia(comment),
-                        line: start_line,
-                        column: start_column,
-                    });
-                    continue;
-                } else if next == Some('*') {
-                    // Multi-line comment - store full comment including "/* */" markers
-                    let mut comment = String::from("/*");
-                    chars.next(); // consume '/'
-                    chars.next(); // consume '*'
-                    column += 2;
-                    let mut depth = 1;
-                    while let Some(&c) = chars.peek() {
-                        if c == '\n' {
-                            line += 1;
-                            column = 1;
-                            comment.push(c);
-                            chars.next();
-                        } else {
-                            column += 1;
-                            if c == '*' && chars.clone().nth(1) == Some('/') {
-                                comment.push('*');
-                                comment.push('/');
-                                chars.next(); // consume '*'
-                                chars.next(); // consume '/'
-                                depth -= 1;
-                                if depth == 0 {
-                                    break;
-                                }
-                            } else if c == '/' && chars.clone().nth(1) == Some('*') {
-                                comment.push('/');
-                                comment.push('*');
-                                chars.next(); // consume '/'
-                                chars.next(); // consume '*'
-                                depth += 1;
-                            } else {
-                                comment.push(c);
-                                chars.next();
-                            }
-                        }
-                    }
-                    tokens.push(SpannedToken {
-                        value: Token::MultiLineCommentTrivia(comment),
-                        line: start_line,
-                        column: start_column,
-                    });
-                    continue;
-                }
-            }
+    /// The interner holding the names of the identifiers lexed so far.
+    pub fn interner(&self) -> &Arc<Interner> {
+        self.scanner.interner()
+    }
 
-            // Handle string literals
-            if ch == '"' || ch == '\'' {
-                let quote = ch;
-                chars.next();
-                column += 1;
-                let mut value = String::new();
-                let mut escaped = false;
-
-                while let Some(&c) = chars.peek() {
-                    if escaped {
-                        match c {
-                            'n' => value.push('\n'),
-                            't' => value.push('\t'),
-                            'r' => value.push('\r'),
-                            '\\' => value.push('\\'),
-                            '"' => value.push('"'),
-                            '\'' => value.push('\''),
-                            _ => value.push(c),
-                        }
-                        escaped = false;
-                        chars.next();
-                        column += 1;
-                    } else if c == '\\' {
-                        escaped = true;
-                        chars.next();
-                        column += 1;
-                    } else if c == quote {
-                        chars.next();
-                        column += 1;
-                        break;
-                    } else {
-                        if c == '\n' {
-                            line += 1;
-                            column = 1;
-                        } else {
-                            column += 1;
-                        }
-                        value.push(c);
-                        chars.next();
-                    }
-                }
+    /// Diagnostics reported by the last call to [`Lexer::lex`].
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        self.scanner.diagnostics()
+    }
 
-                tokens.push(SpannedToken {
-                    value: Token::StringLiteral(value),
-                    line: start_line,
-                    column: start_column,
-                });
-                continue;
-            }
-
-            // Handle numeric literals
-            if ch.is_ascii_digit() {
-                let mut num_str = String::new();
-                let mut has_dot = false;
-                let mut is_bigint = false;
-
-                while let Some(&c) = chars.peek() {
-                    if c.is_ascii_digit() {
-                        num_str.push(c);
-                        chars.next();
-                        column += 1;
-                    } else if c == '.' && !has_dot {
-                        num_str.push(c);
-                        chars.next();
-                        column += 1;
-                        has_dot = true;
-                    } else if c == 'n' && !has_dot {
-                        // BigInt literal
-                        is_bigint = true;
-                        chars.next();
-                        column += 1;
-                        break;
-                    } else {
-                        break;
-                    }
-                }
+    /// Lex the whole source, returning the tokens together with the
+    /// diagnostics reported along the way.
+    pub fn lex_with_diagnostics(&mut self) -> (Vec<SpannedToken>, Vec<Diagnostic>) {
+        let tokens = self.lex();
+        (tokens, std::mem::take(&mut self.scanner.diagnostics))
+    }
 
-                let token = if is_bigint {
-                    Token::BigIntLiteral(num_str)
-                } else {
-                    Token::NumericLiteral(num_str)
-                };
-                tokens.push(SpannedToken {
-                    value: token,
-                    line: start_line,
-                    column: start_column,
-                });
-                continue;
-            }
-
-            // Handle identifiers and keywords
-            if ch.is_ascii_alphabetic() || ch == '_' || ch == '$' {
-                let mut ident = String::new();
-                while let Some(&c) = chars.peek() {
-                    if c.is_ascii_alphanumeric() || c == '_' || c == '$' {
-                        ident.push(c);
-                        chars.next();
-                        column += 1;
-                    } else {
-                        break;
-                    }
-                }
+    /// Bring `tokens` and `diagnostics`, lexed from `previous_source` with
+    /// the same options, up to date with this lexer's source, which is
+    /// `previous_source` after `edit`. Only the damaged region is scanned
+    /// again: scanning resumes at the last line start before the edit, and
+    /// stops once it is back in step with the old tokens after the edit.
+    /// The result is the same as [`Lexer::lex_with_diagnostics`] on the new
+    /// source.
+    ///
+    /// The tokens outside the damaged region stay where they are in
+    /// `tokens`; they are only shifted and pointed at the new source, so
+    /// that `previous_source` can be freed. Nothing is allocated for them.
+    ///
+    /// Scanning can only resume where the scanner state is known from the
+    /// tokens. In JSX files that is only after a `;` outside any braces, so
+    /// more of the file may be scanned again there.
+    pub fn relex(
+        &mut self,
+        previous_source: &Arc<str>,
+        tokens: &mut Vec<SpannedToken>,
+        diagnostics: &mut Vec<Diagnostic>,
+        edit: &TextEdit,
+    ) {
+        let jsx = self.scanner.options.language_variant == LanguageVariant::Jsx;
+        let shift = edit.new_end as isize - edit.old_end as isize;
+        let source = Arc::clone(&self.scanner.source);
 
-                // Check if it's a keyword
-                let token =
-                    crate::token::find_match(&ident).unwrap_or_else(|| Token::Identifier(ident));
-                tokens.push(SpannedToken {
-                    value: token,
-                    line: start_line,
-                    column: start_column,
-                });
-                continue;
-            }
-
-            // Handle operators and punctuation (try longest match first)
-            let mut matched = false;
-            // Build up potential operator strings (up to 3 chars)
-            let mut op_chars = Vec::new();
-            let mut peek_iter = chars.clone();
-            for _ in 0..3 {
-                if let Some(&c) = peek_iter.peek() {
-                    op_chars.push(c);
-                    peek_iter.next();
-                } else {
-                    break;
-                }
+        // Find the last line start before the edit at which scanning can resume
+        let mut state = ResumeState::default();
+        let mut restart = (0, state.clone());
+        for (index, token) in tokens.iter().enumerate() {
+            if token.start >= edit.start {
+                break;
             }
+            if index > 0
+                && tokens[index - 1].kind == TokenKind::NewLineTrivia
+                && state.is_resumable(jsx)
+            {
+                restart = (index, state.clone());
+            }
+            state.advance(token);
+        }
+        let (restart, mut state) = restart;
 
-            // Try matching from longest to shortest
-            for len in (1..=op_chars.len()).rev() {
-                let op_str: String = op_chars[..len].iter().collect();
-                if let Some(token) = crate::token::find_match(&op_str) {
-                    tokens.push(SpannedToken {
-                        value: token,
-                        line: start_line,
-                        column: start_column,
-                    });
-                    // Consume the matched characters
-                    for _ in 0..len {
-                        if let Some(c) = chars.next() {
-                            if c == '\n' {
-                                line += 1;
-                                column = 1;
-                            } else {
-                                column += 1;
-                            }
-                        }
+        let restart_offset = tokens.get(restart).map_or(0, |token| token.start);
+        self.scanner.reset();
+        self.scanner.diagnostics = diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.start < restart_offset)
+            .cloned()
+            .collect();
+        if let Some(token) = tokens.get(restart) {
+            self.scanner.resume_at(token, &state);
+        }
+        for token in &mut tokens[..restart] {
+            token.payload.rebase(previous_source, &source, 0);
+        }
+
+        // Scan until a line start after the edit lines up with an old token
+        // scanned in the same state; everything after it is unchanged.
+        let mut scanned: Vec<SpannedToken> = Vec::new();
+        let mut old_index = restart;
+        let mut old_state = state.clone();
+        loop {
+            let diagnostic_count = self.scanner.diagnostics.len();
+            let token = self.scanner.next_token();
+            let at_line_start = scanned
+                .last()
+                .or_else(|| restart.checked_sub(1).map(|last| &tokens[last]))
+                .is_some_and(|last| last.kind == TokenKind::NewLineTrivia);
+            if at_line_start && token.start >= edit.new_end && state.is_resumable(jsx) {
+                let old_start = token.start.checked_add_signed(-shift);
+                while old_index < tokens.len() && Some(tokens[old_index].start) < old_start {
+                    old_state.advance(&tokens[old_index]);
+                    old_index += 1;
+                }
+                if let Some(old) = tokens.get(old_index)
+                    && Some(old.start) == old_start
+                    && old_index > 0
+                    && tokens[old_index - 1].kind == TokenKind::NewLineTrivia
+                    && old.kind == token.kind
+                    && old.payload == token.payload
+                    && old_state == state
+                {
+                    let old_offset = old.start;
+                    let line_shift = token.line as i64 - old.line as i64;
+                    let reused = restart + scanned.len();
+                    tokens.splice(restart..old_index, scanned);
+                    for old in &mut tokens[reused..] {
+                        old.payload.rebase(previous_source, &source, shift);
+                        old.line = (old.line as i64 + line_shift) as u32;
+                        old.start = old.start.saturating_add_signed(shift);
+                        old.end = old.end.saturating_add_signed(shift);
                     }
-                    matched = true;
-                    break;
+                    // The old diagnostics already cover the token just scanned
+                    let mut relexed = std::mem::take(&mut self.scanner.diagnostics);
+                    relexed.truncate(diagnostic_count);
+                    relexed.extend(
+                        diagnostics
+                            .iter()
+                            .filter(|diagnostic| diagnostic.start >= old_offset)
+                            .map(|diagnostic| Diagnostic {
+                                start: diagnostic.start.saturating_add_signed(shift),
+                                end: diagnostic.end.saturating_add_signed(shift),
+                                ..diagnostic.clone()
+                            }),
+                    );
+                    *diagnostics = relexed;
+                    return;
                 }
             }
 
-            if !matched {
-                // Unknown character
-                tokens.push(SpannedToken {
-                    value: Token::Illegal,
-                    line: start_line,
-                    column: start_column,
-                });
-                chars.next();
-                column += 1;
+            state.advance(&token);
+            let is_eof = token.kind == TokenKind::Eof;
+            scanned.push(token);
+            if is_eof {
+                tokens.truncate(restart);
+                tokens.append(&mut scanned);
+                *diagnostics = std::mem::take(&mut self.scanner.diagnostics);
+                return;
             }
         }
+    }
+}
 
-        // Add EOF token
-        tokens.push(SpannedToken {
-            value: Token::Eof,
-            line,
-            column,
-        });
+/// The part of the scanner state that can be rebuilt by replaying tokens,
+/// used by [`Lexer::relex`] to resume scanning in the middle of a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ResumeState {
+    context_stack: Vec<LexContext>,
+    regex_allowed: bool,
+    after_dot: bool,
+    after_semicolon: bool,
+    /// Set from a `<` that may have opened a JSX tag until the next `>`. An
+    /// unterminated tag scans everything up to the end of the file, `;`
+    /// included.
+    in_tag: bool,
+}
 
-        tokens
+impl Default for ResumeState {
+    fn default() -> Self {
+        Self {
+            context_stack: Vec::new(),
+            regex_allowed: true,
+            after_dot: false,
+            after_semicolon: false,
+            in_tag: false,
+        }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::token::{Token, tokens_to_source};
+impl ResumeState {
+    /// Update the state to what the scanner has after producing `token`.
+    /// Brace and template tracking is exact outside JSX.
+    fn advance(&mut self, token: &SpannedToken) {
+        self.advance_nesting(token.kind);
+        if !token.is_trivia() {
+            match token.kind {
+                TokenKind::LessThan if self.regex_allowed => self.in_tag = true,
+                TokenKind::GreaterThan => self.in_tag = false,
+                _ => {}
+            }
+            let text = crate::token::token_fragment(token).unwrap_or_default();
+            self.regex_allowed = regex_allowed_after(&token.token(), &text, self.after_dot);
+            self.after_dot = matches!(token.kind, TokenKind::Dot | TokenKind::QuestionDot);
+            self.after_semicolon = token.kind == TokenKind::Semicolon;
+        }
+    }
 
-    fn render(tokens: &[SpannedToken]) -> String {
-        tokens_to_source(tokens.iter())
+    /// Track the braces and template substitutions a token of `kind` opens
+    /// or closes.
+    fn advance_nesting(&mut self, kind: TokenKind) {
+        match kind {
+            TokenKind::OpenBrace => self.context_stack.push(LexContext::Block),
+            TokenKind::TemplateHead => self.context_stack.push(LexContext::TemplateSubstitution),
+            TokenKind::CloseBrace | TokenKind::TemplateTail => {
+                self.context_stack.pop();
+            }
+            _ => {}
+        }
     }
 
-    fn lex(input: &str) -> Vec<SpannedToken> {
-        Lexer::new(input).lex()
+    /// Whether the replayed state matches the real scanner's. JSX contexts
+    /// cannot be recovered from tokens, so in JSX files this only holds at
+    /// the top level right after a `;` outside any tag, which never occurs
+    /// inside JSX.
+    fn is_resumable(&self, jsx: bool) -> bool {
+        !jsx || (self.after_semicolon && self.context_stack.is_empty() && !self.in_tag)
     }
+}
 
-    #[test]
-    fn lexes_identifier() {
-        let input = "foo";
-        let tokens = lex(input);
+/// A snapshot of everything [`Scanner`] needs to resume from a position,
+/// taken by [`Scanner::checkpoint`] and restored by [`Scanner::rewind`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScannerState {
+    pos: usize,
+    line: u32,
+    column: u32,
+    context_stack: Vec<LexContext>,
+    jsx_element_ended: bool,
+    regex_allowed: bool,
+    after_dot: bool,
+    type_context: TypeContext,
+    diagnostic_count: usize,
+    finished: bool,
+}
 
-        // Verify exact round-trip rendering
-        assert_eq!(render(&tokens), input);
+/// A pull-based, character-oriented scanner over UTF-8 source text.
+/// Position tracking is done in `char`s for `line`/`column` and in bytes
+/// for `start`/`end`.
+///
+/// Tokens are produced one at a time by [`Scanner::next_token`] (or by
+/// iterating, which stops after `Eof`). A parser can speculate with
+/// [`Scanner::look_ahead`] and [`Scanner::try_scan`], and re-scan a token
+/// whose meaning depends on grammar context with the `rescan_*` methods.
+///
+/// Token payloads are [`Text`] slices of the shared source buffer, so
+/// scanning does not copy the source text. The decoded names of identifiers
+/// are interned in the scanner's [`Interner`], which is shared with every
+/// other scanner that should give the same names the same symbols.
+pub struct Scanner {
+    source: Arc<str>,
+    options: LexerOptions,
+    interner: Arc<Interner>,
+    pos: usize,
+    /// Byte offset where the token currently being scanned starts.
+    token_start: usize,
+    line: u32,
+    column: u32,
+    context_stack: Vec<LexContext>,
+    /// Set when a JSX element has just been closed, so that a following `/`
+    /// is treated as division like after any other operand.
+    jsx_element_ended: bool,
+    /// Whether a `/` at the current position starts a regular expression
+    /// rather than a division, judged from the previous significant token.
+    regex_allowed: bool,
+    /// Whether the previous significant token was `.` or `?.`, after which
+    /// a keyword is a property name rather than a keyword.
+    after_dot: bool,
+    type_context: TypeContext,
+    diagnostics: Vec<Diagnostic>,
+    /// Set once `Eof` has been returned, ending iteration.
+    finished: bool,
+}
+
+impl Scanner {
+    /// Create a new scanner from a source string.
+    pub fn new<S: Into<Arc<str>>>(source: S) -> Self {
+        Self::with_options(source, LexerOptions::default())
     }
 
-    #[test]
-    fn lexes_bigint_literal() {
-        let input = "123n";
-        let tokens = lex(input);
+    /// Create a new scanner that reads `source` according to `options`.
+    pub fn with_options<S: Into<Arc<str>>>(source: S, options: LexerOptions) -> Self {
+        Self {
+            source: source.into(),
+            options,
+            interner: Arc::default(),
+            pos: 0,
+            token_start: 0,
+            line: 1,
+            column: 1,
+            context_stack: Vec::new(),
+            jsx_element_ended: false,
+            regex_allowed: true,
+            after_dot: false,
+            type_context: TypeContext::default(),
+            diagnostics: Vec::new(),
+            finished: false,
+        }
+    }
 
-        // Verify exact round-trip rendering
-        assert_eq!(render(&tokens), input);
+    /// Move back to the start of the source and forget all diagnostics.
+    pub fn reset(&mut self) {
+        self.pos = 0;
+        self.line = 1;
+        self.column = 1;
+        self.context_stack.clear();
+        self.jsx_element_ended = false;
+        self.regex_allowed = true;
+        self.after_dot = false;
+        self.type_context = TypeContext::default();
+        self.diagnostics.clear();
+        self.finished = false;
     }
 
-    #[test]
-    fn skips_whitespace_and_tracks_position() {
-        let input = " \nfoo";
-        let tokens = lex(input);
+    pub fn source(&self) -> &str {
+        &self.source
+    }
 
-        // Verify exact round-trip rendering
-        assert_eq!(render(&tokens), input);
+    /// Byte offset of the next character to be scanned.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
 
-        // Verify tokens include whitespace and newline trivia
-        assert_eq!(
-            tokens,
-            [
-                SpannedToken {
-                    value: Token::WhitespaceTrivia(" ".into()),
-                    line: 1,
-                    column: 1
-                },
-                SpannedToken {
-                    value: Token::NewLineTrivia,
-                    line: 1,
-                    column: 2
-                },
-                SpannedToken {
-                    value: Token::Identifier("foo".into()),
-                    line: 2,
-                    column: 1
-                },
-                SpannedToken {
-                    value: Token::Eof,
-                    line: 2,
-                    column: 4
-                },
-            ]
-        );
+    /// Diagnostics reported so far.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
     }
 
-    #[test]
-    fn lexes_equals_token() {
-        let input = r#"var x = "string" + 5;"#;
-        let tokens = lex(input);
+    /// Intern identifier names into `interner` instead of a fresh one, so
+    /// that they get the same symbols as in tokens scanned elsewhere.
+    pub fn with_interner(mut self, interner: Arc<Interner>) -> Self {
+        self.interner = interner;
+        self
+    }
 
-        // Verify exact round-trip rendering
-        assert_eq!(render(&tokens), input);
+    /// The interner holding the names of the identifiers scanned so far.
+    pub fn interner(&self) -> &Arc<Interner> {
+        &self.interner
     }
 
-    #[test]
-    fn lexes_typescript_imports() {
-        let input = r#"
-import foo from "bar";
-import { baz, qux as quux } from "mod";
-import * as ns from "pkg";
-import "side-effect";
-console.log("hello world");
-"#;
-        let tokens = lex(input);
+    pub fn checkpoint(&self) -> ScannerState {
+        ScannerState {
+            pos: self.pos,
+            line: self.line,
+            column: self.column,
+            context_stack: self.context_stack.clone(),
+            jsx_element_ended: self.jsx_element_ended,
+            regex_allowed: self.regex_allowed,
+            after_dot: self.after_dot,
+            type_context: self.type_context.clone(),
+            diagnostic_count: self.diagnostics.len(),
+            finished: self.finished,
+        }
+    }
 
-        // Verify exact round-trip rendering
-        assert_eq!(render(&tokens), input);
+    /// Restore a checkpoint, dropping diagnostics reported since it was taken.
+    pub fn rewind(&mut self, state: ScannerState) {
+        self.pos = state.pos;
+        self.line = state.line;
+        self.column = state.column;
+        self.context_stack = state.context_stack;
+        self.jsx_element_ended = state.jsx_element_ended;
+        self.regex_allowed = state.regex_allowed;
+        self.after_dot = state.after_dot;
+        self.type_context = state.type_context;
+        self.diagnostics.truncate(state.diagnostic_count);
+        self.finished = state.finished;
     }
 
-    #[test]
-    fn test_typescript_with_trivias() {
-        let src = r#"
-// This is a single-line comment
-import foo from "bar"; /* Block comment before import */
+    /// Run `f` and then restore the scanner to where it was, whatever `f`
+    /// consumed. Used to peek at upcoming tokens.
+    pub fn look_ahead<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        let state = self.checkpoint();
+        let result = f(self);
+        self.rewind(state);
+        result
+    }
 
-import {
-    /* multi-line 
-       trivia */ baz, 
-    // oneline
-    qux as quux
-} from "mod";
+    /// Run `f`, keeping what it consumed if it returns `Some` and rolling the
+    /// scanner back if it returns `None`.
+    pub fn try_scan<T>(&mut self, f: impl FnOnce(&mut Self) -> Option<T>) -> Option<T> {
+        let state = self.checkpoint();
+        let result = f(self);
+        if result.is_none() {
+            self.rewind(state);
+        }
+        result
+    }
 
-/*
-    Another block comment 
-*/
-import * as ns from "pkg";
-// Simple import
-import "side-effect";
-// After import
+    /// Scan the next token, trivia included. Returns `Eof` at the end of the
+    /// source, and keeps returning it if called again.
+    pub fn next_token(&mut self) -> SpannedToken {
+        self.scan_token_with(Self::scan)
+    }
 
-// String literal with // inside
-let s = "test // not a comment";
-/* String with /* nested */ content */
+    /// Re-scan a token starting with `>` (`>>`, `>>>`, `>=`, `>>=`, ...) as a
+    /// lone `>`, as needed to close nested type arguments like
+    /// `Array<Array<T>>`. Scanning resumes right after that `>`.
+    pub fn rescan_greater_token(&mut self, token: &SpannedToken) -> SpannedToken {
+        if !self.source[token.start..].starts_with('>') {
+            return token.clone();
+        }
+        self.reset_to(token);
+        self.scan_token_with(|scanner| {
+            scanner.bump();
+            Token::GreaterThan
+        })
+    }
 
-console.log("hello /* not comment */ world"); // Trailing trivia
-"#;
+    /// Re-scan a `/` or `/=` token as the start of a regular expression, for
+    /// when the grammar expects an operand where the scanner guessed division.
+    /// A regex with no closing `/` is reported as unterminated, which only
+    /// the grammar can tell, and runs to the end of the line, as in tsc.
+    pub fn rescan_slash_token(&mut self, token: &SpannedToken) -> SpannedToken {
+        self.reset_to(token);
+        self.scan_token_with(|scanner| {
+            if let Some(regex) = scanner.scan_regex() {
+                return regex;
+            }
+            let start = scanner.pos;
+            scanner.bump(); // consume '/'
+            while scanner.peek().is_some_and(|c| !is_line_terminator(c)) {
+                scanner.bump();
+            }
+            scanner.error(
+                1161,
+                "Unterminated regular expression literal.",
+                start,
+                scanner.pos,
+            );
+            Token::RegularExpressionLiteral {
+                body: scanner.slice(start + 1, scanner.pos),
+                flags: scanner.slice(scanner.pos, scanner.pos),
+            }
+        })
+    }
 
-        // new Lexer, lex
-        let mut lexer = super::Lexer::new(src);
-        let tokens: Vec<SpannedToken> = lexer.lex();
+    /// Re-scan a `}` token as the continuation of a template literal
+    /// (`TemplateMiddle` or `TemplateTail`), for when the parser knows it
+    /// closes a `${` substitution.
+    pub fn rescan_template_token(&mut self, token: &SpannedToken) -> SpannedToken {
+        self.reset_to(token);
+        self.scan_token_with(|scanner| {
+            scanner.bump(); // consume '}'
+            scanner.scan_template(false)
+        })
+    }
 
-        // Verify exact round-trip rendering
-        assert_eq!(render(&tokens), src);
+    /// Re-scan from `token` as JSX children: text up to the next `<` or `{`,
+    /// or the `<`, `</` or `{` itself.
+    pub fn rescan_jsx_token(&mut self, token: &SpannedToken) -> SpannedToken {
+        self.reset_to(token);
+        if self.context_stack.last() != Some(&LexContext::JsxChildren) {
+            self.context_stack.push(LexContext::JsxChildren);
+        }
+        self.scan_token_with(|scanner| match scanner.peek() {
+            Some(_) => scanner.scan_jsx_child(),
+            None => Token::Eof,
+        })
+    }
+
+    /// Re-scan an identifier or keyword token as a JSX name, which may
+    /// continue with `-` (`data-id`).
+    pub fn rescan_jsx_identifier(&mut self, token: &SpannedToken) -> SpannedToken {
+        self.reset_to(token);
+        self.scan_token_with(Self::scan_jsx_identifier)
+    }
+
+    /// Re-scan a string token as a JSX attribute value, which has no escape
+    /// sequences and may span lines.
+    pub fn rescan_jsx_attribute_value(&mut self, token: &SpannedToken) -> SpannedToken {
+        self.reset_to(token);
+        self.scan_token_with(|scanner| match scanner.peek() {
+            Some(quote @ ('"' | '\'')) => scanner.scan_jsx_attribute_string(quote),
+            _ => scanner.scan(),
+        })
+    }
+
+    /// Re-scan `tokens[index]` with `rescan`, one of the `rescan_*` methods,
+    /// and then the tokens after it until scanning is back in step with
+    /// `tokens`, a complete scan of the source. Returns the tokens replacing
+    /// `tokens[index..end]`, and `end`; [`Scanner::diagnostics`] then holds
+    /// the diagnostics for the replaced source.
+    ///
+    /// The braces and template substitutions open at the token are replayed
+    /// from the tokens before it. JSX nesting cannot be, so in JSX files the
+    /// source after a token rescanned inside JSX is scanned as code.
+    pub fn rescan_tokens(
+        &mut self,
+        tokens: &[SpannedToken],
+        index: usize,
+        rescan: impl FnOnce(&mut Self, &SpannedToken) -> SpannedToken,
+    ) -> (Vec<SpannedToken>, usize) {
+        self.reset();
+        let mut old_state = ResumeState::default();
+        for token in &tokens[..index] {
+            old_state.advance_nesting(token.kind);
+        }
+        self.context_stack = old_state.context_stack.clone();
+
+        let mut rescanned = vec![rescan(self, &tokens[index])];
+        let mut end = index;
+        loop {
+            while end < tokens.len() && tokens[end].start < self.pos {
+                old_state.advance(&tokens[end]);
+                end += 1;
+            }
+            if self.finished {
+                return (rescanned, tokens.len());
+            }
+            let in_step = tokens.get(end).is_some_and(|old| old.start == self.pos)
+                && old_state.context_stack == self.context_stack
+                && old_state.regex_allowed == self.regex_allowed;
+            if in_step {
+                return (rescanned, end);
+            }
+            rescanned.push(self.next_token());
+        }
+    }
+
+    /// Continue scanning at `token` in the replayed `state`.
+    fn resume_at(&mut self, token: &SpannedToken, state: &ResumeState) {
+        self.pos = token.start;
+        self.line = token.line;
+        self.column = token.column;
+        self.context_stack = state.context_stack.clone();
+        self.regex_allowed = state.regex_allowed;
+        self.after_dot = state.after_dot;
+        // Scanning only resumes where the type context is back to default
+        self.type_context = TypeContext::default();
+    }
+
+    /// Position the scanner at the start of `token`, forgetting diagnostics
+    /// reported from there on.
+    fn reset_to(&mut self, token: &SpannedToken) {
+        self.pos = token.start;
+        self.line = token.line;
+        self.column = token.column;
+        self.finished = false;
+        self.diagnostics
+            .retain(|diagnostic| diagnostic.start < token.start);
+    }
+
+    fn scan_token_with(&mut self, scan: impl FnOnce(&mut Self) -> Token) -> SpannedToken {
+        let line = self.line;
+        let column = self.column;
+        let start = self.pos;
+        self.token_start = start;
+        let value = scan(self);
+        if !value.is_trivia() {
+            let text = &self.source[start..self.pos];
+            self.regex_allowed =
+                !self.jsx_element_ended && regex_allowed_after(&value, text, self.after_dot);
+            self.after_dot = matches!(value, Token::Dot | Token::QuestionDot);
+            self.jsx_element_ended = false;
+            if self.options.language_variant == LanguageVariant::Jsx {
+                self.type_context
+                    .advance(&value, text, self.context_stack.len());
+            }
+        }
+        if value == Token::Eof {
+            self.finished = true;
+        }
+        SpannedToken {
+            kind: value.kind(),
+            payload: TokenPayload::new(value, &self.interner),
+            line,
+            column,
+            start,
+            end: self.pos,
+        }
+    }
+
+    /// The source text between the byte offsets `start` and `end`.
+    fn slice(&self, start: usize, end: usize) -> Text {
+        Text::slice(&self.source, start, end)
+    }
+
+    fn error(&mut self, code: u32, message: &str, start: usize, end: usize) {
+        self.diagnostics
+            .push(Diagnostic::error(code, message, start, end));
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.pos..].chars().next()
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.source[self.pos..].chars().nth(n)
+    }
+
+    /// Consume one character, keeping `line`/`column` in sync. All four
+    /// line terminators start a new line; the `\r` of `\r\n` does not, so
+    /// the pair counts once.
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        if is_line_terminator(c) && !(c == '\r' && self.peek() == Some('\n')) {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn scan(&mut self) -> Token {
+        let Some(ch) = self.peek() else {
+            return Token::Eof;
+        };
+
+        // Handle a byte order mark and a `#!` interpreter line at the very
+        // start of the file
+        if self.pos == 0 && ch == '\u{FEFF}' {
+            self.bump();
+            return Token::ByteOrderMark;
+        }
+        let file_start = if self.source.starts_with('\u{FEFF}') {
+            '\u{FEFF}'.len_utf8()
+        } else {
+            0
+        };
+        if self.pos == file_start && self.source[self.pos..].starts_with("#!") {
+            return Token::ShebangTrivia(self.scan_to_line_end());
+        }
+
+        // Handle merge conflict markers left behind by git
+        if matches!(ch, '<' | '=' | '>' | '|') && self.is_conflict_marker(self.pos) {
+            return self.scan_conflict_marker(ch);
+        }
+
+        // Inside JSX, text children and tag contents follow their own rules
+        match self.context_stack.last() {
+            Some(LexContext::JsxChildren) => return self.scan_jsx_child(),
+            Some(LexContext::JsxOpeningTag { .. } | LexContext::JsxClosingTag) => {
+                if let Some(token) = self.scan_jsx_tag_token(ch) {
+                    return token;
+                }
+            }
+            _ => {}
+        }
+
+        // Handle whitespace and newlines (emit as trivia)
+        if is_line_terminator(ch) {
+            return Token::NewLineTrivia(self.scan_line_terminator(ch));
+        }
+        if is_whitespace(ch) {
+            // Collect consecutive whitespace (spaces, tabs, etc.)
+            while let Some(c) = self.peek() {
+                if is_line_terminator(c) || !is_whitespace(c) {
+                    break;
+                }
+                self.bump();
+            }
+            return Token::WhitespaceTrivia(self.slice(self.token_start, self.pos));
+        }
+
+        // Handle comments
+        if ch == '/' {
+            let next = self.peek_nth(1);
+            if next == Some('/') {
+                return self.scan_single_line_comment();
+            } else if next == Some('*') {
+                return self.scan_multi_line_comment();
+            } else if self.regex_allowed
+                && let Some(token) = self.scan_regex()
+            {
+                return token;
+            }
+        }
+
+        // Handle string literals
+        if ch == '"' || ch == '\'' {
+            return self.scan_string(ch);
+        }
+
+        // Handle template literals
+        if ch == '`' {
+            self.bump();
+            return self.scan_template(true);
+        }
+
+        // Handle numeric literals (including leading-dot forms like `.5`)
+        if ch.is_ascii_digit()
+            || (ch == '.' && self.peek_nth(1).is_some_and(|c| c.is_ascii_digit()))
+        {
+            return self.scan_number();
+        }
+
+        // Handle identifiers and keywords, which may start with a `\u` escape
+        if is_identifier_start(ch) || (ch == '\\' && self.peek_nth(1) == Some('u')) {
+            return self.scan_identifier();
+        }
+
+        // Handle private identifiers (`#name`)
+        if ch == '#' {
+            return self.scan_private_identifier();
+        }
+
+        // Braces drive template substitution and JSX expression tracking
+        match ch {
+            '{' => {
+                self.bump();
+                self.context_stack.push(LexContext::Block);
+                return Token::OpenBrace;
+            }
+            '}' => {
+                self.bump();
+                if self.context_stack.pop() == Some(LexContext::TemplateSubstitution) {
+                    return self.scan_template(false);
+                }
+                return Token::CloseBrace;
+            }
+            '<' if self.options.language_variant == LanguageVariant::Jsx
+                && self.regex_allowed
+                && self.starts_jsx_element() =>
+            {
+                self.bump();
+                self.context_stack.push(LexContext::JsxOpeningTag {
+                    self_closing: false,
+                });
+                return Token::LessThan;
+            }
+            _ => {}
+        }
+
+        self.scan_punctuation()
+    }
+
+    /// Consume the line terminator `ch`, taking `\r\n` as one.
+    fn scan_line_terminator(&mut self, ch: char) -> LineTerminator {
+        self.bump();
+        match ch {
+            '\r' if self.peek() == Some('\n') => {
+                self.bump();
+                LineTerminator::CrLf
+            }
+            '\r' => LineTerminator::Cr,
+            '\u{2028}' => LineTerminator::LineSeparator,
+            '\u{2029}' => LineTerminator::ParagraphSeparator,
+            _ => LineTerminator::Lf,
+        }
+    }
+
+    /// Consume everything up to (not including) the next line break.
+    fn scan_to_line_end(&mut self) -> Text {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if is_line_terminator(c) {
+                break;
+            }
+            self.bump();
+        }
+        self.slice(start, self.pos)
+    }
+
+    /// Whether a seven-character merge conflict marker (`<<<<<<<`, `|||||||`,
+    /// `=======` or `>>>>>>>`) starts at the beginning of a line at `offset`.
+    fn is_conflict_marker(&self, offset: usize) -> bool {
+        const MARKER_LENGTH: usize = 7;
+
+        let bytes = self.source.as_bytes();
+        let at_line_start = self.source[..offset]
+            .chars()
+            .next_back()
+            .is_none_or(is_line_terminator);
+        let Some(marker) = bytes.get(offset..offset + MARKER_LENGTH) else {
+            return false;
+        };
+        if !at_line_start || !marker.iter().all(|&b| b == marker[0]) {
+            return false;
+        }
+
+        match marker[0] {
+            b'=' => true,
+            b'<' | b'>' | b'|' => bytes.get(offset + MARKER_LENGTH) == Some(&b' '),
+            _ => false,
+        }
+    }
+
+    /// Scan a merge conflict marker. `<<<<<<<` and `>>>>>>>` markers run to the
+    /// end of their line; `|||||||` and `=======` swallow the other side of
+    /// the conflict up to the next marker, as tsc does.
+    fn scan_conflict_marker(&mut self, marker: char) -> Token {
+        self.error(
+            1185,
+            "Merge conflict marker encountered.",
+            self.token_start,
+            self.token_start + 7,
+        );
+
+        if marker == '<' || marker == '>' {
+            return Token::ConflictMarkerTrivia(self.scan_to_line_end());
+        }
+
+        while let Some(c) = self.peek() {
+            if (c == '=' || c == '>') && c != marker && self.is_conflict_marker(self.pos) {
+                break;
+            }
+            self.bump();
+        }
+        Token::ConflictMarkerTrivia(self.slice(self.token_start, self.pos))
+    }
+
+    fn scan_single_line_comment(&mut self) -> Token {
+        // Single-line comment - store full comment including "//" marker
+        self.bump(); // consume first '/'
+        self.bump(); // consume second '/'
+        while let Some(c) = self.peek() {
+            if is_line_terminator(c) {
+                break;
+            }
+            self.bump();
+        }
+        Token::SingleLineCommentTrivia(self.slice(self.token_start, self.pos))
+    }
+
+    fn scan_multi_line_comment(&mut self) -> Token {
+        // Multi-line comment - store full comment including "/* */" markers
+        self.bump(); // consume '/'
+        self.bump(); // consume '*'
+        let mut depth = 1;
+        let mut terminated = false;
+        while let Some(c) = self.peek() {
+            if c == '*' && self.peek_nth(1) == Some('/') {
+                self.bump(); // consume '*'
+                self.bump(); // consume '/'
+                depth -= 1;
+                if depth == 0 {
+                    terminated = true;
+                    break;
+                }
+            } else if self.options.nested_comments && c == '/' && self.peek_nth(1) == Some('*') {
+                self.bump(); // consume '/'
+                self.bump(); // consume '*'
+                depth += 1;
+            } else {
+                self.bump();
+            }
+        }
+        if !terminated {
+            self.error(1010, "'*/' expected.", self.pos, self.pos);
+        }
+        Token::MultiLineCommentTrivia(self.slice(self.token_start, self.pos))
+    }
+
+    /// Scan a string literal. The token keeps both the raw source text,
+    /// quotes included, and the cooked value with escapes decoded. The value
+    /// is only built up separately once an escape is seen; until then it is
+    /// a slice of the source like the raw text.
+    fn scan_string(&mut self, quote: char) -> Token {
+        self.bump();
+        let content_start = self.pos;
+        let mut cooked: Option<String> = None;
+
+        while let Some(c) = self.peek() {
+            if c == quote {
+                let content_end = self.pos;
+                self.bump();
+                return self.string_token(content_start, content_end, cooked);
+            } else if c == '\\' {
+                let mut value = cooked
+                    .take()
+                    .unwrap_or_else(|| self.source[content_start..self.pos].to_string());
+                self.scan_escape_sequence(&mut value);
+                cooked = Some(value);
+            } else if c == '\n' || c == '\r' {
+                break;
+            } else {
+                if let Some(value) = cooked.as_mut() {
+                    value.push(c);
+                }
+                self.bump();
+            }
+        }
+
+        self.error(
+            1002,
+            "Unterminated string literal.",
+            self.token_start,
+            self.pos,
+        );
+        self.string_token(content_start, self.pos, cooked)
+    }
+
+    fn string_token(
+        &self,
+        content_start: usize,
+        content_end: usize,
+        cooked: Option<String>,
+    ) -> Token {
+        Token::StringLiteral {
+            raw: self.slice(self.token_start, self.pos),
+            value: match cooked {
+                Some(value) => Text::from(value),
+                None => self.slice(content_start, content_end),
+            },
+        }
+    }
+
+    /// Decode the escape sequence starting at the current `\` into `out`,
+    /// reporting malformed and disallowed escapes. Lone surrogates, which a
+    /// Rust string cannot hold, decode to U+FFFD.
+    fn scan_escape_sequence(&mut self, out: &mut String) {
+        let start = self.pos;
+        if self.peek_nth(1) == Some('u') {
+            let Some(value) = self.scan_unicode_escape() else {
+                return;
+            };
+            // A surrogate pair spelled as two `\uXXXX` escapes is one code point
+            if (0xD800..0xDC00).contains(&value)
+                && let Some(low) = self.source[self.pos..]
+                    .strip_prefix("\\u")
+                    .and_then(|rest| rest.get(..4))
+                    .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+                    .filter(|low| (0xDC00..0xE000).contains(low))
+            {
+                for _ in 0..6 {
+                    self.bump();
+                }
+                let combined = 0x10000 + ((value - 0xD800) << 10) + (low - 0xDC00);
+                out.push(char::from_u32(combined).unwrap_or(char::REPLACEMENT_CHARACTER));
+                return;
+            }
+            out.push(char::from_u32(value).unwrap_or(char::REPLACEMENT_CHARACTER));
+            return;
+        }
+
+        self.bump(); // consume '\\'
+        let Some(c) = self.bump() else {
+            return;
+        };
+        match c {
+            'b' => out.push('\u{8}'),
+            'f' => out.push('\u{C}'),
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            't' => out.push('\t'),
+            'v' => out.push('\u{B}'),
+            '0' if !self.peek().is_some_and(|c| c.is_ascii_digit()) => out.push('\0'),
+            '0'..='7' => {
+                // Legacy octal escape: up to three digits, at most \377
+                let max_digits = if c <= '3' { 3 } else { 2 };
+                let mut value = c.to_digit(8).unwrap_or_default();
+                for _ in 1..max_digits {
+                    match self.peek().and_then(|d| d.to_digit(8)) {
+                        Some(digit) => {
+                            value = value * 8 + digit;
+                            self.bump();
+                        }
+                        None => break,
+                    }
+                }
+                self.error(
+                    1487,
+                    &format!(
+                        "Octal escape sequences are not allowed. Use the syntax '\\x{value:02x}'."
+                    ),
+                    start,
+                    self.pos,
+                );
+                out.push(char::from_u32(value).unwrap_or_default());
+            }
+            '8' | '9' => {
+                self.error(
+                    1488,
+                    &format!("Escape sequence '\\{c}' is not allowed."),
+                    start,
+                    self.pos,
+                );
+                out.push(c);
+            }
+            'x' => {
+                let digits: String = self.source[self.pos..].chars().take(2).collect();
+                match u32::from_str_radix(&digits, 16) {
+                    Ok(value)
+                        if digits.len() == 2 && digits.chars().all(|d| d.is_ascii_hexdigit()) =>
+                    {
+                        self.bump();
+                        self.bump();
+                        out.push(char::from_u32(value).unwrap_or_default());
+                    }
+                    _ => self.error(1125, "Hexadecimal digit expected.", self.pos, self.pos),
+                }
+            }
+            // Line continuations contribute nothing to the value
+            '\r' => {
+                if self.peek() == Some('\n') {
+                    self.bump();
+                }
+            }
+            '\n' | '\u{2028}' | '\u{2029}' => {}
+            _ => out.push(c),
+        }
+    }
+
+    /// Scan the rest of a template piece. `from_backtick` is true when the
+    /// opening backtick has just been consumed, false when resuming after the
+    /// `}` that closes a substitution. The token payload is the raw text
+    /// between the delimiters, escapes included.
+    fn scan_template(&mut self, from_backtick: bool) -> Token {
+        let start = self.pos;
+
+        while let Some(c) = self.peek() {
+            if c == '`' {
+                let text = self.slice(start, self.pos);
+                self.bump();
+                return if from_backtick {
+                    Token::NoSubstitutionTemplateLiteral(text)
+                } else {
+                    Token::TemplateTail(text)
+                };
+            }
+            if c == '$' && self.peek_nth(1) == Some('{') {
+                let text = self.slice(start, self.pos);
+                self.bump(); // consume '$'
+                self.bump(); // consume '{'
+                self.context_stack.push(LexContext::TemplateSubstitution);
+                return if from_backtick {
+                    Token::TemplateHead(text)
+                } else {
+                    Token::TemplateMiddle(text)
+                };
+            }
+            if c == '\\' {
+                self.bump();
+            }
+            self.bump();
+        }
+
+        // Unterminated template: treat end of input as the closing backtick
+        self.error(
+            1160,
+            "Unterminated template literal.",
+            self.token_start,
+            self.pos,
+        );
+        let text = self.slice(start, self.pos);
+        if from_backtick {
+            Token::NoSubstitutionTemplateLiteral(text)
+        } else {
+            Token::TemplateTail(text)
+        }
+    }
+
+    /// Try to scan a regular expression literal starting at the current `/`.
+    /// Returns `None` without consuming or reporting anything when no closing
+    /// `/` is found on the same line, so the caller falls back to division.
+    fn scan_regex(&mut self) -> Option<Token> {
+        let rest = &self.source[self.pos + 1..];
+        let mut in_class = false;
+        let mut escaped = false;
+        let mut body_len = None;
+        for (offset, c) in rest.char_indices() {
+            if is_line_terminator(c) {
+                break;
+            }
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '[' {
+                in_class = true;
+            } else if c == ']' {
+                in_class = false;
+            } else if c == '/' && !in_class {
+                body_len = Some(offset);
+                break;
+            }
+        }
+
+        let body_len = body_len?;
+        let body = self.slice(self.pos + 1, self.pos + 1 + body_len);
+        self.bump(); // consume opening '/'
+        for _ in body.chars() {
+            self.bump();
+        }
+        self.bump(); // consume closing '/'
+
+        let flags_start = self.pos;
+        let mut seen = String::new();
+        while let Some(c) = self.peek() {
+            if !is_identifier_part(c) {
+                break;
+            }
+            let start = self.pos;
+            self.bump();
+            if !REGEX_FLAGS.contains(c) {
+                self.error(1499, "Unknown regular expression flag.", start, self.pos);
+            } else if seen.contains(c) {
+                self.error(1500, "Duplicate regular expression flag.", start, self.pos);
+            } else {
+                if (c == 'u' && seen.contains('v')) || (c == 'v' && seen.contains('u')) {
+                    self.error(
+                        1502,
+                        "The Unicode (u) flag and the Unicode Sets (v) flag cannot be set simultaneously.",
+                        start,
+                        self.pos,
+                    );
+                }
+                seen.push(c);
+            }
+        }
+        let flags = self.slice(flags_start, self.pos);
+
+        Some(Token::RegularExpressionLiteral { body, flags })
+    }
+
+    fn scan_number(&mut self) -> Token {
+        let start = self.pos;
+
+        // Hex, octal and binary literals: `0x1F`, `0o17`, `0b1010`
+        if self.peek() == Some('0')
+            && let Some(prefix) = self.peek_nth(1)
+            && let Some((radix, message, code)) = match prefix {
+                'x' | 'X' => Some((16, "Hexadecimal digit expected.", 1125)),
+                'o' | 'O' => Some((8, "Octal digit expected.", 1178)),
+                'b' | 'B' => Some((2, "Binary digit expected.", 1177)),
+                _ => None,
+            }
+        {
+            self.bump();
+            self.bump();
+            if self.scan_digits(|c| c.is_digit(radix)) == 0 {
+                self.error(code, message, self.pos, self.pos);
+            }
+            return self.finish_number(start, true);
+        }
+
+        // Legacy octal literals (`017`) take no separators, fraction or exponent
+        if self.peek() == Some('0') && self.peek_nth(1).is_some_and(|c| c.is_ascii_digit()) {
+            let rest = &self.source[self.pos + 1..];
+            let digits_end = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            if rest[..digits_end].chars().all(|c| c.is_digit(8)) {
+                for _ in 0..=digits_end {
+                    self.bump();
+                }
+                return self.finish_number(start, true);
+            }
+        }
+
+        // `0_1`: a separator may not follow a leading zero
+        if self.peek() == Some('0') && self.peek_nth(1) == Some('_') {
+            self.error(
+                6188,
+                "Numeric separators are not allowed here.",
+                self.pos + 1,
+                self.pos + 2,
+            );
+        }
+
+        let mut is_integer = true;
+        self.scan_digits(|c| c.is_ascii_digit());
+        if self.peek() == Some('.') {
+            is_integer = false;
+            self.bump();
+            self.scan_digits(|c| c.is_ascii_digit());
+        }
+        if let Some('e' | 'E') = self.peek() {
+            is_integer = false;
+            self.bump();
+            if let Some('+' | '-') = self.peek() {
+                self.bump();
+            }
+            if self.scan_digits(|c| c.is_ascii_digit()) == 0 {
+                self.error(1124, "Digit expected.", self.pos, self.pos);
+            }
+        }
+
+        self.finish_number(start, is_integer)
+    }
+
+    /// Scan a run of digits accepted by `is_digit`, allowing single `_`
+    /// separators between them. Returns the number of digits consumed.
+    fn scan_digits(&mut self, is_digit: impl Fn(char) -> bool) -> usize {
+        let mut digits = 0;
+        let mut previous_was_separator = false;
+        while let Some(c) = self.peek() {
+            if c == '_' {
+                let at = self.pos;
+                if previous_was_separator {
+                    self.error(
+                        6189,
+                        "Multiple consecutive numeric separators are not permitted.",
+                        at,
+                        at + 1,
+                    );
+                } else if digits == 0 {
+                    self.error(6188, "Numeric separators are not allowed here.", at, at + 1);
+                }
+                previous_was_separator = true;
+            } else if is_digit(c) {
+                digits += 1;
+                previous_was_separator = false;
+            } else {
+                break;
+            }
+            self.bump();
+        }
+        if previous_was_separator {
+            self.error(
+                6188,
+                "Numeric separators are not allowed here.",
+                self.pos - 1,
+                self.pos,
+            );
+        }
+        digits
+    }
+
+    /// Handle an optional BigInt `n` suffix and check that no identifier
+    /// directly follows the literal.
+    fn finish_number(&mut self, start: usize, is_integer: bool) -> Token {
+        let digits = self.slice(start, self.pos);
+        let token = if self.peek() == Some('n') {
+            self.bump();
+            if !is_integer {
+                self.error(
+                    1353,
+                    "A bigint literal must be an integer.",
+                    start,
+                    self.pos,
+                );
+            } else if let Some(decimal) = digits.strip_prefix('0')
+                && decimal.starts_with(|c: char| c.is_ascii_digit())
+            {
+                // Legacy octal (`017`) and leading-zero decimal (`08`)
+                // forms have no BigInt counterpart
+                if decimal.chars().all(|c| c.is_digit(8)) {
+                    let message =
+                        format!("Octal literals are not allowed. Use the syntax '0o{decimal}n'.");
+                    self.error(1121, &message, start, self.pos);
+                } else {
+                    self.error(
+                        1489,
+                        "Decimals with leading zeros are not allowed.",
+                        start,
+                        self.pos,
+                    );
+                }
+            }
+            Token::BigIntLiteral(digits)
+        } else {
+            Token::NumericLiteral(digits)
+        };
+
+        if self.peek().is_some_and(is_identifier_start) {
+            self.error(
+                1351,
+                "An identifier or keyword cannot immediately follow a numeric literal.",
+                self.pos,
+                self.pos + 1,
+            );
+        }
+
+        token
+    }
+
+    /// Scan an identifier or keyword. The token keeps the raw spelling; unicode
+    /// escapes are decoded only to validate them and to recognise escaped
+    /// keywords, which are reported and lexed as identifiers.
+    fn scan_identifier(&mut self) -> Token {
+        let start = self.pos;
+        let has_escape = self.scan_identifier_name();
+
+        let raw = &self.source[start..self.pos];
+        if has_escape {
+            let token = Token::Identifier(self.slice(start, self.pos));
+            let is_keyword = token
+                .identifier_name()
+                .is_some_and(|name| crate::token::keyword_from_str(&name).is_some());
+            if is_keyword {
+                self.error(
+                    1260,
+                    "Keywords cannot contain escape characters.",
+                    self.token_start,
+                    self.pos,
+                );
+            }
+            return token;
+        }
+
+        // Check if it's a keyword
+        crate::token::keyword_from_str(raw)
+            .unwrap_or_else(|| Token::Identifier(self.slice(start, self.pos)))
+    }
+
+    /// Scan the characters and unicode escapes of an identifier name,
+    /// reporting escapes that are malformed or denote characters not allowed
+    /// in identifiers. Returns whether the name contains an escape.
+    fn scan_identifier_name(&mut self) -> bool {
+        let start = self.pos;
+        let mut has_escape = false;
+        loop {
+            match self.peek() {
+                Some('\\') if self.peek_nth(1) == Some('u') => {
+                    let escape_start = self.pos;
+                    has_escape = true;
+                    let valid = match self.scan_unicode_escape().map(char::from_u32) {
+                        Some(Some(c)) if escape_start == start => is_identifier_start(c),
+                        Some(Some(c)) => is_identifier_part(c),
+                        Some(None) => false,
+                        None => true,
+                    };
+                    if !valid {
+                        self.error(1127, "Invalid character.", escape_start, self.pos);
+                    }
+                }
+                Some(c) if is_identifier_part(c) => {
+                    self.bump();
+                }
+                _ => break,
+            }
+        }
+        has_escape
+    }
+
+    /// Whether the `<` at the current position, in expression position, opens
+    /// a JSX element or fragment rather than a generic arrow function such as
+    /// `<T,>() => x` or `<T extends U>() => x`.
+    fn starts_jsx_element(&self) -> bool {
+        let angle_position = self.type_context.angle_position;
+        if angle_position == AnglePosition::Type {
+            return false;
+        }
+        let rest = &self.source[self.pos + 1..];
+        if rest.starts_with('>') {
+            return true;
+        }
+        if !rest.starts_with(is_identifier_start) {
+            return false;
+        }
+
+        let name_end = rest
+            .find(|c: char| !is_identifier_part(c))
+            .unwrap_or(rest.len());
+        let after_name = rest[name_end..].trim_start();
+        let is_type_parameter = after_name.starts_with(',')
+            || after_name
+                .strip_prefix("extends")
+                .is_some_and(|after| after.starts_with(char::is_whitespace))
+            || (angle_position == AnglePosition::AfterColon
+                && after_name
+                    .strip_prefix('>')
+                    .is_some_and(|after| after.trim_start().starts_with('(')));
+        !is_type_parameter
+    }
+
+    /// Scan one token among JSX children: text, the `<` of a child element,
+    /// the `</` of the closing tag, or the `{` of an expression container.
+    fn scan_jsx_child(&mut self) -> Token {
+        match self.peek() {
+            Some('<') => {
+                self.bump();
+                if self.peek() == Some('/') {
+                    self.bump();
+                    self.context_stack.push(LexContext::JsxClosingTag);
+                    return Token::LessThanSlash;
+                }
+                self.context_stack.push(LexContext::JsxOpeningTag {
+                    self_closing: false,
+                });
+                Token::LessThan
+            }
+            Some('{') => {
+                self.bump();
+                self.context_stack.push(LexContext::JsxExpression);
+                Token::OpenBrace
+            }
+            _ => self.scan_jsx_text(),
+        }
+    }
+
+    /// Scan JSX text up to the next `<` or `{`. Text that is only whitespace
+    /// is reported as `JsxTextAllWhiteSpaces`. Entities are left encoded; see
+    /// [`decode_entities`].
+    fn scan_jsx_text(&mut self) -> Token {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            match c {
+                '<' | '{' => break,
+                '>' => self.error(
+                    1382,
+                    "Unexpected token. Did you mean `{'>'}` or `&gt;`?",
+                    self.pos,
+                    self.pos + 1,
+                ),
+                '}' => self.error(
+                    1381,
+                    "Unexpected token. Did you mean `{'}'}` or `&rbrace;`?",
+                    self.pos,
+                    self.pos + 1,
+                ),
+                _ => {}
+            }
+            self.bump();
+        }
+
+        let text = self.slice(start, self.pos);
+        if text.chars().all(char::is_whitespace) {
+            Token::JsxTextAllWhiteSpaces(text)
+        } else {
+            Token::JsxText(text)
+        }
+    }
+
+    /// Scan the tokens that are special inside a JSX tag: dashed names,
+    /// attribute strings, `{`, and the `/>` or `>` that ends the tag. Returns
+    /// `None` for anything scanned the same way as in ordinary code, such as
+    /// trivia, `=`, `.` and `:`.
+    fn scan_jsx_tag_token(&mut self, ch: char) -> Option<Token> {
+        let token = match ch {
+            '"' | '\'' => self.scan_jsx_attribute_string(ch),
+            '{' => {
+                self.bump();
+                self.context_stack.push(LexContext::JsxExpression);
+                Token::OpenBrace
+            }
+            '/' if self.peek_nth(1) == Some('>') => {
+                self.bump();
+                if let Some(LexContext::JsxOpeningTag { self_closing }) =
+                    self.context_stack.last_mut()
+                {
+                    *self_closing = true;
+                }
+                Token::Slash
+            }
+            '>' => {
+                self.bump();
+                match self.context_stack.pop() {
+                    Some(LexContext::JsxOpeningTag {
+                        self_closing: false,
+                    }) => self.context_stack.push(LexContext::JsxChildren),
+                    Some(LexContext::JsxClosingTag) => {
+                        if self.context_stack.last() == Some(&LexContext::JsxChildren) {
+                            self.context_stack.pop();
+                        }
+                        self.jsx_element_ended = true;
+                    }
+                    _ => self.jsx_element_ended = true,
+                }
+                Token::GreaterThan
+            }
+            c if is_identifier_start(c) || (c == '\\' && self.peek_nth(1) == Some('u')) => {
+                self.scan_jsx_identifier()
+            }
+            _ => return None,
+        };
+
+        Some(token)
+    }
+
+    /// Scan a JSX tag or attribute name, which may contain `-` after its
+    /// first character (`data-id`, `aria-label`). Keywords are plain names
+    /// here, even when spelled with escapes.
+    fn scan_jsx_identifier(&mut self) -> Token {
+        let start = self.pos;
+        self.scan_identifier_name();
+        while self.peek() == Some('-') {
+            self.bump();
+            self.scan_identifier_name();
+        }
+        Token::Identifier(self.slice(start, self.pos))
+    }
+
+    /// Scan a JSX attribute string. Unlike JavaScript strings these have no
+    /// escape sequences and may span lines; the cooked value has HTML
+    /// entities decoded.
+    fn scan_jsx_attribute_string(&mut self, quote: char) -> Token {
+        self.bump();
+        let content_start = self.pos;
+        while let Some(c) = self.peek() {
+            if c == quote {
+                let content_end = self.pos;
+                self.bump();
+                return self.jsx_attribute_string_token(content_start, content_end);
+            }
+            self.bump();
+        }
+
+        self.error(
+            1002,
+            "Unterminated string literal.",
+            self.token_start,
+            self.pos,
+        );
+        self.jsx_attribute_string_token(content_start, self.pos)
+    }
+
+    fn jsx_attribute_string_token(&self, content_start: usize, content_end: usize) -> Token {
+        let cooked = match decode_entities(&self.source[content_start..content_end]) {
+            Cow::Borrowed(_) => None,
+            Cow::Owned(value) => Some(value),
+        };
+        self.string_token(content_start, content_end, cooked)
+    }
+
+    /// Scan a `#` and the identifier after it as a single private identifier.
+    /// A `#` that does not start a name is reported and lexed as `Hash`.
+    fn scan_private_identifier(&mut self) -> Token {
+        self.bump(); // consume '#'
+        if self.peek() == Some('!') {
+            self.error(
+                18026,
+                "'#!' can only be used at the start of a file.",
+                self.token_start,
+                self.pos + 1,
+            );
+            return Token::Hash;
+        }
+        let starts_name = match self.peek() {
+            Some('\\') => self.peek_nth(1) == Some('u'),
+            Some(c) => is_identifier_start(c),
+            None => false,
+        };
+        if !starts_name {
+            self.error(1127, "Invalid character.", self.token_start, self.pos);
+            return Token::Hash;
+        }
+
+        // Private names may spell keywords, escaped or not: `#if`, `#\u0069f`
+        let name_start = self.pos;
+        self.scan_identifier_name();
+        Token::PrivateIdentifier(self.slice(name_start, self.pos))
+    }
+
+    /// Scan a `\uXXXX` or `\u{X...}` escape starting at the backslash and
+    /// return the code point it denotes, reporting malformed escapes. The
+    /// value may be a surrogate, which callers must handle.
+    fn scan_unicode_escape(&mut self) -> Option<u32> {
+        let start = self.pos;
+        self.bump(); // consume '\\'
+        self.bump(); // consume 'u'
+
+        let mut digits = String::new();
+        if self.peek() == Some('{') {
+            self.bump();
+            while let Some(c) = self.peek().filter(|c| c.is_ascii_hexdigit()) {
+                digits.push(c);
+                self.bump();
+            }
+            if digits.is_empty() {
+                self.error(1125, "Hexadecimal digit expected.", self.pos, self.pos);
+                return None;
+            }
+            if self.peek() != Some('}') {
+                self.error(
+                    1199,
+                    "Unterminated Unicode escape sequence.",
+                    start,
+                    self.pos,
+                );
+                return None;
+            }
+            self.bump();
+        } else {
+            while digits.len() < 4
+                && let Some(c) = self.peek().filter(|c| c.is_ascii_hexdigit())
+            {
+                digits.push(c);
+                self.bump();
+            }
+            if digits.len() < 4 {
+                self.error(1125, "Hexadecimal digit expected.", self.pos, self.pos);
+                return None;
+            }
+        }
+
+        let value = u32::from_str_radix(&digits, 16).unwrap_or(u32::MAX);
+        if value > 0x10FFFF {
+            self.error(
+                1198,
+                "An extended Unicode escape value must be between 0x0 and 0x10FFFF inclusive.",
+                start,
+                self.pos,
+            );
+            return None;
+        }
+        Some(value)
+    }
+
+    /// Scan the longest punctuator at the current position, using the
+    /// longest-first [`PUNCTUATORS`](crate::token::PUNCTUATORS) table.
+    fn scan_punctuation(&mut self) -> Token {
+        let rest = &self.source[self.pos..];
+        let matched = crate::token::PUNCTUATORS
+            .iter()
+            .find(|(text, _)| rest.starts_with(text))
+            .map(|(text, token)| (text.len(), token.clone()));
+
+        if let Some((mut len, mut token)) = matched {
+            // `a?.5:b` is a conditional with `.5`, not optional chaining
+            if token == Token::QuestionDot && rest[2..].starts_with(|c: char| c.is_ascii_digit()) {
+                token = Token::Question;
+                len = 1;
+            }
+            // Punctuators are ASCII, so their byte length is their char count
+            for _ in 0..len {
+                self.bump();
+            }
+            return token;
+        }
+
+        // Unknown character
+        self.bump();
+        self.error(1127, "Invalid character.", self.token_start, self.pos);
+        Token::Illegal(self.slice(self.token_start, self.pos))
+    }
+}
+
+impl Iterator for Scanner {
+    type Item = SpannedToken;
+
+    /// Yield tokens up to and including `Eof`, then stop.
+    fn next(&mut self) -> Option<SpannedToken> {
+        if self.finished {
+            return None;
+        }
+        Some(self.next_token())
+    }
+}
+
+/// Whether `c` is ECMAScript whitespace: Unicode white space plus U+FEFF
+/// ZERO WIDTH NO-BREAK SPACE. Line terminators are checked first.
+fn is_whitespace(c: char) -> bool {
+    c.is_whitespace() || c == '\u{FEFF}'
+}
+
+/// Decide whether a `/` following `token` (spelled `text` in the source)
+/// begins a regular expression. After anything that can end an expression —
+/// identifiers, literals, closing brackets, postfix operators, most keywords
+/// and any word after a `.` or `?.` (`after_dot`) — it is a division; after
+/// other punctuators and operand-expecting keywords it is a regex.
+fn regex_allowed_after(token: &Token, text: &str, after_dot: bool) -> bool {
+    let is_word = text
+        .chars()
+        .next()
+        .is_some_and(|c| is_identifier_part(c) || c == '\\');
+    if is_word {
+        return !after_dot
+            && matches!(
+                token,
+                Token::Await
+                    | Token::Case
+                    | Token::Delete
+                    | Token::Do
+                    | Token::Else
+                    | Token::In
+                    | Token::InstanceOf
+                    | Token::New
+                    | Token::Return
+                    | Token::Throw
+                    | Token::TypeOf
+                    | Token::Void
+                    | Token::Yield
+            );
+    }
+
+    !matches!(
+        token,
+        Token::CloseParen
+            | Token::CloseBracket
+            | Token::CloseBrace
+            | Token::PlusPlus
+            | Token::MinusMinus
+            | Token::PrivateIdentifier(_)
+            | Token::NumericLiteral(_)
+            | Token::BigIntLiteral(_)
+            | Token::StringLiteral { .. }
+            | Token::RegularExpressionLiteral { .. }
+            | Token::NoSubstitutionTemplateLiteral(_)
+            | Token::TemplateTail(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use super::*;
+    use crate::token::{Token, tokens_to_source, tokens_to_source_exact};
+
+    fn render(tokens: &[SpannedToken]) -> String {
+        tokens_to_source_exact(tokens.iter())
+    }
+
+    fn lex(input: &str) -> Vec<SpannedToken> {
+        Lexer::new(input).lex()
+    }
+
+    #[test]
+    fn lexes_identifier() {
+        let input = "foo";
+        let tokens = lex(input);
+
+        // Verify exact round-trip rendering
+        assert_eq!(render(&tokens), input);
+    }
+
+    #[test]
+    fn lexes_bigint_literal() {
+        let input = "123n";
+        let tokens = lex(input);
+
+        // Verify exact round-trip rendering
+        assert_eq!(render(&tokens), input);
+    }
+
+    #[test]
+    fn skips_whitespace_and_tracks_position() {
+        let input = " \nfoo";
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.lex();
+
+        // Verify exact round-trip rendering
+        assert_eq!(render(&tokens), input);
+
+        // Verify tokens include whitespace and newline trivia
+        assert_eq!(
+            tokens,
+            [
+                SpannedToken {
+                    kind: TokenKind::WhitespaceTrivia,
+                    payload: TokenPayload::Text(" ".into()),
+                    line: 1,
+                    column: 1,
+                    start: 0,
+                    end: 1
+                },
+                SpannedToken {
+                    kind: TokenKind::NewLineTrivia,
+                    payload: TokenPayload::NewLine(LineTerminator::Lf),
+                    line: 1,
+                    column: 2,
+                    start: 1,
+                    end: 2
+                },
+                SpannedToken {
+                    kind: TokenKind::Identifier,
+                    payload: TokenPayload::Symbol {
+                        raw: "foo".into(),
+                        symbol: lexer.interner().get("foo").unwrap(),
+                    },
+                    line: 2,
+                    column: 1,
+                    start: 2,
+                    end: 5
+                },
+                SpannedToken {
+                    kind: TokenKind::Eof,
+                    payload: TokenPayload::None,
+                    line: 2,
+                    column: 4,
+                    start: 5,
+                    end: 5
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn lexes_equals_token() {
+        let input = r#"var x = "string" + 5;"#;
+        let tokens = lex(input);
+
+        // Verify exact round-trip rendering
+        assert_eq!(render(&tokens), input);
+    }
+
+    #[test]
+    fn lexes_typescript_imports() {
+        let input = r#"
+import foo from "bar";
+import { baz, qux as quux } from "mod";
+import * as ns from "pkg";
+import "side-effect";
+console.log("hello world");
+"#;
+        let tokens = lex(input);
+
+        // Verify exact round-trip rendering
+        assert_eq!(render(&tokens), input);
+    }
+
+    #[test]
+    fn test_typescript_with_trivias() {
+        let src = r#"
+// This is a single-line comment
+import foo from "bar"; /* Block comment before import */
+
+import {
+    /* multi-line 
+       trivia */ baz, 
+    // oneline
+    qux as quux
+} from "mod";
+
+/*
+    Another block comment 
+*/
+import * as ns from "pkg";
+// Simple import
+import "side-effect";
+// After import
+
+// String literal with // inside
+let s = "test // not a comment";
+/* String with /* nested */ content */
+
+console.log("hello /* not comment */ world"); // Trailing trivia
+"#;
+
+        // new Lexer, lex
+        let mut lexer = super::Lexer::new(src);
+        let tokens: Vec<SpannedToken> = lexer.lex();
+
+        // Verify exact round-trip rendering
+        assert_eq!(render(&tokens), src);
+    }
+
+    fn values(tokens: &[SpannedToken]) -> Vec<Token> {
+        tokens.iter().map(|token| token.token()).collect()
+    }
+
+    #[test]
+    fn lexes_no_substitution_template() {
+        let input = r"`hello \` world`";
+        let tokens = lex(input);
+
+        assert_eq!(render(&tokens), input);
+        assert_eq!(
+            values(&tokens),
+            [
+                Token::NoSubstitutionTemplateLiteral(r"hello \` world".into()),
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn lexes_template_with_substitutions() {
+        let input = "`a${b}c${ { d: 1 }.d }e`";
+        let tokens = lex(input);
+
+        assert_eq!(render(&tokens), input);
+        let significant: Vec<Token> = values(&tokens)
+            .into_iter()
+            .filter(|token| !matches!(token, Token::WhitespaceTrivia(_)))
+            .collect();
+        assert_eq!(
+            significant,
+            [
+                Token::TemplateHead("a".into()),
+                Token::Identifier("b".into()),
+                Token::TemplateMiddle("c".into()),
+                Token::OpenBrace,
+                Token::Identifier("d".into()),
+                Token::Colon,
+                Token::NumericLiteral("1".into()),
+                Token::CloseBrace,
+                Token::Dot,
+                Token::Identifier("d".into()),
+                Token::TemplateTail("e".into()),
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn lexes_nested_templates_with_positions() {
+        let input = "x = `a${`b${c}`}\nd`;";
+        let tokens = lex(input);
+
+        assert_eq!(render(&tokens), input);
+        let pieces: Vec<(Token, u32, u32)> = tokens
+            .iter()
+            .filter(|token| {
+                matches!(
+                    token.token(),
+                    Token::TemplateHead(_) | Token::TemplateTail(_) | Token::Semicolon
+                )
+            })
+            .map(|token| (token.token(), token.line, token.column))
+            .collect();
+        assert_eq!(
+            pieces,
+            [
+                (Token::TemplateHead("a".into()), 1, 5),
+                (Token::TemplateHead("b".into()), 1, 9),
+                (Token::TemplateTail("".into()), 1, 14),
+                (Token::TemplateTail("\nd".into()), 1, 16),
+                (Token::Semicolon, 2, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn lexes_every_punctuator() {
+        for (text, expected) in crate::token::PUNCTUATORS {
+            let input = format!("a {text} b");
+            let tokens = lex(&input);
+
+            assert_eq!(tokens_to_source(&tokens), input);
+            assert_eq!(&tokens[2].token(), expected, "{input}");
+            assert_eq!((tokens[2].start, tokens[2].end), (2, 2 + text.len()));
+        }
+    }
+
+    #[test]
+    fn lexes_longest_shift_assignment() {
+        let tokens = lex("x >>>= 1; y >>= 2; z >>> 3");
+        let operators: Vec<Token> = tokens
+            .into_iter()
+            .map(|token| token.token())
+            .filter(|token| token.is_punctuation() && *token != Token::Semicolon)
+            .collect();
+
+        assert_eq!(
+            operators,
+            [
+                Token::GreaterThanGreaterThanGreaterThanEquals,
+                Token::GreaterThanGreaterThanEquals,
+                Token::GreaterThanGreaterThanGreaterThan,
+            ]
+        );
+    }
+
+    #[test]
+    fn question_dot_before_digit_is_conditional() {
+        let input = "a?.5:b; c?.d";
+        let tokens = lex(input);
+
+        assert_eq!(render(&tokens), input);
+        assert_eq!(
+            values(&tokens),
+            [
+                Token::Identifier("a".into()),
+                Token::Question,
+                Token::NumericLiteral(".5".into()),
+                Token::Colon,
+                Token::Identifier("b".into()),
+                Token::Semicolon,
+                Token::WhitespaceTrivia(" ".into()),
+                Token::Identifier("c".into()),
+                Token::QuestionDot,
+                Token::Identifier("d".into()),
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn lexes_regex_literal_with_flags() {
+        let input = r#"s.replace(/a\/b[/]/g, "")"#;
+        let tokens = lex(input);
+
+        assert_eq!(render(&tokens), input);
+        assert!(tokens.iter().any(|token| token.token()
+            == Token::RegularExpressionLiteral {
+                body: r"a\/b[/]".into(),
+                flags: "g".into(),
+            }));
+    }
+
+    #[test]
+    fn lexes_slash_as_division_after_operand() {
+        let input = "a / b / c; (x) / 2; arr[0] /= 3";
+        let tokens = lex(input);
+
+        assert_eq!(render(&tokens), input);
+        assert!(
+            !tokens
+                .iter()
+                .any(|token| matches!(token.token(), Token::RegularExpressionLiteral { .. }))
+        );
+    }
+
+    #[test]
+    fn lexes_slash_as_division_after_any_number() {
+        let input = "x = .5 / 2; y = 1n / 2;";
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.lex();
+
+        assert_eq!(render(&tokens), input);
+        assert!(lexer.diagnostics().is_empty());
+        assert_eq!(
+            tokens
+                .iter()
+                .filter(|token| token.kind == TokenKind::Slash)
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn lexes_regex_after_keyword_and_operator() {
+        let input = "return /x+/i.test(s) || /[a-z]/u;";
+        let tokens = lex(input);
+
+        assert_eq!(render(&tokens), input);
+        let regexes: Vec<Token> = values(&tokens)
+            .into_iter()
+            .filter(|token| matches!(token, Token::RegularExpressionLiteral { .. }))
+            .collect();
+        assert_eq!(
+            regexes,
+            [
+                Token::RegularExpressionLiteral {
+                    body: "x+".into(),
+                    flags: "i".into(),
+                },
+                Token::RegularExpressionLiteral {
+                    body: "[a-z]".into(),
+                    flags: "u".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_invalid_regex_flags() {
+        let cases = [
+            ("/a/dgimsy", vec![]),
+            ("/a/v", vec![]),
+            ("/a/q", vec![(1499, 3..4)]),
+            ("/a/gig", vec![(1500, 5..6)]),
+            ("/a/uv", vec![(1502, 4..5)]),
+            ("/a/gxg", vec![(1499, 4..5), (1500, 5..6)]),
+        ];
+
+        for (input, expected) in cases {
+            let mut lexer = Lexer::new(input);
+            let tokens = lexer.lex();
+
+            assert_eq!(render(&tokens), input);
+            let reported: Vec<(u32, Range<usize>)> = lexer
+                .diagnostics()
+                .iter()
+                .map(|diagnostic| (diagnostic.code, diagnostic.start..diagnostic.end))
+                .collect();
+            assert_eq!(reported, expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn unterminated_regex_falls_back_to_slash() {
+        let input = "x = /abc\ny";
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.lex();
+
+        assert_eq!(render(&tokens), input);
+        assert!(tokens.iter().any(|token| token.token() == Token::Slash));
+        // Only the parser knows whether a regex was meant
+        assert!(lexer.diagnostics().is_empty());
+    }
+
+    #[test]
+    fn lexes_slash_as_division_after_keyword_property() {
+        let input = "x.return / 2; map.delete / n; a?.in / b;";
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.lex();
+
+        assert_eq!(render(&tokens), input);
+        assert!(lexer.diagnostics().is_empty());
+        assert_eq!(
+            tokens
+                .iter()
+                .filter(|token| token.kind == TokenKind::Slash)
+                .count(),
+            3
+        );
+    }
+
+    #[test]
+    fn lexes_numeric_literal_forms() {
+        let input = "0x1F 0XaB 0o17 0b1010 017 08 1e-7 2.5E+3 1_000_000 .5 5. 0xFFn 0b1n 0o7n";
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.lex();
+
+        assert_eq!(render(&tokens), input);
+        assert!(lexer.diagnostics().is_empty());
+        let literals: Vec<Token> = values(&tokens)
+            .into_iter()
+            .filter(|token| !matches!(token, Token::WhitespaceTrivia(_) | Token::Eof))
+            .collect();
+        assert_eq!(
+            literals,
+            [
+                Token::NumericLiteral("0x1F".into()),
+                Token::NumericLiteral("0XaB".into()),
+                Token::NumericLiteral("0o17".into()),
+                Token::NumericLiteral("0b1010".into()),
+                Token::NumericLiteral("017".into()),
+                Token::NumericLiteral("08".into()),
+                Token::NumericLiteral("1e-7".into()),
+                Token::NumericLiteral("2.5E+3".into()),
+                Token::NumericLiteral("1_000_000".into()),
+                Token::NumericLiteral(".5".into()),
+                Token::NumericLiteral("5.".into()),
+                Token::BigIntLiteral("0xFF".into()),
+                Token::BigIntLiteral("0b1".into()),
+                Token::BigIntLiteral("0o7".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_invalid_numeric_literals() {
+        let cases = [
+            ("1__0", 6189, 2..3),
+            ("1_", 6188, 1..2),
+            ("0x", 1125, 2..2),
+            ("0b;", 1177, 2..2),
+            ("1e", 1124, 2..2),
+            ("1.5n", 1353, 0..4),
+            ("3in", 1351, 1..2),
+            ("017n", 1121, 0..4),
+            ("08n", 1489, 0..3),
+            ("0_1", 6188, 1..2),
+        ];
+
+        for (input, code, range) in cases {
+            let mut lexer = Lexer::new(input);
+            lexer.lex();
+
+            let reported: Vec<(u32, usize, usize)> = lexer
+                .diagnostics()
+                .iter()
+                .map(|diagnostic| (diagnostic.code, diagnostic.start, diagnostic.end))
+                .collect();
+            assert_eq!(reported, [(code, range.start, range.end)], "{input}");
+        }
+    }
+
+    #[test]
+    fn records_byte_ranges_for_non_ascii_source() {
+        let input = "let s = \"é😀\"; s";
+        let tokens = lex(input);
+
+        // Ranges tile the source without gaps or overlaps
+        let mut offset = 0;
+        for token in &tokens {
+            assert_eq!(token.start, offset);
+            offset = token.end;
+        }
+        assert_eq!(offset, input.len());
+
+        let string = &tokens[6];
+        assert_eq!(
+            string.token(),
+            Token::StringLiteral {
+                raw: "\"é😀\"".into(),
+                value: "é😀".into(),
+            }
+        );
+        assert_eq!(&input[string.start..string.end], "\"é😀\"");
+        let last = &tokens[tokens.len() - 2];
+        assert_eq!(last.token(), Token::Identifier("s".into()));
+        assert_eq!((last.column, last.start, last.end), (15, 18, 19));
+    }
+
+    #[test]
+    fn reports_unterminated_and_malformed_tokens() {
+        let cases = [
+            ("x = \"abc\ny", 1002, 4..8),
+            ("/* open", 1010, 7..7),
+            ("`abc ${x}", 1160, 8..9),
+            ("a ¬ b", 1127, 2..4),
+        ];
+
+        for (input, code, range) in cases {
+            let (_, diagnostics) = Lexer::new(input).lex_with_diagnostics();
+
+            let reported: Vec<(u32, usize, usize)> = diagnostics
+                .iter()
+                .map(|diagnostic| (diagnostic.code, diagnostic.start, diagnostic.end))
+                .collect();
+            assert_eq!(reported, [(code, range.start, range.end)], "{input}");
+        }
+    }
+
+    #[test]
+    fn unterminated_string_stops_at_line_end() {
+        let tokens = lex("'abc\nx");
+
+        assert_eq!(
+            values(&tokens),
+            [
+                Token::StringLiteral {
+                    raw: "'abc".into(),
+                    value: "abc".into(),
+                },
+                Token::NewLineTrivia(LineTerminator::Lf),
+                Token::Identifier("x".into()),
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_illegal_characters_verbatim() {
+        let input = "a ¬ b";
+        let tokens = lex(input);
+
+        assert_eq!(render(&tokens), input);
+        assert!(
+            tokens
+                .iter()
+                .any(|token| token.token() == Token::Illegal("¬".into()))
+        );
+    }
+
+    #[test]
+    fn payloads_reference_source_and_intern_identifiers() {
+        let source: Arc<str> = Arc::from("let foo = \\u0066oo /* note */ + 'plain' + 'esc\\n';");
+        let mut lexer = Lexer::new(Arc::clone(&source));
+        let tokens = lexer.lex();
+        let source_range = source.as_bytes().as_ptr_range();
+
+        // Names are interned decoded, so an escaped spelling is the same name
+        let identifiers: Vec<_> = tokens.iter().filter_map(SpannedToken::symbol).collect();
+        let foo = lexer.interner().get("foo");
+        assert_eq!(identifiers, [foo.unwrap(), foo.unwrap()]);
+        assert_eq!(lexer.interner().len(), 1);
+
+        let borrowed = |text: &Text| source_range.contains(&text.as_ptr());
+        for token in &tokens {
+            match &token.token() {
+                Token::MultiLineCommentTrivia(text)
+                | Token::WhitespaceTrivia(text)
+                | Token::Identifier(text) => {
+                    assert!(borrowed(text), "{text:?} was copied");
+                }
+                Token::StringLiteral { raw, value } => {
+                    assert!(borrowed(raw));
+                    assert_eq!(borrowed(value), !raw.contains('\\'));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn comment_texts(tokens: &[SpannedToken]) -> Vec<&str> {
+        tokens
+            .iter()
+            .filter(|token| {
+                matches!(
+                    token.kind,
+                    TokenKind::MultiLineCommentTrivia | TokenKind::SingleLineCommentTrivia
+                )
+            })
+            .filter_map(|token| token.payload.text().map(Text::as_str))
+            .collect()
+    }
+
+    #[test]
+    fn comment_conformance() {
+        // (input, comments it must contain, whether '*/' expected is reported)
+        let cases: &[(&str, &[&str], bool)] = &[
+            ("/**/", &["/**/"], false),
+            ("/***/", &["/***/"], false),
+            ("/* * / */", &["/* * / */"], false),
+            ("/*/ */", &["/*/ */"], false),
+            (
+                "/* see /* legacy */ x = 1;",
+                &["/* see /* legacy */"],
+                false,
+            ),
+            ("/* a */ b /* c */", &["/* a */", "/* c */"], false),
+            ("/* line\nbreak */x", &["/* line\nbreak */"], false),
+            ("/* // inner */ y", &["/* // inner */"], false),
+            ("// /* not a block\nz", &["// /* not a block"], false),
+            ("// trailing */", &["// trailing */"], false),
+            ("x // eof", &["// eof"], false),
+            ("/** doc */", &["/** doc */"], false),
+            ("/* open", &["/* open"], true),
+            ("/* open *", &["/* open *"], true),
+            ("\"/* string */\"", &[], false),
+        ];
+
+        for &(input, expected, unterminated) in cases {
+            let mut lexer = Lexer::new(input);
+            let tokens = lexer.lex();
+
+            assert_eq!(render(&tokens), input, "{input}");
+            assert_eq!(comment_texts(&tokens), expected, "{input}");
+            let reported = lexer.diagnostics().iter().any(|d| d.code == 1010);
+            assert_eq!(reported, unterminated, "{input}");
+        }
+    }
+
+    #[test]
+    fn first_close_ends_block_comment() {
+        let tokens = lex("/* see /* legacy */ x = 1;");
+
+        assert_eq!(
+            values(&tokens)[..3],
+            [
+                Token::MultiLineCommentTrivia("/* see /* legacy */".into()),
+                Token::WhitespaceTrivia(" ".into()),
+                Token::Identifier("x".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_comments_are_opt_in() {
+        let input = "/* a /* b */ c */ x";
+        let options = LexerOptions {
+            nested_comments: true,
+            ..LexerOptions::default()
+        };
+        let tokens = Lexer::with_options(input, options).lex();
+
+        assert_eq!(render(&tokens), input);
+        assert_eq!(comment_texts(&tokens), ["/* a /* b */ c */"]);
+    }
+
+    #[test]
+    fn lexes_unicode_identifiers() {
+        let input = "const größe = 名前 + ñ_1 + $é + x\u{200D}y;";
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.lex();
+
+        assert_eq!(render(&tokens), input);
+        assert!(lexer.diagnostics().is_empty());
+        let identifiers: Vec<Token> = values(&tokens)
+            .into_iter()
+            .filter(|token| matches!(token, Token::Identifier(_)))
+            .collect();
+        assert_eq!(
+            identifiers,
+            [
+                Token::Identifier("größe".into()),
+                Token::Identifier("名前".into()),
+                Token::Identifier("ñ_1".into()),
+                Token::Identifier("$é".into()),
+                Token::Identifier("x\u{200D}y".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_raw_spelling_of_escaped_identifiers() {
+        let input = r"let \u0061b = \u{1F600}x + caf\u00e9;";
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.lex();
+
+        assert_eq!(render(&tokens), input);
+        let names: Vec<String> = tokens
+            .iter()
+            .filter_map(|token| token.identifier_name())
+            .map(|name| name.into_owned())
+            .collect();
+        assert_eq!(names, ["ab", "\u{1F600}x", "café"]);
+        // U+1F600 is an emoji, not an ID_Start code point
+        let reported: Vec<u32> = lexer.diagnostics().iter().map(|d| d.code).collect();
+        assert_eq!(reported, [1127]);
+    }
+
+    #[test]
+    fn reports_malformed_identifier_escapes() {
+        let cases = [
+            (r"\u00", 1125),
+            (r"\u{}", 1125),
+            (r"\u{61", 1199),
+            (r"\u{110000}", 1198),
+            (r"\u0076ar", 1260),
+        ];
+
+        for (input, code) in cases {
+            let mut lexer = Lexer::new(input);
+            lexer.lex();
+
+            let reported: Vec<u32> = lexer.diagnostics().iter().map(|d| d.code).collect();
+            assert_eq!(reported, [code], "{input}");
+        }
+    }
+
+    #[test]
+    fn lexes_private_identifiers() {
+        let input = "class C { #count = 0; inc() { this.#count /= 2; return #count in this; } }";
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.lex();
+
+        assert_eq!(render(&tokens), input);
+        assert!(lexer.diagnostics().is_empty());
+        let private: Vec<&SpannedToken> = tokens
+            .iter()
+            .filter(|token| matches!(token.token(), Token::PrivateIdentifier(_)))
+            .collect();
+        assert_eq!(private.len(), 3);
+        assert_eq!(private[0].token(), Token::PrivateIdentifier("count".into()));
+        assert_eq!((private[0].start, private[0].end), (10, 16));
+        assert!(
+            !tokens
+                .iter()
+                .any(|token| matches!(token.token(), Token::RegularExpressionLiteral { .. }))
+        );
+    }
+
+    #[test]
+    fn private_names_may_spell_keywords() {
+        let input = r"class A { #if = 1; #\u0069f = 2 }";
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.lex();
+
+        assert_eq!(render(&tokens), input);
+        assert!(lexer.diagnostics().is_empty());
+        let private: Vec<Token> = tokens
+            .iter()
+            .map(SpannedToken::token)
+            .filter(|token| matches!(token, Token::PrivateIdentifier(_)))
+            .collect();
+        assert_eq!(private.len(), 2);
+    }
+
+    #[test]
+    fn reports_hash_not_followed_by_name() {
+        let mut lexer = Lexer::new("# x");
+        let tokens = lexer.lex();
+
+        assert_eq!(tokens[0].token(), Token::Hash);
+        let reported: Vec<(u32, usize, usize)> = lexer
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| (diagnostic.code, diagnostic.start, diagnostic.end))
+            .collect();
+        assert_eq!(reported, [(1127, 0, 1)]);
+    }
+
+    #[test]
+    fn lexes_shebang_line() {
+        let input = "#!/usr/bin/env node\nmain();";
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.lex();
+
+        assert_eq!(render(&tokens), input);
+        assert!(lexer.diagnostics().is_empty());
+        assert_eq!(
+            tokens[0].token(),
+            Token::ShebangTrivia("#!/usr/bin/env node".into())
+        );
+        assert_eq!(tokens[1].token(), Token::NewLineTrivia(LineTerminator::Lf));
+    }
+
+    #[test]
+    fn tracks_lines_across_all_line_terminators() {
+        let input = "a\r\nb\rc\u{2028}d\u{2029}e\n// f\r\ng";
+        let tokens = lex(input);
+        assert_eq!(render(&tokens), input);
+
+        let terminators: Vec<LineTerminator> = tokens
+            .iter()
+            .filter_map(|token| match token.token() {
+                Token::NewLineTrivia(terminator) => Some(terminator),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            terminators,
+            [
+                LineTerminator::CrLf,
+                LineTerminator::Cr,
+                LineTerminator::LineSeparator,
+                LineTerminator::ParagraphSeparator,
+                LineTerminator::Lf,
+                LineTerminator::CrLf,
+            ]
+        );
+
+        let positions: Vec<(String, u32, u32)> = tokens
+            .iter()
+            .filter(|token| !token.token().is_trivia() && token.token() != Token::Eof)
+            .map(|token| {
+                (
+                    input[token.start..token.end].to_string(),
+                    token.line,
+                    token.column,
+                )
+            })
+            .collect();
+        let expected = [("a", 1), ("b", 2), ("c", 3), ("d", 4), ("e", 5), ("g", 7)];
+        assert_eq!(
+            positions,
+            expected.map(|(text, line)| (text.to_string(), line, 1))
+        );
+        assert_eq!(
+            tokens
+                .iter()
+                .find(|token| matches!(token.token(), Token::SingleLineCommentTrivia(_)))
+                .map(|token| &input[token.start..token.end]),
+            Some("// f")
+        );
+    }
+
+    #[test]
+    fn lexes_byte_order_mark_before_shebang() {
+        let input = "\u{FEFF}#!/usr/bin/env node\r\nmain();";
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.lex();
+
+        assert_eq!(render(&tokens), input);
+        assert!(lexer.diagnostics().is_empty());
+        assert_eq!(tokens[0].token(), Token::ByteOrderMark);
+        assert_eq!((tokens[0].start, tokens[0].end), (0, 3));
+        assert_eq!(
+            tokens[1].token(),
+            Token::ShebangTrivia("#!/usr/bin/env node".into())
+        );
+        assert_eq!(tokens[3].token(), Token::Identifier("main".into()));
+        assert_eq!((tokens[3].line, tokens[3].column), (2, 1));
+    }
+
+    #[test]
+    fn reports_shebang_after_start_of_file() {
+        let mut lexer = Lexer::new("x;\n#!/bin/sh");
+        lexer.lex();
+
+        // The rest of the line lexes as code: `/bin/sh` is a regular
+        // expression with an unknown `h` flag
+        let reported: Vec<u32> = lexer.diagnostics().iter().map(|d| d.code).collect();
+        assert_eq!(reported, [18026, 1499]);
+    }
+
+    #[test]
+    fn lexes_merge_conflict_markers() {
+        let input = "\
+let a = 1;
+<<<<<<< HEAD
+let b = 2;
+=======
+let b = 3;
+>>>>>>> feature
+a << b;
+";
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.lex();
+
+        assert_eq!(render(&tokens), input);
+        let markers: Vec<(Token, u32)> = tokens
+            .iter()
+            .filter(|token| matches!(token.token(), Token::ConflictMarkerTrivia(_)))
+            .map(|token| (token.token(), token.line))
+            .collect();
+        assert_eq!(
+            markers,
+            [
+                (Token::ConflictMarkerTrivia("<<<<<<< HEAD".into()), 2),
+                (
+                    Token::ConflictMarkerTrivia("=======\nlet b = 3;\n".into()),
+                    4
+                ),
+                (Token::ConflictMarkerTrivia(">>>>>>> feature".into()), 6),
+            ]
+        );
+        let reported: Vec<(u32, u32)> = lexer
+            .diagnostics()
+            .iter()
+            .map(|d| (d.code, d.start as u32))
+            .collect();
+        assert_eq!(reported, [(1185, 11), (1185, 35), (1185, 54)]);
+    }
+
+    fn string_value(input: &str) -> (String, Vec<u32>) {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.lex();
+        assert_eq!(render(&tokens), input);
+        let Token::StringLiteral { raw, value } = &tokens[0].token() else {
+            panic!("expected a string literal in {input}");
+        };
+        assert_eq!(raw, input);
+        let codes = lexer.diagnostics().iter().map(|d| d.code).collect();
+        (value.to_string(), codes)
+    }
+
+    #[test]
+    fn preserves_string_spelling() {
+        let input = r#"f('it\'s', "é", 'a"b', "\u{1F600}")"#;
+        let tokens = lex(input);
+
+        assert_eq!(render(&tokens), input);
+    }
+
+    #[test]
+    fn decodes_string_escapes() {
+        let cases = [
+            (r"'\n\t\r\b\f\v'", "\n\t\r\u{8}\u{C}\u{B}"),
+            (r"'\x41B\u{43}'", "ABC"),
+            (r"'😀'", "\u{1F600}"),
+            (r"'\0'", "\0"),
+            (r#"'\'\"\\'"#, "'\"\\"),
+            ("'a\\\nb'", "ab"),
+            ("'a\\\r\nb'", "ab"),
+            (r"'\q'", "q"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(
+                string_value(input),
+                (expected.to_string(), vec![]),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn reports_invalid_string_escapes() {
+        assert_eq!(string_value(r"'\101'"), ("A".to_string(), vec![1487]));
+        assert_eq!(string_value(r"'\8'"), ("8".to_string(), vec![1488]));
+        assert_eq!(string_value(r"'\xZ1'"), ("Z1".to_string(), vec![1125]));
+        assert_eq!(string_value(r"'\uD83D'"), ("\u{FFFD}".to_string(), vec![]));
+    }
+
+    fn lex_jsx(input: &str) -> (Vec<SpannedToken>, Vec<crate::diagnostic::Diagnostic>) {
+        let options = LexerOptions {
+            language_variant: LanguageVariant::Jsx,
+            ..LexerOptions::default()
+        };
+        Lexer::with_options(input, options).lex_with_diagnostics()
+    }
+
+    fn significant(tokens: &[SpannedToken]) -> Vec<Token> {
+        values(tokens)
+            .into_iter()
+            .filter(|token| !token.is_trivia() && *token != Token::Eof)
+            .collect()
+    }
+
+    #[test]
+    fn lexes_jsx_element() {
+        let input = r#"x = <div className="a &amp; \n" data-id={id}>Hi {name}!<br/></div>;"#;
+        let (tokens, diagnostics) = lex_jsx(input);
+
+        assert_eq!(render(&tokens), input);
+        assert!(diagnostics.is_empty());
+        assert_eq!(
+            significant(&tokens),
+            [
+                Token::Identifier("x".into()),
+                Token::Equals,
+                Token::LessThan,
+                Token::Identifier("div".into()),
+                Token::Identifier("className".into()),
+                Token::Equals,
+                Token::StringLiteral {
+                    raw: r#""a &amp; \n""#.into(),
+                    value: r"a & \n".into(),
+                },
+                Token::Identifier("data-id".into()),
+                Token::Equals,
+                Token::OpenBrace,
+                Token::Identifier("id".into()),
+                Token::CloseBrace,
+                Token::GreaterThan,
+                Token::JsxText("Hi ".into()),
+                Token::OpenBrace,
+                Token::Identifier("name".into()),
+                Token::CloseBrace,
+                Token::JsxText("!".into()),
+                Token::LessThan,
+                Token::Identifier("br".into()),
+                Token::Slash,
+                Token::GreaterThan,
+                Token::LessThanSlash,
+                Token::Identifier("div".into()),
+                Token::GreaterThan,
+                Token::Semicolon,
+            ]
+        );
+    }
+
+    #[test]
+    fn lexes_nested_jsx_across_lines() {
+        let input = "\
+return (
+  <>
+    <List items={items.map(i => <Item key={i} />)} />
+    {/* comment */}
+  </>
+) / 2;
+";
+        let (tokens, diagnostics) = lex_jsx(input);
+
+        assert_eq!(render(&tokens), input);
+        assert!(diagnostics.is_empty());
+        let whitespace_children = tokens
+            .iter()
+            .filter(|token| matches!(token.token(), Token::JsxTextAllWhiteSpaces(_)))
+            .count();
+        assert_eq!(whitespace_children, 3);
+        let closing = tokens
+            .iter()
+            .find(|token| token.token() == Token::LessThanSlash)
+            .unwrap();
+        assert_eq!((closing.line, closing.column), (5, 3));
+        assert!(tokens.iter().any(|token| token.token() == Token::Slash));
+    }
+
+    #[test]
+    fn keeps_comparisons_and_generics_out_of_jsx() {
+        let input = "a < b > c; const f = <T,>(x: T) => x;";
+        let (tokens, _) = lex_jsx(input);
+
+        assert_eq!(render(&tokens), input);
+        assert!(!tokens.iter().any(|token| matches!(
+            token.token(),
+            Token::JsxText(_) | Token::JsxTextAllWhiteSpaces(_)
+        )));
+
+        let standard = lex("<div>text</div>");
+        assert!(
+            !standard
+                .iter()
+                .any(|token| matches!(token.token(), Token::JsxText(_)))
+        );
+    }
+
+    #[test]
+    fn keeps_type_parameters_in_types_out_of_jsx() {
+        for input in [
+            "type Fn = <T>(x: T) => T;\nconst y = 1;",
+            "type G<T> = <U>(x: U) => T;\nconst y = 1;",
+            "type H<T = A<B>>= <U>(x: U) => T;\nconst y = 1;",
+            "let f: <T>(x: T) => T = id;\nconst y = 1;",
+            "function g(cb: <T>(x: T) => T) {}\nconst y = 1;",
+        ] {
+            let (tokens, diagnostics) = lex_jsx(input);
+
+            assert_eq!(render(&tokens), input);
+            assert!(diagnostics.is_empty(), "{input:?}: {diagnostics:?}");
+            assert!(
+                !tokens
+                    .iter()
+                    .any(|token| matches!(token.token(), Token::JsxText(_))),
+                "{input:?}"
+            );
+        }
+
+        for input in [
+            "const a = c ? <a/> : <b>x</b>;",
+            "const o = { icon: <i>x</i> };",
+            "const p = { a: c ? 1 : 2, b: <b>x</b> };",
+            "type T = A; const e = <b>x</b>;",
+        ] {
+            let (tokens, diagnostics) = lex_jsx(input);
+
+            assert!(diagnostics.is_empty(), "{input:?}: {diagnostics:?}");
+            assert!(
+                tokens
+                    .iter()
+                    .any(|token| matches!(token.token(), Token::JsxText(_))),
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn reports_stray_jsx_text_characters() {
+        let (_, diagnostics) = lex_jsx("<p>a > b }</p>");
+
+        let reported: Vec<(u32, usize)> = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.code, diagnostic.start))
+            .collect();
+        assert_eq!(reported, [(1382, 5), (1381, 9)]);
+    }
+
+    fn next_significant(scanner: &mut Scanner) -> SpannedToken {
+        loop {
+            let token = scanner.next_token();
+            if !token.token().is_trivia() {
+                return token;
+            }
+        }
+    }
+
+    #[test]
+    fn scanner_iterates_like_lex() {
+        let input = "let x = `a${b}c` / 2; // done\n";
+        let scanned: Vec<SpannedToken> = Scanner::new(input).collect();
+
+        assert_eq!(scanned, lex(input));
+        assert_eq!(scanned.last().map(|token| token.kind), Some(TokenKind::Eof));
+    }
+
+    #[test]
+    fn look_ahead_and_failed_try_scan_restore_state() {
+        let mut scanner = Scanner::new("a 'open\nb");
+
+        let peeked = scanner.look_ahead(|scanner| {
+            next_significant(scanner);
+            next_significant(scanner)
+        });
+        assert!(matches!(peeked.token(), Token::StringLiteral { .. }));
+        assert!(scanner.diagnostics().is_empty());
+
+        let attempt = scanner.try_scan(|scanner| {
+            let token = next_significant(scanner);
+            (token.token() == Token::Comma).then_some(token)
+        });
+        assert_eq!(attempt, None);
+        assert_eq!(scanner.position(), 0);
+
+        let kept = scanner.try_scan(|scanner| Some(next_significant(scanner)));
+        assert_eq!(
+            kept.map(|token| token.token()),
+            Some(Token::Identifier("a".into()))
+        );
+        assert_eq!(scanner.position(), 1);
+    }
+
+    #[test]
+    fn rescans_greater_token_for_type_arguments() {
+        let mut scanner = Scanner::new("Array<T>= y");
+        let tokens: Vec<SpannedToken> = (0..4).map(|_| scanner.next_token()).collect();
+        assert_eq!(tokens[3].token(), Token::GreaterThanEquals);
+
+        let greater = scanner.rescan_greater_token(&tokens[3]);
+        assert_eq!(
+            (greater.token(), greater.start, greater.end),
+            (Token::GreaterThan, 7, 8)
+        );
+        assert_eq!(scanner.next_token().token(), Token::Equals);
+    }
+
+    #[test]
+    fn rescans_slash_as_regex() {
+        let mut scanner = Scanner::new("if (x) /a b/g.test(s)");
+        let slash = scanner
+            .by_ref()
+            .find(|token| token.token() == Token::Slash)
+            .unwrap();
+
+        let regex = scanner.rescan_slash_token(&slash);
+        assert_eq!(
+            regex.token(),
+            Token::RegularExpressionLiteral {
+                body: "a b".into(),
+                flags: "g".into(),
+            }
+        );
+        assert_eq!(scanner.next_token().token(), Token::Dot);
+    }
+
+    #[test]
+    fn reports_unterminated_regex_on_rescan() {
+        let mut scanner = Scanner::new("if (x) /abc\ny");
+        let slash = scanner
+            .by_ref()
+            .find(|token| token.token() == Token::Slash)
+            .unwrap();
+        assert!(scanner.diagnostics().is_empty());
+
+        let rescanned = scanner.rescan_slash_token(&slash);
+        assert_eq!(
+            rescanned.token(),
+            Token::RegularExpressionLiteral {
+                body: "abc".into(),
+                flags: "".into(),
+            }
+        );
+        let reported: Vec<(u32, usize, usize)> = scanner
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| (diagnostic.code, diagnostic.start, diagnostic.end))
+            .collect();
+        assert_eq!(reported, [(1161, 7, 11)]);
+    }
+
+    #[test]
+    fn rescans_close_brace_as_template_continuation() {
+        let mut scanner = Scanner::new("} tail`;");
+        let brace = scanner.next_token();
+        assert_eq!(brace.token(), Token::CloseBrace);
+
+        let tail = scanner.rescan_template_token(&brace);
+        assert_eq!(tail.token(), Token::TemplateTail(" tail".into()));
+        assert_eq!(scanner.next_token().token(), Token::Semicolon);
+    }
+
+    #[test]
+    fn rescans_jsx_tokens() {
+        let mut scanner = Scanner::new(r#"data-id="a\n" text {x}"#);
+
+        let name = scanner.next_token();
+        let name = scanner.rescan_jsx_identifier(&name);
+        assert_eq!(name.token(), Token::Identifier("data-id".into()));
+        assert_eq!(scanner.next_token().token(), Token::Equals);
+
+        let string = scanner.next_token();
+        let value = scanner.rescan_jsx_attribute_value(&string);
+        assert_eq!(
+            value.token(),
+            Token::StringLiteral {
+                raw: r#""a\n""#.into(),
+                value: r"a\n".into(),
+            }
+        );
+
+        let space = scanner.next_token();
+        let text = scanner.rescan_jsx_token(&space);
+        assert_eq!(text.token(), Token::JsxText(" text ".into()));
+        assert_eq!(scanner.next_token().token(), Token::OpenBrace);
+    }
+
+    fn relex_after(
+        old: &str,
+        range: std::ops::Range<usize>,
+        replacement: &str,
+        options: LexerOptions,
+    ) {
+        let old: Arc<str> = old.into();
+        let mut lexer = Lexer::with_options(Arc::clone(&old), options);
+        let (mut tokens, mut diagnostics) = lexer.lex_with_diagnostics();
+        let interner = Arc::clone(lexer.interner());
+        let new = format!("{}{replacement}{}", &old[..range.start], &old[range.end..]);
+        let edit = TextEdit {
+            start: range.start,
+            old_end: range.end,
+            new_end: range.start + replacement.len(),
+        };
+
+        // Relexing keeps the shared interner, so the old symbols stay valid
+        Lexer::with_options(new.as_str(), options)
+            .with_interner(Arc::clone(&interner))
+            .relex(&old, &mut tokens, &mut diagnostics, &edit);
+        let expected = Lexer::with_options(new.as_str(), options)
+            .with_interner(interner)
+            .lex_with_diagnostics();
+        assert_eq!((tokens, diagnostics), expected, "relexing {new:?}");
+    }
+
+    #[test]
+    fn relex_matches_full_lex() {
+        let old = "let a = 1;\nconst s = 'str';\r\n/* block\n comment */\nlet t = `x${y}z`;\nx = a / b / c;\nlet 0b2;\n";
+        let edits: &[(std::ops::Range<usize>, &str)] = &[
+            (0..0, "// new\n"),
+            (4..5, "alpha"),
+            (11..16, "var"),
+            (21..21, "'"),
+            (27..27, "\n"),
+            (28..30, "/*"),
+            (38..38, "*/"),
+            (55..56, "`"),
+            (60..61, "{"),
+            (74..75, "/ re /"),
+            (old.len()..old.len(), "more;"),
+            (0..old.len(), ""),
+        ];
+        for (range, replacement) in edits {
+            relex_after(old, range.clone(), replacement, LexerOptions::default());
+        }
+    }
+
+    #[test]
+    fn relex_matches_full_lex_in_jsx() {
+        let options = LexerOptions {
+            language_variant: LanguageVariant::Jsx,
+            ..LexerOptions::default()
+        };
+        let old =
+            "const a = 1;\nconst e = <div>\n  text {a}\n</div>;\nlet b = 2;\nlet c = x / 2;\n";
+        for (range, replacement) in [
+            (26..26, "<b>"),
+            (33..37, "words"),
+            (53..55, "c"),
+            (73..73, "\n"),
+        ] {
+            relex_after(old, range, replacement, options);
+        }
+        // The `;` is inside a tag that never closes
+        relex_after("<a&amp;\nlet", 11..11, "'", options);
+    }
+
+    #[test]
+    fn relex_reuses_tokens_outside_the_edit() {
+        let old: Arc<str> = "let a = 1;\nlet b = 2;\nlet c = 'three';\n".into();
+        let mut tokens = Lexer::new(Arc::clone(&old)).lex();
+        // Mark the first and last tokens so reuse is visible in the output
+        tokens[0].column = 100;
+        let last = tokens.len() - 2;
+        tokens[last].column = 200;
+        let buffer = tokens.as_ptr();
+
+        let edit = TextEdit {
+            start: 15,
+            old_end: 16,
+            new_end: 16,
+        };
+        let new: Arc<str> = "let a = 1;\nlet x = 2;\nlet c = 'three';\n".into();
+        let mut diagnostics = Vec::new();
+        Lexer::new(Arc::clone(&new)).relex(&old, &mut tokens, &mut diagnostics, &edit);
+
+        assert_eq!(render(&tokens), &*new);
+        assert!(diagnostics.is_empty());
+        assert_eq!(tokens[0].column, 100);
+        assert_eq!(tokens[tokens.len() - 2].column, 200);
+        assert_eq!(tokens[tokens.len() - 2].start, new.len() - 1);
+        // The tokens were updated in place and moved onto the new source
+        assert_eq!(tokens.as_ptr(), buffer);
+        assert_eq!(Arc::strong_count(&old), 1);
+        let new_range = new.as_bytes().as_ptr_range();
+        let string = tokens
+            .iter()
+            .find(|token| token.kind == TokenKind::StringLiteral)
+            .unwrap();
+        let TokenPayload::String { raw, .. } = &string.payload else {
+            panic!("expected a string payload");
+        };
+        assert!(new_range.contains(&raw.as_ptr()));
+    }
+}
+
+/// Property tests over generated input. Editors send the server half-typed
+/// text on every keystroke, so the lexer has to hold its invariants for any
+/// string at all, not just for valid TypeScript.
+#[cfg(test)]
+mod properties {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::token::tokens_to_source_exact;
+
+    /// Pieces of TypeScript that are interesting to the lexer on their own
+    /// or when glued to whatever comes next.
+    const FRAGMENTS: &[&str] = &[
+        "let",
+        "const",
+        "x",
+        "_$1",
+        "yield",
+        "await",
+        "of",
+        "\\u0061",
+        "\\u{62}",
+        "\\u{",
+        "#",
+        "#x",
+        "@",
+        "0",
+        "1_000",
+        "1_",
+        "0x1F",
+        "0b2",
+        "0o7",
+        ".5",
+        "1e",
+        "1e+3",
+        "10n",
+        "09",
+        "'",
+        "\"",
+        "'a\\n'",
+        "\\",
+        "`",
+        "${",
+        "}",
+        "{",
+        "(",
+        ")",
+        "[",
+        "]",
+        "/",
+        "/*",
+        "*/",
+        "//",
+        "/a+/g",
+        "=",
+        "=>",
+        "?.",
+        "?.5",
+        "??=",
+        ">>>=",
+        "<",
+        "</",
+        ">",
+        "<div>",
+        "</div>",
+        "<a b='c'>",
+        "&amp;",
+        "&#x41;",
+        "{/* c */}",
+        ";",
+        ",",
+        " ",
+        "\t",
+        "\n",
+        "\r",
+        "\r\n",
+        "\u{2028}",
+        "\u{2029}",
+        "\u{FEFF}",
+        "\u{a0}",
+        "#!",
+        "<<<<<<< HEAD",
+        "=======",
+        ">>>>>>> branch",
+        "é",
+        "℮",
+        "𝑥",
+        "\0",
+    ];
+
+    fn fragment() -> impl Strategy<Value = String> {
+        prop_oneof![
+            4 => proptest::sample::select(FRAGMENTS).prop_map(str::to_string),
+            1 => "[a-z]{1,6}",
+            1 => any::<char>().prop_map(String::from),
+        ]
+    }
+
+    /// TypeScript-ish text, mostly garbage.
+    fn source() -> impl Strategy<Value = String> {
+        proptest::collection::vec(fragment(), 0..48).prop_map(|fragments| fragments.concat())
+    }
+
+    /// Complete tokens whose text the lexer keeps verbatim.
+    const WELL_FORMED: &[&str] = &[
+        "let",
+        "x",
+        "_$1",
+        "#x",
+        "0",
+        "1_000",
+        "0x1F",
+        "1.5e-3",
+        ".5",
+        "10n",
+        "'a\\n'",
+        "\"b\"",
+        "`t`",
+        "`a${x}b`",
+        "`a${x}b${y}c`",
+        "=",
+        "=>",
+        "?.",
+        "??=",
+        ">>>=",
+        "...",
+        "(",
+        ")",
+        "{",
+        "}",
+        "[",
+        "]",
+        ";",
+        ",",
+        "// c",
+        "/* c */",
+        "@",
+        "!==",
+    ];
+
+    /// Complete tokens separated by trivia, so rendering them back needs no
+    /// separators of its own.
+    fn well_formed_source() -> impl Strategy<Value = String> {
+        let separator = proptest::sample::select(&[" ", "\t", "\n", "\r\n", "\u{2028}"][..]);
+        proptest::collection::vec((proptest::sample::select(WELL_FORMED), separator), 0..32)
+            .prop_map(|pieces| {
+                pieces
+                    .into_iter()
+                    .flat_map(|(token, separator)| [token, separator])
+                    .collect()
+            })
+    }
+
+    fn options() -> impl Strategy<Value = LexerOptions> {
+        (any::<bool>(), any::<bool>()).prop_map(|(nested_comments, jsx)| LexerOptions {
+            nested_comments,
+            language_variant: if jsx {
+                LanguageVariant::Jsx
+            } else {
+                LanguageVariant::Standard
+            },
+        })
+    }
+
+    /// The largest char boundary of `text` at or before `offset`.
+    fn floor_boundary(text: &str, offset: usize) -> usize {
+        (0..=offset.min(text.len()))
+            .rev()
+            .find(|&offset| text.is_char_boundary(offset))
+            .unwrap_or(0)
+    }
+
+    /// Check the invariants every token stream must satisfy: tokens tile the
+    /// input without gaps or overlaps, only the final `Eof` is empty, and
+    /// each token's line and column match its byte offset.
+    fn check_tokens(input: &str, tokens: &[SpannedToken], diagnostics: &[Diagnostic]) {
+        let (last, tokens_before_eof) = tokens.split_last().expect("at least Eof");
+        assert_eq!(last.token(), Token::Eof);
+        assert_eq!((last.start, last.end), (input.len(), input.len()));
+
+        let mut offset = 0;
+        let mut line = 1;
+        let mut column = 1;
+        let mut chars = input.char_indices().peekable();
+        for token in tokens {
+            assert_eq!(token.start, offset, "gap or overlap before {token:?}");
+            assert!(
+                token.start < token.end || token.token() == Token::Eof,
+                "empty token {token:?}"
+            );
+            while let Some(&(index, c)) = chars.peek()
+                && index < token.start
+            {
+                chars.next();
+                let crlf = c == '\r' && chars.peek().is_some_and(|&(_, next)| next == '\n');
+                if is_line_terminator(c) && !crlf {
+                    line += 1;
+                    column = 1;
+                } else {
+                    column += 1;
+                }
+            }
+            assert_eq!((token.line, token.column), (line, column), "{token:?}");
+            offset = token.end;
+        }
+        assert!(
+            tokens_before_eof
+                .iter()
+                .all(|token| token.token() != Token::Eof)
+        );
+
+        for diagnostic in diagnostics {
+            assert!(diagnostic.start <= diagnostic.end && diagnostic.end <= input.len());
+        }
+
+        // The tokens render back to the input, however malformed it is
+        assert_eq!(tokens_to_source_exact(tokens), input);
+    }
+
+    proptest! {
+        #[test]
+        fn tokens_tile_any_input(input in source(), options in options()) {
+            let (tokens, diagnostics) =
+                Lexer::with_options(input.as_str(), options).lex_with_diagnostics();
+            check_tokens(&input, &tokens, &diagnostics);
+        }
+
+        #[test]
+        fn tokens_tile_arbitrary_unicode(input in any::<String>(), options in options()) {
+            let (tokens, diagnostics) =
+                Lexer::with_options(input.as_str(), options).lex_with_diagnostics();
+            check_tokens(&input, &tokens, &diagnostics);
+        }
+
+        #[test]
+        fn well_formed_tokens_round_trip(input in well_formed_source()) {
+            let tokens = Lexer::new(input.as_str()).lex();
+            prop_assert_eq!(tokens_to_source_exact(&tokens), input);
+        }
+
+        #[test]
+        fn relex_matches_full_lex_after_any_edit(
+            old in source(),
+            replacement in source(),
+            start in any::<proptest::sample::Index>(),
+            length in 0..=16usize,
+            options in options(),
+        ) {
+            let start = floor_boundary(&old, start.index(old.len() + 1));
+            let end = floor_boundary(&old, start + length);
+            let old: Arc<str> = old.into();
+            let mut lexer = Lexer::with_options(Arc::clone(&old), options);
+            let (mut tokens, mut diagnostics) = lexer.lex_with_diagnostics();
+            let interner = Arc::clone(lexer.interner());
+            let new = format!("{}{replacement}{}", &old[..start], &old[end..]);
+            let edit = TextEdit {
+                start,
+                old_end: end,
+                new_end: start + replacement.len(),
+            };
+
+            Lexer::with_options(new.as_str(), options)
+                .with_interner(Arc::clone(&interner))
+                .relex(&old, &mut tokens, &mut diagnostics, &edit);
+            let expected = Lexer::with_options(new.as_str(), options)
+                .with_interner(interner)
+                .lex_with_diagnostics();
+            prop_assert_eq!((tokens, diagnostics), expected);
+        }
     }
 }
@@ -0,0 +1,16 @@
+pub mod ast;
+pub mod diagnostic;
+pub mod jsdoc;
+pub mod jsx;
+pub mod lexer;
+pub mod line_index;
+pub mod parser;
+pub mod token;
+pub mod token_kind;
+// pub mod analyzer;
+pub mod semantic_tokens;
+pub mod server;
+pub mod symbol;
+pub mod syntax;
+pub mod text;
+pub mod trivia;
@@ -0,0 +1,272 @@
+//! Line index module — converts between byte offsets and LSP positions.
+
+use tower_lsp::lsp_types::{Position, PositionEncodingKind};
+
+use crate::lexer::TextEdit;
+
+/// The unit LSP `Position::character` is counted in, as negotiated with the
+/// client. The protocol default is UTF-16.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PositionEncoding {
+    Utf8,
+    #[default]
+    Utf16,
+    Utf32,
+}
+
+impl PositionEncoding {
+    /// Pick the encoding to use from the client's advertised list, preferring
+    /// UTF-8 (no conversion needed) and falling back to the UTF-16 default.
+    pub fn negotiate(offered: Option<&[PositionEncodingKind]>) -> Self {
+        let offered = offered.unwrap_or_default();
+        if offered.contains(&PositionEncodingKind::UTF8) {
+            Self::Utf8
+        } else if offered.contains(&PositionEncodingKind::UTF32) {
+            Self::Utf32
+        } else {
+            Self::Utf16
+        }
+    }
+
+    pub fn kind(self) -> PositionEncodingKind {
+        match self {
+            Self::Utf8 => PositionEncodingKind::UTF8,
+            Self::Utf16 => PositionEncodingKind::UTF16,
+            Self::Utf32 => PositionEncodingKind::UTF32,
+        }
+    }
+
+    fn width(self, c: char) -> u32 {
+        match self {
+            Self::Utf8 => c.len_utf8() as u32,
+            Self::Utf16 => c.len_utf16() as u32,
+            Self::Utf32 => 1,
+        }
+    }
+}
+
+/// Start offsets of every line in a document. Lines are split on `\n`,
+/// `\r\n` and `\r`, the terminators LSP recognises.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineIndex {
+    line_starts: Vec<usize>,
+    len: usize,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> Self {
+        let mut line_starts = vec![0];
+        let bytes = text.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\r' if bytes.get(i + 1) == Some(&b'\n') => {
+                    i += 2;
+                    line_starts.push(i);
+                }
+                b'\r' | b'\n' => {
+                    i += 1;
+                    line_starts.push(i);
+                }
+                _ => i += 1,
+            }
+        }
+
+        Self {
+            line_starts,
+            len: text.len(),
+        }
+    }
+
+    /// Update the index for `edit`, after which the document reads `text`.
+    /// Only the edited lines are scanned again; the line starts after them
+    /// are shifted in place.
+    pub fn apply_edit(&mut self, text: &str, edit: &TextEdit) {
+        // Whether a line starts at an offset depends on the byte before it,
+        // and for a `\r` on the byte at it, so the line starts from the
+        // edit to one past its end are recomputed
+        let from = edit.start.max(1);
+        let first = self.line_starts.partition_point(|&start| start < from);
+        let last = self
+            .line_starts
+            .partition_point(|&start| start <= edit.old_end + 1);
+
+        let bytes = text.as_bytes();
+        let mut line_starts = Vec::new();
+        for i in from - 1..(edit.new_end + 1).min(bytes.len()) {
+            let ends_line = match bytes[i] {
+                b'\n' => true,
+                b'\r' => bytes.get(i + 1) != Some(&b'\n'),
+                _ => false,
+            };
+            if ends_line {
+                line_starts.push(i + 1);
+            }
+        }
+
+        for start in &mut self.line_starts[last..] {
+            *start = (*start + edit.new_end) - edit.old_end;
+        }
+        self.line_starts.splice(first..last, line_starts);
+        self.len = text.len();
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Convert a byte offset into `text` to an LSP position. Offsets past the
+    /// end clamp to the end; offsets inside a character snap to its start.
+    pub fn position(&self, text: &str, offset: usize, encoding: PositionEncoding) -> Position {
+        let mut offset = offset.min(self.len);
+        while !text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let character = text[self.line_starts[line]..offset]
+            .chars()
+            .map(|c| encoding.width(c))
+            .sum();
+
+        Position::new(line as u32, character)
+    }
+
+    /// Convert an LSP position to a byte offset into `text`. Positions past
+    /// the end of a line clamp to the line end, and positions past the last
+    /// line clamp to the end of the document.
+    pub fn offset(&self, text: &str, position: Position, encoding: PositionEncoding) -> usize {
+        let line = position.line as usize;
+        let Some(&line_start) = self.line_starts.get(line) else {
+            return self.len;
+        };
+        let line_end = self.line_end(text, line);
+
+        let mut units = 0;
+        for (index, c) in text[line_start..line_end].char_indices() {
+            let width = encoding.width(c);
+            if units + width > position.character {
+                return line_start + index;
+            }
+            units += width;
+        }
+
+        line_end
+    }
+
+    /// Byte offset of the end of `line`, excluding its terminator.
+    fn line_end(&self, text: &str, line: usize) -> usize {
+        let next_start = self.line_starts.get(line + 1).copied().unwrap_or(self.len);
+        let content = &text[self.line_starts[line]..next_start];
+        let trimmed = content
+            .strip_suffix("\r\n")
+            .or_else(|| content.strip_suffix('\n'))
+            .or_else(|| content.strip_suffix('\r'))
+            .unwrap_or(content);
+
+        self.line_starts[line] + trimmed.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_ascii_offsets() {
+        let text = "let a;\nlet b;";
+        let index = LineIndex::new(text);
+
+        assert_eq!(index.line_count(), 2);
+        assert_eq!(
+            index.position(text, 11, PositionEncoding::Utf16),
+            Position::new(1, 4)
+        );
+        assert_eq!(
+            index.offset(text, Position::new(1, 4), PositionEncoding::Utf16),
+            11
+        );
+    }
+
+    #[test]
+    fn counts_columns_per_encoding() {
+        // "é" is 2 UTF-8 bytes / 1 UTF-16 unit; "😀" is 4 bytes / 2 units.
+        let text = "é😀x";
+        let index = LineIndex::new(text);
+        let x = text.find('x').unwrap();
+
+        assert_eq!(
+            index.position(text, x, PositionEncoding::Utf8),
+            Position::new(0, 6)
+        );
+        assert_eq!(
+            index.position(text, x, PositionEncoding::Utf16),
+            Position::new(0, 3)
+        );
+        assert_eq!(
+            index.position(text, x, PositionEncoding::Utf32),
+            Position::new(0, 2)
+        );
+        for (encoding, character) in [
+            (PositionEncoding::Utf8, 6),
+            (PositionEncoding::Utf16, 3),
+            (PositionEncoding::Utf32, 2),
+        ] {
+            assert_eq!(index.offset(text, Position::new(0, character), encoding), x);
+        }
+    }
+
+    #[test]
+    fn applies_edits_like_a_rebuild() {
+        let old = "a\r\nb\rc\nd";
+        let edits = [
+            (0..0, "\n"),
+            (1..1, "x"),
+            (2..3, ""),
+            (1..2, ""),
+            (4..5, "\n"),
+            (5..6, "\r\n\r"),
+            (0..old.len(), ""),
+            (old.len()..old.len(), "\r"),
+        ];
+        for (range, replacement) in edits {
+            let new = format!("{}{replacement}{}", &old[..range.start], &old[range.end..]);
+            let mut index = LineIndex::new(old);
+            index.apply_edit(
+                &new,
+                &TextEdit {
+                    start: range.start,
+                    old_end: range.end,
+                    new_end: range.start + replacement.len(),
+                },
+            );
+            assert_eq!(index, LineIndex::new(&new), "{new:?}");
+        }
+    }
+
+    #[test]
+    fn snaps_inside_surrogate_pair_and_clamps() {
+        let text = "😀\r\nab";
+        let index = LineIndex::new(text);
+
+        assert_eq!(
+            index.offset(text, Position::new(0, 1), PositionEncoding::Utf16),
+            0
+        );
+        assert_eq!(
+            index.offset(text, Position::new(0, 10), PositionEncoding::Utf16),
+            4
+        );
+        assert_eq!(
+            index.offset(text, Position::new(1, 1), PositionEncoding::Utf16),
+            7
+        );
+        assert_eq!(
+            index.offset(text, Position::new(9, 0), PositionEncoding::Utf16),
+            text.len()
+        );
+        assert_eq!(
+            index.position(text, 2, PositionEncoding::Utf16),
+            Position::new(0, 0)
+        );
+    }
+}
@@ -1,17 +1,6 @@
-mod lexer;
-mod token;
-// mod parser;
-// mod ast;
-// mod analyzer;
-mod server;
-
 use log::error;
 
-// use lexer::Lexer;
-// use parser::Parser;
-// use ast::AST;
-// use analyzer::Analyzer;
-// use server::Server;
+use rs_ts_lsp_server::server;
 
 #[tokio::main]
 async fn main() {
@@ -1,11 +1,3246 @@
-/// Parser module - Generates an Abstract Syntax Tree (AST) from tokens.
+//! Parser module — builds a lossless syntax tree from the lexer's tokens.
+//!
+//! The parser is recursive descent with precedence climbing for binary
+//! operators, and follows TypeScript's parser in what it accepts and in how
+//! it settles ambiguities such as `a < b > (c)`. It never gives up: a token
+//! that fits nowhere is reported and wrapped in an `Error` node, so every
+//! byte of the input ends up in the tree.
+
+use std::collections::HashSet;
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::ast::{AstNode, SourceFile};
+use crate::diagnostic::Diagnostic;
+use crate::jsx::LanguageVariant;
+use crate::lexer::{Lexer, LexerOptions, Scanner};
+use crate::symbol::Interner;
+use crate::syntax::{
+    Checkpoint, GreenNode, GreenNodeBuilder, GreenToken, Mark, NodeKind, SyntaxNode,
+};
+use crate::token::{OperatorPrecedence, SpannedToken};
+use crate::token_kind::{TokenKind, TokenKindSet};
+use crate::trivia::is_line_break;
+
+/// Tokens that end the construct being parsed rather than start a new one.
+/// An unexpected one is reported but left for an enclosing rule to consume.
+const RECOVERY: TokenKindSet = TokenKindSet::new(&[
+    TokenKind::Eof,
+    TokenKind::CloseParen,
+    TokenKind::CloseBracket,
+    TokenKind::CloseBrace,
+    TokenKind::Semicolon,
+    TokenKind::Comma,
+    TokenKind::Colon,
+    TokenKind::TemplateMiddle,
+    TokenKind::TemplateTail,
+]);
+
+/// Keywords that name a type on their own.
+const KEYWORD_TYPES: TokenKindSet = TokenKindSet::new(&[
+    TokenKind::Any,
+    TokenKind::Unknown,
+    TokenKind::Number,
+    TokenKind::BigInt,
+    TokenKind::Boolean,
+    TokenKind::String,
+    TokenKind::Symbol,
+    TokenKind::Object,
+    TokenKind::Never,
+    TokenKind::Undefined,
+    TokenKind::Void,
+    TokenKind::This,
+]);
+
+/// Modifiers a class member can carry.
+const CLASS_MEMBER_MODIFIERS: TokenKindSet = TokenKindSet::new(&[
+    TokenKind::Public,
+    TokenKind::Private,
+    TokenKind::Protected,
+    TokenKind::Static,
+    TokenKind::Readonly,
+    TokenKind::Abstract,
+    TokenKind::Override,
+    TokenKind::Declare,
+    TokenKind::Async,
+]);
+
+/// Modifiers a constructor parameter can carry.
+const PARAMETER_MODIFIERS: TokenKindSet = TokenKindSet::new(&[
+    TokenKind::Public,
+    TokenKind::Private,
+    TokenKind::Protected,
+    TokenKind::Readonly,
+    TokenKind::Override,
+]);
+
+/// How deeply statements, expressions, types and binding patterns may nest
+/// before the parser stops descending, which would overflow the stack.
+const MAX_DEPTH: usize = 1000;
+
+/// The result of parsing a document.
+#[derive(Debug, Clone)]
+pub struct Parse {
+    green: GreenNode,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Parse {
+    pub fn green(&self) -> &GreenNode {
+        &self.green
+    }
+
+    pub fn syntax(&self) -> SyntaxNode {
+        SyntaxNode::new_root(self.green.clone())
+    }
+
+    pub fn tree(&self) -> SourceFile {
+        SourceFile::cast(self.syntax()).expect("the root is a source file")
+    }
+
+    /// Lexer and parser diagnostics, in source order.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+}
+
+/// What the code around the current position allows.
+#[derive(Debug, Clone, Copy, Default)]
+struct Context {
+    /// In the initializer of a `for` statement, where `in` ends the
+    /// expression instead of being an operator.
+    disallow_in: bool,
+    /// In an async function, where `await` is an operator.
+    r#await: bool,
+    /// In a generator, where `yield` is an operator.
+    r#yield: bool,
+    /// In the branch of a conditional before its `:`, where
+    /// `a ? (b): c => d` could also be an arrow function returning `c`. One
+    /// is only taken if a `:` follows it, as in `a ? (b): c => d : e`.
+    when_true: bool,
+}
+
+/// Where to return to when a speculative parse fails.
+struct Snapshot {
+    pos: usize,
+    emitted: usize,
+    rescans: usize,
+    mark: Mark,
+    diagnostics: usize,
+    context: Context,
+}
+
+/// Tokens and lexer diagnostics replaced by re-scanning a token, kept to
+/// put them back when the parse that asked for the rescan is rewound.
+struct Rescan {
+    /// Where the replaced tokens started in `tokens`.
+    index: usize,
+    /// How many tokens replaced them.
+    len: usize,
+    tokens: Vec<SpannedToken>,
+    /// The source range the replaced diagnostics start in.
+    range: Range<usize>,
+    diagnostics: Vec<Diagnostic>,
+}
+
 pub struct Parser {
-    // TODO: Implement parser
+    source: Arc<str>,
+    tokens: Vec<SpannedToken>,
+    language_variant: LanguageVariant,
+    /// Re-scans tokens whose meaning the lexer guessed wrong, such as a `/`
+    /// that starts a regex where the lexer expected a division.
+    scanner: Scanner,
+    /// Rescans done so far, most recent last.
+    rescans: Vec<Rescan>,
+    /// Index in `tokens` of the current token, which is never trivia.
+    pos: usize,
+    /// Tokens before this index have been added to the tree. Trivia is added
+    /// lazily, right before the next token or node, so nodes start at their
+    /// first token instead of at the trivia in front of it.
+    emitted: usize,
+    builder: GreenNodeBuilder,
+    /// The lexer's diagnostics.
+    lexer_diagnostics: Vec<Diagnostic>,
+    diagnostics: Vec<Diagnostic>,
+    /// Errors found so far, counting the ones not reported because another
+    /// was already reported at the same place.
+    errors: usize,
+    context: Context,
+    /// How many [`Parser::nested`] rules are being parsed.
+    depth: usize,
+    /// Source offsets where parentheses turned out not to start an arrow
+    /// function; token indices shift with rescans. Without this, nested
+    /// parentheses that each have to be tried as parameters take
+    /// exponential time.
+    not_arrow_functions: HashSet<usize>,
 }
 
 impl Parser {
-    pub fn new() -> Self {
-        Parser {}
+    /// Create a parser for `source`.
+    pub fn new<S: Into<Arc<str>>>(source: S) -> Self {
+        Self::with_options(source, LexerOptions::default())
+    }
+
+    /// Create a parser that lexes `source` according to `options`.
+    pub fn with_options<S: Into<Arc<str>>>(source: S, options: LexerOptions) -> Self {
+        let source = source.into();
+        let mut lexer = Lexer::with_options(Arc::clone(&source), options);
+        let (tokens, diagnostics) = lexer.lex_with_diagnostics();
+        let interner = Arc::clone(lexer.interner());
+        Self::from_tokens(source, tokens, diagnostics, options, interner)
+    }
+
+    /// Create a parser over tokens already lexed from `source` with
+    /// `options`, such as the ones a document keeps up to date as it is
+    /// edited. `tokens` must be a complete lexer output ending in `Eof`;
+    /// `diagnostics` are the lexer's and are passed through to the result.
+    /// Tokens the parser re-scans intern their names into `interner`, which
+    /// should be the one `tokens` were lexed with.
+    pub fn from_tokens(
+        source: Arc<str>,
+        tokens: Vec<SpannedToken>,
+        diagnostics: Vec<Diagnostic>,
+        options: LexerOptions,
+        interner: Arc<Interner>,
+    ) -> Self {
+        let scanner = Scanner::with_options(Arc::clone(&source), options).with_interner(interner);
+        let mut parser = Self {
+            source,
+            tokens,
+            language_variant: options.language_variant,
+            scanner,
+            rescans: Vec::new(),
+            pos: 0,
+            emitted: 0,
+            builder: GreenNodeBuilder::new(),
+            lexer_diagnostics: diagnostics,
+            diagnostics: Vec::new(),
+            errors: 0,
+            context: Context::default(),
+            depth: 0,
+            not_arrow_functions: HashSet::new(),
+        };
+        parser.pos = parser.next_significant(0);
+        parser
+    }
+
+    /// Parse the whole document.
+    pub fn parse(mut self) -> Parse {
+        // Not `start_node`: leading trivia belongs inside the root
+        self.builder.start_node(NodeKind::SourceFile);
+        while !self.at(TokenKind::Eof) {
+            self.statement();
+        }
+        self.bump();
+        self.finish_node();
+
+        self.diagnostics.append(&mut self.lexer_diagnostics);
+        self.diagnostics.sort_by_key(|diagnostic| diagnostic.start);
+        Parse {
+            green: self.builder.finish(),
+            diagnostics: self.diagnostics,
+        }
+    }
+
+    // Tokens
+
+    /// The index of the first token at or after `index` that is not trivia.
+    fn next_significant(&self, mut index: usize) -> usize {
+        while index + 1 < self.tokens.len() && self.tokens[index].is_trivia() {
+            index += 1;
+        }
+        index
+    }
+
+    /// The index of the `n`th token after the current one, skipping trivia.
+    /// Past the end this is the index of `Eof`.
+    fn nth_index(&self, n: usize) -> usize {
+        let mut index = self.pos;
+        for _ in 0..n {
+            if index + 1 >= self.tokens.len() {
+                break;
+            }
+            index = self.next_significant(index + 1);
+        }
+        index
+    }
+
+    fn current(&self) -> TokenKind {
+        self.nth(0)
+    }
+
+    fn nth(&self, n: usize) -> TokenKind {
+        self.tokens[self.nth_index(n)].kind
+    }
+
+    fn at(&self, kind: TokenKind) -> bool {
+        self.current() == kind
+    }
+
+    /// The source text of the current token.
+    fn current_text(&self) -> &str {
+        let token = &self.tokens[self.pos];
+        &self.source[token.start..token.end]
+    }
+
+    /// Whether a line break separates the current token from the previous
+    /// one.
+    fn has_preceding_line_break(&self) -> bool {
+        self.line_break_before(self.pos)
+    }
+
+    /// Whether a line break separates the `n`th token from the one before.
+    fn nth_has_preceding_line_break(&self, n: usize) -> bool {
+        self.line_break_before(self.nth_index(n))
+    }
+
+    fn line_break_before(&self, index: usize) -> bool {
+        self.tokens[..index]
+            .iter()
+            .rev()
+            .take_while(|token| token.is_trivia())
+            .any(is_line_break)
+    }
+
+    // Building the tree
+
+    /// Add the trivia in front of the current token to the tree.
+    fn emit_trivia(&mut self) {
+        while self.emitted < self.pos {
+            let token = GreenToken::from_spanned(&self.tokens[self.emitted], &self.source);
+            self.builder.token(token);
+            self.emitted += 1;
+        }
+    }
+
+    /// Add the current token to the tree and move to the next one.
+    fn bump(&mut self) {
+        self.emit_trivia();
+        let token = GreenToken::from_spanned(&self.tokens[self.pos], &self.source);
+        self.builder.token(token);
+        self.emitted = self.pos + 1;
+        if self.pos + 1 < self.tokens.len() {
+            self.pos = self.next_significant(self.pos + 1);
+        }
+    }
+
+    fn eat(&mut self, kind: TokenKind) -> bool {
+        let eaten = self.at(kind);
+        if eaten {
+            self.bump();
+        }
+        eaten
+    }
+
+    /// Consume a token of `kind`, or report that it is missing.
+    fn expect(&mut self, kind: TokenKind) -> bool {
+        if self.eat(kind) {
+            return true;
+        }
+        self.error_expected(kind.text().unwrap_or_default());
+        false
+    }
+
+    fn at_greater_than(&self) -> bool {
+        self.current().is_punctuation() && self.current_text().starts_with('>')
+    }
+
+    /// Consume a `>`, re-scanning `>>`, `>=` and the like into a `>` and
+    /// the rest where type arguments end.
+    fn eat_greater_than(&mut self) -> bool {
+        if !self.at_greater_than() {
+            return false;
+        }
+        if !self.at(TokenKind::GreaterThan) {
+            self.rescan(Scanner::rescan_greater_token);
+        }
+        self.bump();
+        true
+    }
+
+    /// Re-scan the current token with `rescan` and splice the tokens that
+    /// change into `tokens`, keeping the old ones for `rewind`.
+    fn rescan(&mut self, rescan: impl FnOnce(&mut Scanner, &SpannedToken) -> SpannedToken) {
+        let index = self.pos;
+        let (tokens, end) = self.scanner.rescan_tokens(&self.tokens, index, rescan);
+        let range =
+            self.tokens[index].start..self.tokens.get(end).map_or(usize::MAX, |token| token.start);
+        let diagnostics = self.scanner.diagnostics().to_vec();
+        let diagnostics = self.replace_lexer_diagnostics(range.clone(), diagnostics);
+        let len = tokens.len();
+        let tokens = self.tokens.splice(index..end, tokens).collect();
+        self.rescans.push(Rescan {
+            index,
+            len,
+            tokens,
+            range,
+            diagnostics,
+        });
+    }
+
+    /// Replace the lexer diagnostics starting in `range` with
+    /// `diagnostics`, returning the old ones.
+    fn replace_lexer_diagnostics(
+        &mut self,
+        range: Range<usize>,
+        diagnostics: Vec<Diagnostic>,
+    ) -> Vec<Diagnostic> {
+        let old = self
+            .lexer_diagnostics
+            .extract_if(.., |diagnostic| range.contains(&diagnostic.start))
+            .collect();
+        self.lexer_diagnostics.extend(diagnostics);
+        old
+    }
+
+    fn expect_greater_than(&mut self) {
+        if !self.eat_greater_than() {
+            self.error_expected(">");
+        }
+    }
+
+    fn start_node(&mut self, kind: NodeKind) {
+        self.emit_trivia();
+        self.builder.start_node(kind);
+    }
+
+    fn checkpoint(&mut self) -> Checkpoint {
+        self.emit_trivia();
+        self.builder.checkpoint()
+    }
+
+    fn start_node_at(&mut self, checkpoint: Checkpoint, kind: NodeKind) {
+        self.builder.start_node_at(checkpoint, kind);
+    }
+
+    fn finish_node(&mut self) {
+        self.builder.finish_node();
+    }
+
+    /// A node holding just the current token.
+    fn token_node(&mut self, kind: NodeKind) {
+        self.start_node(kind);
+        self.bump();
+        self.finish_node();
+    }
+
+    // Diagnostics and recovery
+
+    /// Report a problem at the current token, unless one was just reported
+    /// there: after the first, further errors at the same place are noise.
+    fn error(&mut self, code: u32, message: impl Into<String>) {
+        self.errors += 1;
+        let token = &self.tokens[self.pos];
+        let start = token.start;
+        if self
+            .diagnostics
+            .last()
+            .is_some_and(|diagnostic| diagnostic.start == start)
+        {
+            return;
+        }
+        self.diagnostics
+            .push(Diagnostic::error(code, message, start, token.end));
+    }
+
+    fn error_expected(&mut self, what: &str) {
+        self.error(1005, format!("'{what}' expected."));
+    }
+
+    /// Report the current token, and skip it inside an `Error` node unless
+    /// an enclosing rule can use it.
+    fn error_and_recover(&mut self, code: u32, message: &str) {
+        self.error(code, message);
+        if !RECOVERY.contains(self.current()) {
+            self.token_node(NodeKind::Error);
+        }
+    }
+
+    /// Run `parse` one level deeper. Past [`MAX_DEPTH`] levels the current
+    /// token and the ones after it up to the bracket that closes the
+    /// enclosing construct are reported and skipped instead. Something is
+    /// always skipped before the end of the file, so loops over statements
+    /// or list elements still make progress.
+    fn nested(&mut self, parse: impl FnOnce(&mut Self)) {
+        if self.depth < MAX_DEPTH {
+            self.depth += 1;
+            parse(self);
+            self.depth -= 1;
+            return;
+        }
+
+        // tsc has no code for this
+        self.error(1000, "Nesting is too deep.");
+        if self.at(TokenKind::Eof) {
+            return;
+        }
+        self.start_node(NodeKind::Error);
+        let mut open = 0usize;
+        let mut first = true;
+        loop {
+            match self.current() {
+                TokenKind::OpenParen
+                | TokenKind::OpenBracket
+                | TokenKind::OpenBrace
+                | TokenKind::TemplateHead => open += 1,
+                TokenKind::CloseParen
+                | TokenKind::CloseBracket
+                | TokenKind::CloseBrace
+                | TokenKind::TemplateTail
+                    if open > 0 =>
+                {
+                    open -= 1;
+                }
+                TokenKind::CloseParen
+                | TokenKind::CloseBracket
+                | TokenKind::CloseBrace
+                | TokenKind::TemplateTail
+                    if first => {}
+                TokenKind::CloseParen
+                | TokenKind::CloseBracket
+                | TokenKind::CloseBrace
+                | TokenKind::TemplateTail
+                | TokenKind::Eof => break,
+                _ => {}
+            }
+            self.bump();
+            first = false;
+        }
+        self.finish_node();
+    }
+
+    // Speculation
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            pos: self.pos,
+            emitted: self.emitted,
+            rescans: self.rescans.len(),
+            mark: self.builder.mark(),
+            diagnostics: self.diagnostics.len(),
+            context: self.context,
+        }
+    }
+
+    fn rewind(&mut self, snapshot: Snapshot) {
+        self.pos = snapshot.pos;
+        self.emitted = snapshot.emitted;
+        while self.rescans.len() > snapshot.rescans {
+            let rescan = self
+                .rescans
+                .pop()
+                .expect("rescans is longer than the snapshot's");
+            self.tokens
+                .splice(rescan.index..rescan.index + rescan.len, rescan.tokens);
+            self.replace_lexer_diagnostics(rescan.range, rescan.diagnostics);
+        }
+        self.builder.rewind(snapshot.mark);
+        self.diagnostics.truncate(snapshot.diagnostics);
+        self.context = snapshot.context;
+    }
+
+    /// Run `parse` and keep what it built if it returns `true` without
+    /// reporting anything; otherwise act as if it never ran. A successful
+    /// `parse` may leave nodes open for the caller to finish.
+    fn try_parse(&mut self, parse: impl FnOnce(&mut Self) -> bool) -> bool {
+        let snapshot = self.snapshot();
+        let errors = self.errors;
+        if parse(self) && self.errors == errors {
+            true
+        } else {
+            self.rewind(snapshot);
+            false
+        }
     }
+
+    fn with_context(&mut self, context: Context, parse: impl FnOnce(&mut Self)) {
+        let outer = std::mem::replace(&mut self.context, context);
+        parse(self);
+        self.context = outer;
+    }
+
+    /// Run `parse` with `in` allowed as an operator again, as inside
+    /// brackets.
+    fn allowing_in(&mut self, parse: impl FnOnce(&mut Self)) {
+        let context = Context {
+            disallow_in: false,
+            when_true: false,
+            ..self.context
+        };
+        self.with_context(context, parse);
+    }
+
+    // Statements
+
+    fn statement(&mut self) {
+        self.nested(Self::statement_inner);
+    }
+
+    fn statement_inner(&mut self) {
+        let start = self.checkpoint();
+        if self.declaration(start) {
+            return;
+        }
+        match self.current() {
+            TokenKind::OpenBrace => self.block(),
+            TokenKind::Semicolon => self.token_node(NodeKind::EmptyStatement),
+            // `import(...)` and `import.meta` start expressions
+            TokenKind::Import if !matches!(self.nth(1), TokenKind::OpenParen | TokenKind::Dot) => {
+                self.import_declaration(start);
+            }
+            TokenKind::Export => self.export_declaration(),
+            TokenKind::If => self.if_statement(),
+            TokenKind::Do => self.do_statement(),
+            TokenKind::While => self.while_statement(),
+            TokenKind::For => self.for_statement(),
+            TokenKind::Continue => self.break_or_continue(NodeKind::ContinueStatement),
+            TokenKind::Break => self.break_or_continue(NodeKind::BreakStatement),
+            TokenKind::Return => self.return_statement(),
+            TokenKind::With => self.with_statement(),
+            TokenKind::Switch => self.switch_statement(),
+            TokenKind::Throw => self.throw_statement(),
+            TokenKind::Try => self.try_statement(),
+            TokenKind::Debugger => {
+                self.start_node(NodeKind::DebuggerStatement);
+                self.bump();
+                self.semicolon();
+                self.finish_node();
+            }
+            kind if is_identifier(kind) && self.nth(1) == TokenKind::Colon => {
+                self.start_node(NodeKind::LabeledStatement);
+                self.bump();
+                self.bump();
+                self.statement();
+                self.finish_node();
+            }
+            kind if is_start_of_expression(kind) => self.expression_statement(),
+            // Always consume something, or the statement loops would spin
+            _ => {
+                self.error(1128, "Declaration or statement expected.");
+                self.token_node(NodeKind::Error);
+            }
+        }
+    }
+
+    /// End a statement: at a `;`, or where automatic semicolon insertion
+    /// puts one, before a line break, a `}` or the end of the file.
+    fn semicolon(&mut self) {
+        if self.eat(TokenKind::Semicolon)
+            || self.at(TokenKind::CloseBrace)
+            || self.at(TokenKind::Eof)
+            || self.has_preceding_line_break()
+        {
+            return;
+        }
+        self.error_expected(";");
+    }
+
+    fn expression_statement(&mut self) {
+        self.start_node(NodeKind::ExpressionStatement);
+        self.expression();
+        self.semicolon();
+        self.finish_node();
+    }
+
+    fn block(&mut self) {
+        self.start_node(NodeKind::Block);
+        self.expect(TokenKind::OpenBrace);
+        while !self.at(TokenKind::CloseBrace) && !self.at(TokenKind::Eof) {
+            self.statement();
+        }
+        self.expect(TokenKind::CloseBrace);
+        self.finish_node();
+    }
+
+    /// Whether `let`, `using` or `await` starts a declaration here rather
+    /// than an expression such as `let = 1` or `using(x)`.
+    fn at_variable_declaration_list(&self) -> bool {
+        match self.current() {
+            TokenKind::Var | TokenKind::Const => true,
+            TokenKind::Let => {
+                is_start_of_binding(self.nth(1)) && self.nth(1) != TokenKind::DotDotDot
+            }
+            TokenKind::Using => is_identifier(self.nth(1)) && !self.nth_has_preceding_line_break(1),
+            TokenKind::Await => {
+                self.nth(1) == TokenKind::Using
+                    && !self.nth_has_preceding_line_break(1)
+                    && is_identifier(self.nth(2))
+                    && !self.nth_has_preceding_line_break(2)
+            }
+            _ => false,
+        }
+    }
+
+    /// A declaration that `export` can precede, as a node that starts at
+    /// `start` so that it holds the modifiers parsed before it. Returns
+    /// `false` without consuming anything if none starts here.
+    fn declaration(&mut self, start: Checkpoint) -> bool {
+        match self.current() {
+            TokenKind::Var | TokenKind::Const => self.variable_statement(start),
+            TokenKind::Let | TokenKind::Using | TokenKind::Await
+                if self.at_variable_declaration_list() =>
+            {
+                self.variable_statement(start);
+            }
+            TokenKind::Function => self.function_declaration(start, false),
+            TokenKind::Async
+                if self.nth(1) == TokenKind::Function && !self.nth_has_preceding_line_break(1) =>
+            {
+                self.function_declaration(start, false);
+            }
+            TokenKind::Class => self.class(start, NodeKind::ClassDeclaration),
+            TokenKind::Abstract
+                if self.nth(1) == TokenKind::Class && !self.nth_has_preceding_line_break(1) =>
+            {
+                self.class(start, NodeKind::ClassDeclaration);
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn variable_statement(&mut self, start: Checkpoint) {
+        self.start_node_at(start, NodeKind::VariableStatement);
+        self.variable_declaration_list();
+        self.semicolon();
+        self.finish_node();
+    }
+
+    /// The keyword and the declarations after it, without the `;`.
+    fn variable_declaration_list(&mut self) {
+        self.start_node(NodeKind::VariableDeclarationList);
+        if self.at(TokenKind::Await) {
+            self.bump();
+        }
+        self.bump();
+        loop {
+            self.start_node(NodeKind::VariableDeclaration);
+            self.binding_name();
+            // The definite assignment assertion in `let x!: T`
+            if !self.has_preceding_line_break() {
+                self.eat(TokenKind::Bang);
+            }
+            if self.at(TokenKind::Colon) {
+                self.type_annotation();
+            }
+            // Unlike a parameter's, this initializer keeps the `in` context,
+            // so that `for (var x = a in b)` stops before `in`.
+            if self.eat(TokenKind::Equals) {
+                self.assignment_expression();
+            }
+            self.finish_node();
+            if !self.eat(TokenKind::Comma) {
+                break;
+            }
+        }
+        self.finish_node();
+    }
+
+    /// `( expression )` after `if`, `while` and the like.
+    fn parenthesized_condition(&mut self) {
+        self.expect(TokenKind::OpenParen);
+        self.allowing_in(Self::expression);
+        self.expect(TokenKind::CloseParen);
+    }
+
+    fn if_statement(&mut self) {
+        self.start_node(NodeKind::IfStatement);
+        self.bump();
+        self.parenthesized_condition();
+        self.statement();
+        if self.eat(TokenKind::Else) {
+            self.statement();
+        }
+        self.finish_node();
+    }
+
+    fn do_statement(&mut self) {
+        self.start_node(NodeKind::DoStatement);
+        self.bump();
+        self.statement();
+        self.expect(TokenKind::While);
+        self.parenthesized_condition();
+        // A `;` is inserted after `do ... while (x)` even on the same line
+        self.eat(TokenKind::Semicolon);
+        self.finish_node();
+    }
+
+    fn while_statement(&mut self) {
+        self.start_node(NodeKind::WhileStatement);
+        self.bump();
+        self.parenthesized_condition();
+        self.statement();
+        self.finish_node();
+    }
+
+    /// `for (;;)`, `for (x in y)`, `for (x of y)` and `for await (x of y)`.
+    /// Which one it is shows only after the initializer, so the node is
+    /// started at a checkpoint.
+    fn for_statement(&mut self) {
+        let checkpoint = self.checkpoint();
+        self.bump();
+        self.eat(TokenKind::Await);
+        self.expect(TokenKind::OpenParen);
+
+        let context = Context {
+            disallow_in: true,
+            ..self.context
+        };
+        self.with_context(context, |parser| {
+            if parser.at_variable_declaration_list() {
+                parser.variable_declaration_list();
+            } else if !parser.at(TokenKind::Semicolon) {
+                parser.expression();
+            }
+        });
+
+        match self.current() {
+            TokenKind::Of => {
+                self.start_node_at(checkpoint, NodeKind::ForOfStatement);
+                self.bump();
+                self.allowing_in(Self::assignment_expression);
+            }
+            TokenKind::In => {
+                self.start_node_at(checkpoint, NodeKind::ForInStatement);
+                self.bump();
+                self.allowing_in(Self::expression);
+            }
+            _ => {
+                self.start_node_at(checkpoint, NodeKind::ForStatement);
+                self.expect(TokenKind::Semicolon);
+                if !self.at(TokenKind::Semicolon) {
+                    self.allowing_in(Self::expression);
+                }
+                self.expect(TokenKind::Semicolon);
+                if !self.at(TokenKind::CloseParen) {
+                    self.allowing_in(Self::expression);
+                }
+            }
+        }
+        self.expect(TokenKind::CloseParen);
+        self.statement();
+        self.finish_node();
+    }
+
+    /// `break` or `continue`, with a label only on the same line.
+    fn break_or_continue(&mut self, node: NodeKind) {
+        self.start_node(node);
+        self.bump();
+        if is_identifier(self.current()) && !self.has_preceding_line_break() {
+            self.bump();
+        }
+        self.semicolon();
+        self.finish_node();
+    }
+
+    /// `return`, whose value must start on the same line: `return` followed
+    /// by a line break returns nothing.
+    fn return_statement(&mut self) {
+        self.start_node(NodeKind::ReturnStatement);
+        self.bump();
+        if !self.at(TokenKind::Semicolon)
+            && !self.at(TokenKind::CloseBrace)
+            && !self.at(TokenKind::Eof)
+            && !self.has_preceding_line_break()
+        {
+            self.allowing_in(Self::expression);
+        }
+        self.semicolon();
+        self.finish_node();
+    }
+
+    fn with_statement(&mut self) {
+        self.start_node(NodeKind::WithStatement);
+        self.bump();
+        self.parenthesized_condition();
+        self.statement();
+        self.finish_node();
+    }
+
+    fn switch_statement(&mut self) {
+        self.start_node(NodeKind::SwitchStatement);
+        self.bump();
+        self.parenthesized_condition();
+
+        self.start_node(NodeKind::CaseBlock);
+        self.expect(TokenKind::OpenBrace);
+        while !self.at(TokenKind::CloseBrace) && !self.at(TokenKind::Eof) {
+            match self.current() {
+                TokenKind::Case => {
+                    self.start_node(NodeKind::CaseClause);
+                    self.bump();
+                    self.allowing_in(Self::expression);
+                }
+                TokenKind::Default => {
+                    self.start_node(NodeKind::DefaultClause);
+                    self.bump();
+                }
+                _ => {
+                    self.error_expected("case");
+                    self.token_node(NodeKind::Error);
+                    continue;
+                }
+            }
+            self.expect(TokenKind::Colon);
+            while !matches!(
+                self.current(),
+                TokenKind::Case | TokenKind::Default | TokenKind::CloseBrace | TokenKind::Eof
+            ) {
+                self.statement();
+            }
+            self.finish_node();
+        }
+        self.expect(TokenKind::CloseBrace);
+        self.finish_node();
+
+        self.finish_node();
+    }
+
+    /// `throw`, whose value must start on the same line. Unlike `return`,
+    /// a line break is an error rather than the end of the statement.
+    fn throw_statement(&mut self) {
+        self.start_node(NodeKind::ThrowStatement);
+        self.bump();
+        if self.has_preceding_line_break() {
+            self.error(1142, "Line break not permitted here.");
+        }
+        self.allowing_in(Self::expression);
+        self.semicolon();
+        self.finish_node();
+    }
+
+    fn try_statement(&mut self) {
+        self.start_node(NodeKind::TryStatement);
+        self.bump();
+        self.block();
+        let has_catch = self.at(TokenKind::Catch);
+        if has_catch {
+            self.start_node(NodeKind::CatchClause);
+            self.bump();
+            // The binding is optional, as in `catch { ... }`
+            if self.eat(TokenKind::OpenParen) {
+                self.start_node(NodeKind::VariableDeclaration);
+                self.binding_name();
+                if self.at(TokenKind::Colon) {
+                    self.type_annotation();
+                }
+                self.finish_node();
+                self.expect(TokenKind::CloseParen);
+            }
+            self.block();
+            self.finish_node();
+        }
+        if self.eat(TokenKind::Finally) {
+            self.block();
+        } else if !has_catch {
+            self.error_expected("catch");
+        }
+        self.finish_node();
+    }
+
+    // Declarations
+
+    /// `function` and `async function` declarations. A declaration without
+    /// a body is an overload signature. Only the one after `export default`
+    /// may be anonymous.
+    fn function_declaration(&mut self, start: Checkpoint, is_default: bool) {
+        self.start_node_at(start, NodeKind::FunctionDeclaration);
+        let is_async = self.eat(TokenKind::Async);
+        self.bump();
+        let is_generator = self.eat(TokenKind::Asterisk);
+        if is_identifier(self.current()) {
+            self.bump();
+        } else if !is_default {
+            self.error(1003, "Identifier expected.");
+        }
+        self.function_rest(is_async, is_generator, true);
+        self.finish_node();
+    }
+
+    /// `import x, { y as z } from "m"`, `import * as ns from "m"`,
+    /// `import "m"` and their `type` forms, or `import x = require("m")`.
+    fn import_declaration(&mut self, start: Checkpoint) {
+        self.bump();
+        // `import type from "m"` imports a default export named `type`
+        let is_type_only = self.at(TokenKind::Type)
+            && match self.nth(1) {
+                TokenKind::OpenBrace | TokenKind::Asterisk => true,
+                TokenKind::From => self.nth(2) == TokenKind::From,
+                next => is_identifier(next),
+            };
+        if is_type_only {
+            self.bump();
+        }
+
+        if is_identifier(self.current()) && self.nth(1) == TokenKind::Equals {
+            self.start_node_at(start, NodeKind::ImportEqualsDeclaration);
+            self.bump();
+            self.bump();
+            if self.at(TokenKind::Require) && self.nth(1) == TokenKind::OpenParen {
+                self.start_node(NodeKind::ExternalModuleReference);
+                self.bump();
+                self.bump();
+                self.module_specifier();
+                self.expect(TokenKind::CloseParen);
+                self.finish_node();
+            } else {
+                self.entity_name();
+            }
+            self.semicolon();
+            self.finish_node();
+            return;
+        }
+
+        self.start_node_at(start, NodeKind::ImportDeclaration);
+        if !self.at(TokenKind::StringLiteral) {
+            self.import_clause();
+            self.expect(TokenKind::From);
+        }
+        self.module_specifier();
+        self.import_attributes();
+        self.semicolon();
+        self.finish_node();
+    }
+
+    /// The bindings between `import` and `from`.
+    fn import_clause(&mut self) {
+        self.start_node(NodeKind::ImportClause);
+        if is_identifier(self.current()) {
+            self.bump();
+            if !self.eat(TokenKind::Comma) {
+                self.finish_node();
+                return;
+            }
+        }
+        match self.current() {
+            TokenKind::Asterisk => {
+                self.start_node(NodeKind::NamespaceImport);
+                self.bump();
+                self.expect(TokenKind::As);
+                if is_identifier(self.current()) {
+                    self.bump();
+                } else {
+                    self.error(1003, "Identifier expected.");
+                }
+                self.finish_node();
+            }
+            TokenKind::OpenBrace => {
+                self.named_imports_or_exports(NodeKind::NamedImports, NodeKind::ImportSpecifier);
+            }
+            _ => self.error_expected("{"),
+        }
+        self.finish_node();
+    }
+
+    /// `{ a, type b, c as d, "e" as f }` after `import` or `export`.
+    fn named_imports_or_exports(&mut self, node: NodeKind, specifier: NodeKind) {
+        self.start_node(node);
+        self.bump();
+        while !self.at(TokenKind::CloseBrace) && !self.at(TokenKind::Eof) {
+            self.start_node(specifier);
+            // `type` is a name of its own in `{ type }` and `{ type as t }`
+            let is_type_only = self.at(TokenKind::Type)
+                && is_module_export_name(self.nth(1))
+                && !(self.nth(1) == TokenKind::As
+                    && is_module_export_name(self.nth(2))
+                    && !is_module_export_name(self.nth(3)));
+            if is_type_only {
+                self.bump();
+            }
+            self.module_export_name();
+            if self.eat(TokenKind::As) {
+                self.module_export_name();
+            }
+            self.finish_node();
+            if !self.eat(TokenKind::Comma) {
+                break;
+            }
+        }
+        self.expect(TokenKind::CloseBrace);
+        self.finish_node();
+    }
+
+    /// A name in an import or export specifier: any identifier or keyword,
+    /// or a string.
+    fn module_export_name(&mut self) {
+        if is_module_export_name(self.current()) {
+            self.bump();
+        } else {
+            self.error(1003, "Identifier expected.");
+        }
+    }
+
+    /// The string naming a module, as in `from "m"`.
+    fn module_specifier(&mut self) {
+        if !self.eat(TokenKind::StringLiteral) {
+            self.error(1141, "String literal expected.");
+        }
+    }
+
+    /// `with { type: "json" }` after a module specifier, if present.
+    fn import_attributes(&mut self) {
+        if !self.at(TokenKind::With) || self.has_preceding_line_break() {
+            return;
+        }
+        self.start_node(NodeKind::ImportAttributes);
+        self.bump();
+        self.expect(TokenKind::OpenBrace);
+        while !self.at(TokenKind::CloseBrace) && !self.at(TokenKind::Eof) {
+            self.start_node(NodeKind::ImportAttribute);
+            if matches!(self.current(), TokenKind::StringLiteral)
+                || is_identifier_or_keyword(self.current())
+            {
+                self.bump();
+            } else {
+                self.error(1003, "Identifier expected.");
+            }
+            self.expect(TokenKind::Colon);
+            self.allowing_in(Self::assignment_expression);
+            self.finish_node();
+            if !self.eat(TokenKind::Comma) {
+                break;
+            }
+        }
+        self.expect(TokenKind::CloseBrace);
+        self.finish_node();
+    }
+
+    /// Everything that starts with `export`: re-exports and export lists,
+    /// `export =`, `export default`, and declarations with an `export`
+    /// modifier, which become part of the declaration's node.
+    fn export_declaration(&mut self) {
+        let start = self.checkpoint();
+        self.bump();
+        match self.current() {
+            TokenKind::Equals => {
+                self.start_node_at(start, NodeKind::ExportAssignment);
+                self.bump();
+                self.allowing_in(Self::assignment_expression);
+                self.semicolon();
+                self.finish_node();
+            }
+            TokenKind::Asterisk | TokenKind::OpenBrace => self.export_list(start),
+            TokenKind::Type
+                if matches!(self.nth(1), TokenKind::Asterisk | TokenKind::OpenBrace) =>
+            {
+                self.bump();
+                self.export_list(start);
+            }
+            TokenKind::Default => {
+                self.bump();
+                match self.current() {
+                    TokenKind::Function => self.function_declaration(start, true),
+                    TokenKind::Async
+                        if self.nth(1) == TokenKind::Function
+                            && !self.nth_has_preceding_line_break(1) =>
+                    {
+                        self.function_declaration(start, true);
+                    }
+                    TokenKind::Class => self.class(start, NodeKind::ClassDeclaration),
+                    TokenKind::Abstract
+                        if self.nth(1) == TokenKind::Class
+                            && !self.nth_has_preceding_line_break(1) =>
+                    {
+                        self.class(start, NodeKind::ClassDeclaration);
+                    }
+                    _ => {
+                        self.start_node_at(start, NodeKind::ExportAssignment);
+                        self.allowing_in(Self::assignment_expression);
+                        self.semicolon();
+                        self.finish_node();
+                    }
+                }
+            }
+            TokenKind::Import => self.import_declaration(start),
+            _ => {
+                if !self.declaration(start) {
+                    self.start_node_at(start, NodeKind::Error);
+                    self.error(1128, "Declaration or statement expected.");
+                    self.finish_node();
+                }
+            }
+        }
+    }
+
+    /// `export * from "m"`, `export * as ns from "m"` and
+    /// `export { a as b } from "m"`, from the `*` or `{` on. Only a list
+    /// can leave out the `from`.
+    fn export_list(&mut self, start: Checkpoint) {
+        self.start_node_at(start, NodeKind::ExportDeclaration);
+        let needs_from = self.at(TokenKind::Asterisk);
+        if needs_from {
+            if self.nth(1) == TokenKind::As {
+                self.start_node(NodeKind::NamespaceExport);
+                self.bump();
+                self.bump();
+                self.module_export_name();
+                self.finish_node();
+            } else {
+                self.bump();
+            }
+        } else {
+            self.named_imports_or_exports(NodeKind::NamedExports, NodeKind::ExportSpecifier);
+        }
+        if self.eat(TokenKind::From) {
+            self.module_specifier();
+            self.import_attributes();
+        } else if needs_from {
+            self.expect(TokenKind::From);
+        }
+        self.semicolon();
+        self.finish_node();
+    }
+
+    /// A class declaration or expression, modifiers included.
+    fn class(&mut self, start: Checkpoint, node: NodeKind) {
+        self.start_node_at(start, node);
+        self.eat(TokenKind::Abstract);
+        self.bump();
+        // `class implements I {}` is an anonymous class, not one named
+        // `implements`
+        if is_identifier(self.current()) && !matches!(self.current(), TokenKind::Implements) {
+            self.bump();
+        }
+        if self.at(TokenKind::LessThan) {
+            self.type_parameter_list();
+        }
+        while matches!(self.current(), TokenKind::Extends | TokenKind::Implements) {
+            self.heritage_clause();
+        }
+
+        self.expect(TokenKind::OpenBrace);
+        while !self.at(TokenKind::CloseBrace) && !self.at(TokenKind::Eof) {
+            let start = self.pos;
+            self.class_member();
+            if self.pos == start {
+                self.token_node(NodeKind::Error);
+            }
+        }
+        self.expect(TokenKind::CloseBrace);
+        self.finish_node();
+    }
+
+    /// `extends Base<T>` or `implements A, B<T>`.
+    fn heritage_clause(&mut self) {
+        self.start_node(NodeKind::HeritageClause);
+        self.bump();
+        loop {
+            let checkpoint = self.checkpoint();
+            self.left_hand_side_expression();
+            // Type arguments before the class body look like a comparison
+            // to the expression parser, so they are parsed here instead
+            if self.at(TokenKind::LessThan) {
+                self.start_node_at(checkpoint, NodeKind::ExpressionWithTypeArguments);
+                self.type_argument_list();
+                self.finish_node();
+            }
+            if !self.eat(TokenKind::Comma) {
+                break;
+            }
+        }
+        self.finish_node();
+    }
+
+    fn class_member(&mut self) {
+        if self.at(TokenKind::Semicolon) {
+            self.token_node(NodeKind::SemicolonClassElement);
+            return;
+        }
+        if self.at(TokenKind::Static) && self.nth(1) == TokenKind::OpenBrace {
+            self.start_node(NodeKind::ClassStaticBlockDeclaration);
+            self.bump();
+            self.with_context(Context::default(), Self::block);
+            self.finish_node();
+            return;
+        }
+
+        let checkpoint = self.checkpoint();
+        let mut is_async = false;
+        while CLASS_MEMBER_MODIFIERS.contains(self.current()) && self.next_can_follow_modifier() {
+            is_async |= self.at(TokenKind::Async);
+            self.bump();
+        }
+
+        if self.at(TokenKind::OpenBracket) && self.at_index_signature() {
+            self.start_node_at(checkpoint, NodeKind::IndexSignature);
+            self.index_signature_rest();
+            self.semicolon();
+            self.finish_node();
+            return;
+        }
+        if self.at(TokenKind::Constructor) && self.nth(1) == TokenKind::OpenParen {
+            self.start_node_at(checkpoint, NodeKind::Constructor);
+            self.bump();
+            self.function_rest(false, false, true);
+            self.finish_node();
+            return;
+        }
+
+        let is_generator = self.eat(TokenKind::Asterisk);
+        let accessor = match self.current() {
+            TokenKind::Get if is_start_of_property_name(self.nth(1)) => Some(NodeKind::GetAccessor),
+            TokenKind::Set if is_start_of_property_name(self.nth(1)) => Some(NodeKind::SetAccessor),
+            _ => None,
+        };
+        if accessor.is_some() {
+            self.bump();
+        }
+        if !self.property_name() {
+            self.error(
+                1068,
+                "Unexpected token. A constructor, method, accessor, or property was expected.",
+            );
+            return;
+        }
+
+        if let Some(accessor) = accessor {
+            self.start_node_at(checkpoint, accessor);
+            self.function_rest(false, false, true);
+        } else {
+            // Optional members, and the definite assignment assertion in
+            // `x!: T`
+            if !self.eat(TokenKind::Question) && !self.has_preceding_line_break() {
+                self.eat(TokenKind::Bang);
+            }
+            if is_generator || matches!(self.current(), TokenKind::OpenParen | TokenKind::LessThan)
+            {
+                self.start_node_at(checkpoint, NodeKind::MethodDeclaration);
+                self.function_rest(is_async, is_generator, true);
+            } else {
+                self.start_node_at(checkpoint, NodeKind::PropertyDeclaration);
+                if self.at(TokenKind::Colon) {
+                    self.type_annotation();
+                }
+                self.with_context(Context::default(), Self::initializer);
+                self.semicolon();
+            }
+        }
+        self.finish_node();
+    }
+
+    /// Whether the token after a modifier keyword lets it be a modifier.
+    /// Otherwise the keyword is the member's name, as in `static() {}` or
+    /// `readonly: boolean`.
+    fn next_can_follow_modifier(&self) -> bool {
+        let next = self.nth(1);
+        !self.nth_has_preceding_line_break(1)
+            && (is_start_of_property_name(next)
+                || matches!(next, TokenKind::Asterisk | TokenKind::OpenBrace))
+    }
+
+    /// Whether `[` starts an index signature rather than a computed name.
+    fn at_index_signature(&self) -> bool {
+        is_identifier(self.nth(1)) && self.nth(2) == TokenKind::Colon
+    }
+
+    /// `[key: K]: V`, from the `[` on.
+    fn index_signature_rest(&mut self) {
+        self.bump();
+        self.start_node(NodeKind::Parameter);
+        self.bump();
+        self.type_annotation();
+        self.finish_node();
+        self.expect(TokenKind::CloseBracket);
+        if self.at(TokenKind::Colon) {
+            self.type_annotation();
+        }
+    }
+
+    // Expressions
+
+    /// An expression, commas included.
+    fn expression(&mut self) {
+        let checkpoint = self.checkpoint();
+        self.assignment_expression();
+        while self.at(TokenKind::Comma) {
+            self.start_node_at(checkpoint, NodeKind::BinaryExpression);
+            self.bump();
+            self.assignment_expression();
+            self.finish_node();
+        }
+    }
+
+    /// An expression without a top-level comma: assignments, arrow
+    /// functions, `yield` and conditionals.
+    fn assignment_expression(&mut self) {
+        self.nested(Self::assignment_expression_inner);
+    }
+
+    fn assignment_expression_inner(&mut self) {
+        if self.is_yield_expression() {
+            self.yield_expression();
+            return;
+        }
+        if self.try_arrow_function() {
+            return;
+        }
+        if self.at_simple_arrow_function() {
+            self.simple_arrow_function();
+            return;
+        }
+
+        let checkpoint = self.checkpoint();
+        self.binary_expression(checkpoint, OperatorPrecedence::Assignment);
+        if self.current().is_assignment_operator() {
+            self.start_node_at(checkpoint, NodeKind::BinaryExpression);
+            self.bump();
+            self.assignment_expression();
+            self.finish_node();
+        } else if self.at(TokenKind::Question) {
+            self.start_node_at(checkpoint, NodeKind::ConditionalExpression);
+            self.bump();
+            let context = Context {
+                disallow_in: false,
+                when_true: true,
+                ..self.context
+            };
+            self.with_context(context, Self::assignment_expression);
+            self.expect(TokenKind::Colon);
+            self.assignment_expression();
+            self.finish_node();
+        }
+    }
+
+    /// A unary expression followed by binary operators that bind tighter
+    /// than `min`, all wrapped from `checkpoint` on.
+    fn binary_expression(&mut self, checkpoint: Checkpoint, min: OperatorPrecedence) {
+        self.unary_expression();
+        loop {
+            let kind = self.current();
+            let Some(precedence) = kind.binary_operator_precedence() else {
+                break;
+            };
+            // `**` is right-associative, everything else left-associative
+            let binds = if kind == TokenKind::AsteriskAsterisk {
+                precedence >= min
+            } else {
+                precedence > min
+            };
+            if !binds || (kind == TokenKind::In && self.context.disallow_in) {
+                break;
+            }
+
+            if matches!(kind, TokenKind::As | TokenKind::Satisfies) {
+                if self.has_preceding_line_break() {
+                    break;
+                }
+                let node = if kind == TokenKind::As {
+                    NodeKind::AsExpression
+                } else {
+                    NodeKind::SatisfiesExpression
+                };
+                self.start_node_at(checkpoint, node);
+                self.bump();
+                if self.at(TokenKind::Const) {
+                    self.token_node(NodeKind::TypeReference);
+                } else {
+                    self.ty();
+                }
+                self.finish_node();
+                continue;
+            }
+
+            self.start_node_at(checkpoint, NodeKind::BinaryExpression);
+            self.bump();
+            let right = self.checkpoint();
+            self.binary_expression(right, precedence);
+            self.finish_node();
+        }
+    }
+
+    fn unary_expression(&mut self) {
+        self.nested(Self::unary_expression_inner);
+    }
+
+    fn unary_expression_inner(&mut self) {
+        let node = match self.current() {
+            TokenKind::Plus
+            | TokenKind::Minus
+            | TokenKind::Tilde
+            | TokenKind::Bang
+            | TokenKind::PlusPlus
+            | TokenKind::MinusMinus => NodeKind::PrefixUnaryExpression,
+            TokenKind::Delete => NodeKind::DeleteExpression,
+            TokenKind::TypeOf => NodeKind::TypeOfExpression,
+            TokenKind::Void => NodeKind::VoidExpression,
+            TokenKind::Await if self.is_await_expression() => NodeKind::AwaitExpression,
+            TokenKind::LessThan if self.language_variant == LanguageVariant::Standard => {
+                self.start_node(NodeKind::TypeAssertionExpression);
+                self.bump();
+                self.ty();
+                self.expect_greater_than();
+                self.unary_expression();
+                self.finish_node();
+                return;
+            }
+            _ => {
+                let checkpoint = self.checkpoint();
+                self.left_hand_side_expression();
+                if matches!(self.current(), TokenKind::PlusPlus | TokenKind::MinusMinus)
+                    && !self.has_preceding_line_break()
+                {
+                    self.start_node_at(checkpoint, NodeKind::PostfixUnaryExpression);
+                    self.bump();
+                    self.finish_node();
+                }
+                return;
+            }
+        };
+
+        self.start_node(node);
+        self.bump();
+        self.unary_expression();
+        self.finish_node();
+    }
+
+    /// `await x` outside an async function is still parsed as an `await`
+    /// expression when it clearly is one, as TypeScript does, so the error
+    /// can say what is wrong.
+    fn is_await_expression(&self) -> bool {
+        self.context.r#await || self.next_starts_operand_on_same_line()
+    }
+
+    fn is_yield_expression(&self) -> bool {
+        self.at(TokenKind::Yield)
+            && (self.context.r#yield || self.next_starts_operand_on_same_line())
+    }
+
+    fn next_starts_operand_on_same_line(&self) -> bool {
+        let next = self.nth(1);
+        (next == TokenKind::Identifier || next.is_keyword() || next.is_literal())
+            && !self.nth_has_preceding_line_break(1)
+    }
+
+    fn yield_expression(&mut self) {
+        self.start_node(NodeKind::YieldExpression);
+        self.bump();
+        if !self.has_preceding_line_break()
+            && (self.at(TokenKind::Asterisk) || is_start_of_expression(self.current()))
+        {
+            self.eat(TokenKind::Asterisk);
+            self.assignment_expression();
+        }
+        self.finish_node();
+    }
+
+    fn left_hand_side_expression(&mut self) {
+        let checkpoint = self.checkpoint();
+        if self.at(TokenKind::New) {
+            self.new_expression();
+        } else {
+            self.primary_expression();
+        }
+        self.member_or_call_rest(checkpoint, true);
+    }
+
+    fn new_expression(&mut self) {
+        if self.nth(1) == TokenKind::Dot {
+            self.meta_property();
+            return;
+        }
+
+        self.start_node(NodeKind::NewExpression);
+        self.bump();
+        let callee = self.checkpoint();
+        if self.at(TokenKind::New) {
+            self.new_expression();
+        } else {
+            self.primary_expression();
+        }
+        self.member_or_call_rest(callee, false);
+        if self.at(TokenKind::LessThan) {
+            self.try_type_arguments_in_expression();
+        }
+        if self.at(TokenKind::OpenParen) {
+            self.argument_list();
+        }
+        self.finish_node();
+    }
+
+    /// `new.target` or `import.meta`.
+    fn meta_property(&mut self) {
+        self.start_node(NodeKind::MetaProperty);
+        self.bump();
+        self.bump();
+        self.property_name_token();
+        self.finish_node();
+    }
+
+    /// Property accesses, element accesses, calls, tagged templates and
+    /// non-null assertions after the expression that starts at
+    /// `checkpoint`. The callee of `new` takes no calls: the first argument
+    /// list belongs to `new` itself.
+    fn member_or_call_rest(&mut self, checkpoint: Checkpoint, allow_calls: bool) {
+        loop {
+            match self.current() {
+                TokenKind::Dot => {
+                    self.start_node_at(checkpoint, NodeKind::PropertyAccessExpression);
+                    self.bump();
+                    self.property_name_token();
+                    self.finish_node();
+                }
+                TokenKind::QuestionDot if allow_calls => match self.nth(1) {
+                    TokenKind::OpenParen | TokenKind::LessThan => {
+                        self.start_node_at(checkpoint, NodeKind::CallExpression);
+                        self.bump();
+                        if self.at(TokenKind::LessThan) {
+                            self.type_argument_list();
+                        }
+                        self.argument_list();
+                        self.finish_node();
+                    }
+                    TokenKind::OpenBracket => {
+                        self.start_node_at(checkpoint, NodeKind::ElementAccessExpression);
+                        self.bump();
+                        self.element_access();
+                        self.finish_node();
+                    }
+                    _ => {
+                        self.start_node_at(checkpoint, NodeKind::PropertyAccessExpression);
+                        self.bump();
+                        self.property_name_token();
+                        self.finish_node();
+                    }
+                },
+                TokenKind::OpenBracket => {
+                    self.start_node_at(checkpoint, NodeKind::ElementAccessExpression);
+                    self.element_access();
+                    self.finish_node();
+                }
+                TokenKind::Bang if !self.has_preceding_line_break() => {
+                    self.start_node_at(checkpoint, NodeKind::NonNullExpression);
+                    self.bump();
+                    self.finish_node();
+                }
+                TokenKind::NoSubstitutionTemplateLiteral | TokenKind::TemplateHead => {
+                    self.start_node_at(checkpoint, NodeKind::TaggedTemplateExpression);
+                    self.template_literal();
+                    self.finish_node();
+                }
+                TokenKind::OpenParen if allow_calls => {
+                    self.start_node_at(checkpoint, NodeKind::CallExpression);
+                    self.argument_list();
+                    self.finish_node();
+                }
+                TokenKind::LessThan if allow_calls => {
+                    if !self.try_type_arguments_in_expression() {
+                        break;
+                    }
+                    match self.current() {
+                        TokenKind::OpenParen => {
+                            self.start_node_at(checkpoint, NodeKind::CallExpression);
+                            self.argument_list();
+                        }
+                        TokenKind::NoSubstitutionTemplateLiteral | TokenKind::TemplateHead => {
+                            self.start_node_at(checkpoint, NodeKind::TaggedTemplateExpression);
+                            self.template_literal();
+                        }
+                        _ => self.start_node_at(checkpoint, NodeKind::ExpressionWithTypeArguments),
+                    }
+                    self.finish_node();
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// `[index]`, after the expression and any `?.`.
+    fn element_access(&mut self) {
+        self.bump();
+        self.allowing_in(Self::expression);
+        self.expect(TokenKind::CloseBracket);
+    }
+
+    /// The name after `.` or `?.`: any identifier or keyword, or a private
+    /// name.
+    fn property_name_token(&mut self) {
+        let kind = self.current();
+        if is_identifier_or_keyword(kind) || kind == TokenKind::PrivateIdentifier {
+            self.bump();
+        } else {
+            self.error(1003, "Identifier expected.");
+        }
+    }
+
+    /// Type arguments in an expression, as in `f<T>(x)`, kept only if they
+    /// parse and what follows cannot continue a comparison instead.
+    fn try_type_arguments_in_expression(&mut self) -> bool {
+        self.try_parse(|parser| {
+            parser.type_argument_list();
+            parser.can_follow_type_arguments()
+        })
+    }
+
+    fn can_follow_type_arguments(&self) -> bool {
+        match self.current() {
+            TokenKind::OpenParen
+            | TokenKind::NoSubstitutionTemplateLiteral
+            | TokenKind::TemplateHead => true,
+            TokenKind::LessThan | TokenKind::GreaterThan | TokenKind::Plus | TokenKind::Minus => {
+                false
+            }
+            kind => {
+                self.has_preceding_line_break()
+                    || kind.binary_operator_precedence().is_some()
+                    || !is_start_of_expression(kind)
+            }
+        }
+    }
+
+    fn argument_list(&mut self) {
+        self.start_node(NodeKind::ArgumentList);
+        self.expect(TokenKind::OpenParen);
+        while !self.at(TokenKind::CloseParen) && !self.at(TokenKind::Eof) {
+            self.allowing_in(|parser| {
+                if parser.at(TokenKind::DotDotDot) {
+                    parser.spread_element();
+                } else {
+                    parser.assignment_expression();
+                }
+            });
+            if !self.eat(TokenKind::Comma) {
+                break;
+            }
+        }
+        self.expect(TokenKind::CloseParen);
+        self.finish_node();
+    }
+
+    fn spread_element(&mut self) {
+        self.start_node(NodeKind::SpreadElement);
+        self.bump();
+        self.assignment_expression();
+        self.finish_node();
+    }
+
+    fn primary_expression(&mut self) {
+        // The lexer takes a `/` after `)` or `}` for a division
+        if matches!(self.current(), TokenKind::Slash | TokenKind::SlashEquals) {
+            self.rescan(Scanner::rescan_slash_token);
+        }
+        match self.current() {
+            TokenKind::This => self.token_node(NodeKind::ThisExpression),
+            TokenKind::Super => self.token_node(NodeKind::SuperExpression),
+            // The callee of a dynamic `import("./m")`
+            TokenKind::Import if self.nth(1) == TokenKind::OpenParen => {
+                self.token_node(NodeKind::ImportExpression);
+            }
+            TokenKind::Import if self.nth(1) == TokenKind::Dot => self.meta_property(),
+            TokenKind::NumericLiteral
+            | TokenKind::BigIntLiteral
+            | TokenKind::StringLiteral
+            | TokenKind::RegularExpressionLiteral
+            | TokenKind::NoSubstitutionTemplateLiteral
+            | TokenKind::True
+            | TokenKind::False
+            | TokenKind::Null => self.token_node(NodeKind::LiteralExpression),
+            TokenKind::TemplateHead => self.template_expression(),
+            TokenKind::OpenParen => {
+                self.start_node(NodeKind::ParenthesizedExpression);
+                self.bump();
+                self.allowing_in(Self::expression);
+                self.expect(TokenKind::CloseParen);
+                self.finish_node();
+            }
+            TokenKind::OpenBracket => self.array_literal(),
+            TokenKind::OpenBrace => self.object_literal(),
+            TokenKind::Function => self.function_expression(),
+            TokenKind::Async
+                if self.nth(1) == TokenKind::Function && !self.nth_has_preceding_line_break(1) =>
+            {
+                self.function_expression();
+            }
+            TokenKind::Class => {
+                let start = self.checkpoint();
+                self.class(start, NodeKind::ClassExpression);
+            }
+            kind if is_identifier(kind) || kind == TokenKind::PrivateIdentifier => {
+                self.token_node(NodeKind::IdentifierExpression);
+            }
+            _ => self.error_and_recover(1109, "Expression expected."),
+        }
+    }
+
+    /// `function` and `async function` expressions, whose name is optional.
+    fn function_expression(&mut self) {
+        self.start_node(NodeKind::FunctionExpression);
+        let is_async = self.eat(TokenKind::Async);
+        self.bump();
+        let is_generator = self.eat(TokenKind::Asterisk);
+        if is_identifier(self.current()) {
+            self.bump();
+        }
+        self.function_rest(is_async, is_generator, false);
+        self.finish_node();
+    }
+
+    /// The template of a tagged template.
+    fn template_literal(&mut self) {
+        if self.at(TokenKind::TemplateHead) {
+            self.template_expression();
+        } else {
+            self.token_node(NodeKind::LiteralExpression);
+        }
+    }
+
+    fn template_expression(&mut self) {
+        self.start_node(NodeKind::TemplateExpression);
+        self.bump();
+        loop {
+            self.start_node(NodeKind::TemplateSpan);
+            self.allowing_in(Self::expression);
+            let kind = self.current();
+            if matches!(kind, TokenKind::TemplateMiddle | TokenKind::TemplateTail) {
+                self.bump();
+            } else {
+                self.error_expected("}");
+            }
+            self.finish_node();
+            if kind != TokenKind::TemplateMiddle {
+                break;
+            }
+        }
+        self.finish_node();
+    }
+
+    fn array_literal(&mut self) {
+        self.start_node(NodeKind::ArrayLiteralExpression);
+        self.bump();
+        while !self.at(TokenKind::CloseBracket) && !self.at(TokenKind::Eof) {
+            // A hole, as in `[a, , b]`
+            if self.eat(TokenKind::Comma) {
+                continue;
+            }
+            self.allowing_in(|parser| {
+                if parser.at(TokenKind::DotDotDot) {
+                    parser.spread_element();
+                } else {
+                    parser.assignment_expression();
+                }
+            });
+            if !self.at(TokenKind::CloseBracket) && !self.eat(TokenKind::Comma) {
+                break;
+            }
+        }
+        self.expect(TokenKind::CloseBracket);
+        self.finish_node();
+    }
+
+    fn object_literal(&mut self) {
+        self.start_node(NodeKind::ObjectLiteralExpression);
+        self.bump();
+        while !self.at(TokenKind::CloseBrace) && !self.at(TokenKind::Eof) {
+            self.allowing_in(Self::object_member);
+            if !self.eat(TokenKind::Comma) {
+                break;
+            }
+        }
+        self.expect(TokenKind::CloseBrace);
+        self.finish_node();
+    }
+
+    fn object_member(&mut self) {
+        if self.at(TokenKind::DotDotDot) {
+            self.start_node(NodeKind::SpreadAssignment);
+            self.bump();
+            self.assignment_expression();
+            self.finish_node();
+            return;
+        }
+
+        let checkpoint = self.checkpoint();
+        let is_async = self.at(TokenKind::Async)
+            && (self.nth(1) == TokenKind::Asterisk || is_start_of_property_name(self.nth(1)))
+            && !self.nth_has_preceding_line_break(1);
+        if is_async {
+            self.bump();
+        }
+        let is_generator = self.eat(TokenKind::Asterisk);
+        let accessor = match self.current() {
+            TokenKind::Get if is_start_of_property_name(self.nth(1)) => Some(NodeKind::GetAccessor),
+            TokenKind::Set if is_start_of_property_name(self.nth(1)) => Some(NodeKind::SetAccessor),
+            _ => None,
+        };
+        if accessor.is_some() {
+            self.bump();
+        }
+
+        if !self.property_name() {
+            self.error_and_recover(1136, "Property assignment expected.");
+            return;
+        }
+
+        if let Some(accessor) = accessor {
+            self.start_node_at(checkpoint, accessor);
+            self.function_rest(false, false, false);
+        } else if is_async
+            || is_generator
+            || matches!(self.current(), TokenKind::OpenParen | TokenKind::LessThan)
+        {
+            self.start_node_at(checkpoint, NodeKind::MethodDeclaration);
+            self.function_rest(is_async, is_generator, false);
+        } else if self.at(TokenKind::Colon) {
+            self.start_node_at(checkpoint, NodeKind::PropertyAssignment);
+            self.bump();
+            self.assignment_expression();
+        } else {
+            self.start_node_at(checkpoint, NodeKind::ShorthandPropertyAssignment);
+            if self.eat(TokenKind::Equals) {
+                self.assignment_expression();
+            }
+        }
+        self.finish_node();
+    }
+
+    /// A property name: an identifier, keyword, string, number or private
+    /// name as a bare token, or a `[computed]` name in its own node.
+    fn property_name(&mut self) -> bool {
+        match self.current() {
+            TokenKind::OpenBracket => {
+                self.start_node(NodeKind::ComputedPropertyName);
+                self.bump();
+                self.allowing_in(Self::assignment_expression);
+                self.expect(TokenKind::CloseBracket);
+                self.finish_node();
+                true
+            }
+            kind if is_start_of_property_name(kind) => {
+                self.bump();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Type parameters, parameters, return type and body of a function
+    /// whose name, if any, has been parsed. Where `may_omit_body`, as in
+    /// declarations and class bodies, the body can be left out for an
+    /// overload or abstract signature.
+    fn function_rest(&mut self, is_async: bool, is_generator: bool, may_omit_body: bool) {
+        self.signature_rest();
+        if may_omit_body && !self.at(TokenKind::OpenBrace) {
+            self.semicolon();
+            return;
+        }
+        let context = Context {
+            disallow_in: false,
+            r#await: is_async,
+            r#yield: is_generator,
+            when_true: false,
+        };
+        self.with_context(context, |parser| {
+            if parser.at(TokenKind::OpenBrace) {
+                parser.block();
+            } else {
+                parser.error_expected("{");
+            }
+        });
+    }
+
+    // Arrow functions
+
+    /// `(params) => body`, `<T>(params) => body` and their `async` forms.
+    /// Whether parentheses start an arrow function cannot be told without
+    /// looking past them, so the parameters are parsed speculatively.
+    fn try_arrow_function(&mut self) -> bool {
+        let is_async = self.at(TokenKind::Async)
+            && !self.nth_has_preceding_line_break(1)
+            && matches!(self.nth(1), TokenKind::OpenParen | TokenKind::LessThan);
+        let offset = usize::from(is_async);
+        match self.nth(offset) {
+            TokenKind::OpenParen => {
+                // Cheap rejection of the common parenthesized expressions
+                let next = self.nth(offset + 1);
+                let may_be_parameter = matches!(
+                    next,
+                    TokenKind::CloseParen
+                        | TokenKind::DotDotDot
+                        | TokenKind::OpenBrace
+                        | TokenKind::OpenBracket
+                        | TokenKind::This
+                ) || is_identifier(next);
+                if !may_be_parameter {
+                    return false;
+                }
+            }
+            TokenKind::LessThan if self.language_variant == LanguageVariant::Standard => {}
+            _ => return false,
+        }
+
+        let start = self.tokens[self.pos].start;
+        if self.not_arrow_functions.contains(&start) {
+            return false;
+        }
+
+        let context = self.context;
+        let snapshot = self.snapshot();
+        let mut has_return_type = false;
+        let parsed = self.try_parse(|parser| {
+            parser.start_node(NodeKind::ArrowFunction);
+            if is_async {
+                parser.bump();
+            }
+            parser.context.r#await = is_async;
+            if parser.at(TokenKind::LessThan) {
+                parser.type_parameter_list();
+            }
+            parser.parameter_list();
+            if parser.at(TokenKind::Colon) {
+                has_return_type = true;
+                parser.return_type_annotation();
+            }
+            parser.at(TokenKind::EqualsGreaterThan) && !parser.has_preceding_line_break()
+        });
+        self.context = context;
+        if !parsed {
+            self.not_arrow_functions.insert(start);
+            return false;
+        }
+        self.bump();
+        self.arrow_function_body(is_async);
+        if context.when_true && has_return_type && !self.at(TokenKind::Colon) {
+            self.rewind(snapshot);
+            self.not_arrow_functions.insert(start);
+            return false;
+        }
+        self.finish_node();
+        true
+    }
+
+    fn at_simple_arrow_function(&self) -> bool {
+        let offset = usize::from(
+            self.at(TokenKind::Async)
+                && is_identifier(self.nth(1))
+                && !self.nth_has_preceding_line_break(1),
+        );
+        is_identifier(self.nth(offset))
+            && self.nth(offset + 1) == TokenKind::EqualsGreaterThan
+            && !self.nth_has_preceding_line_break(offset + 1)
+    }
+
+    /// `x => body` or `async x => body`.
+    fn simple_arrow_function(&mut self) {
+        self.start_node(NodeKind::ArrowFunction);
+        let is_async = self.nth(1) != TokenKind::EqualsGreaterThan;
+        if is_async {
+            self.bump();
+        }
+        self.token_node(NodeKind::Parameter);
+        self.bump();
+        self.arrow_function_body(is_async);
+        self.finish_node();
+    }
+
+    fn arrow_function_body(&mut self, is_async: bool) {
+        let context = Context {
+            r#await: is_async,
+            r#yield: false,
+            ..self.context
+        };
+        self.with_context(context, |parser| {
+            if parser.at(TokenKind::OpenBrace) {
+                parser.allowing_in(Self::block);
+            } else {
+                parser.assignment_expression();
+            }
+        });
+    }
+
+    // Parameters and bindings
+
+    fn parameter_list(&mut self) {
+        self.start_node(NodeKind::ParameterList);
+        self.expect(TokenKind::OpenParen);
+        while !self.at(TokenKind::CloseParen) && !self.at(TokenKind::Eof) {
+            self.parameter();
+            if !self.eat(TokenKind::Comma) {
+                break;
+            }
+        }
+        self.expect(TokenKind::CloseParen);
+        self.finish_node();
+    }
+
+    fn parameter(&mut self) {
+        self.start_node(NodeKind::Parameter);
+        while PARAMETER_MODIFIERS.contains(self.current()) && is_start_of_binding(self.nth(1)) {
+            self.bump();
+        }
+        self.eat(TokenKind::DotDotDot);
+        self.binding_name();
+        self.eat(TokenKind::Question);
+        if self.at(TokenKind::Colon) {
+            self.type_annotation();
+        }
+        self.initializer();
+        self.finish_node();
+    }
+
+    /// An identifier, or an object or array destructuring pattern.
+    fn binding_name(&mut self) {
+        self.nested(Self::binding_name_inner);
+    }
+
+    fn binding_name_inner(&mut self) {
+        match self.current() {
+            TokenKind::OpenBrace => self.object_binding_pattern(),
+            TokenKind::OpenBracket => self.array_binding_pattern(),
+            kind if is_identifier(kind) || kind == TokenKind::This => self.bump(),
+            _ => self.error(1003, "Identifier expected."),
+        }
+    }
+
+    /// `= value`, if present.
+    fn initializer(&mut self) {
+        if self.eat(TokenKind::Equals) {
+            self.allowing_in(Self::assignment_expression);
+        }
+    }
+
+    fn object_binding_pattern(&mut self) {
+        self.start_node(NodeKind::ObjectBindingPattern);
+        self.bump();
+        while !self.at(TokenKind::CloseBrace) && !self.at(TokenKind::Eof) {
+            self.start_node(NodeKind::BindingElement);
+            if self.eat(TokenKind::DotDotDot) {
+                self.binding_name();
+            } else if !self.property_name() {
+                self.error(1003, "Identifier expected.");
+            } else if self.eat(TokenKind::Colon) {
+                self.binding_name();
+            }
+            self.initializer();
+            self.finish_node();
+            if !self.eat(TokenKind::Comma) {
+                break;
+            }
+        }
+        self.expect(TokenKind::CloseBrace);
+        self.finish_node();
+    }
+
+    fn array_binding_pattern(&mut self) {
+        self.start_node(NodeKind::ArrayBindingPattern);
+        self.bump();
+        while !self.at(TokenKind::CloseBracket) && !self.at(TokenKind::Eof) {
+            if self.eat(TokenKind::Comma) {
+                continue;
+            }
+            self.start_node(NodeKind::BindingElement);
+            self.eat(TokenKind::DotDotDot);
+            self.binding_name();
+            self.initializer();
+            self.finish_node();
+            if !self.at(TokenKind::CloseBracket) && !self.eat(TokenKind::Comma) {
+                break;
+            }
+        }
+        self.expect(TokenKind::CloseBracket);
+        self.finish_node();
+    }
+
+    /// `: T`
+    fn type_annotation(&mut self) {
+        self.start_node(NodeKind::TypeAnnotation);
+        self.bump();
+        self.ty();
+        self.finish_node();
+    }
+
+    /// `: T` after a parameter list, where `T` can be a type predicate.
+    fn return_type_annotation(&mut self) {
+        self.start_node(NodeKind::TypeAnnotation);
+        self.bump();
+        self.return_type();
+        self.finish_node();
+    }
+
+    // Types
+
+    fn ty(&mut self) {
+        self.nested(Self::ty_inner);
+    }
+
+    fn ty_inner(&mut self) {
+        if self.at(TokenKind::LessThan) {
+            self.start_node(NodeKind::FunctionType);
+            self.type_parameter_list();
+            self.parameter_list();
+            self.function_type_rest();
+            return;
+        }
+        if self.at(TokenKind::OpenParen)
+            && self.try_parse(|parser| {
+                parser.start_node(NodeKind::FunctionType);
+                parser.parameter_list();
+                parser.at(TokenKind::EqualsGreaterThan)
+            })
+        {
+            self.function_type_rest();
+            return;
+        }
+
+        let checkpoint = self.checkpoint();
+        self.union_type();
+        if self.at(TokenKind::Extends) && !self.has_preceding_line_break() {
+            self.start_node_at(checkpoint, NodeKind::ConditionalType);
+            self.bump();
+            self.union_type();
+            self.expect(TokenKind::Question);
+            self.ty();
+            self.expect(TokenKind::Colon);
+            self.ty();
+            self.finish_node();
+        }
+    }
+
+    /// `=> T` and the end of a function type.
+    fn function_type_rest(&mut self) {
+        self.expect(TokenKind::EqualsGreaterThan);
+        self.return_type();
+        self.finish_node();
+    }
+
+    /// A return type: a type, or a type predicate such as `x is string`,
+    /// `this is T`, `asserts x` or `asserts x is T`.
+    fn return_type(&mut self) {
+        let is_parameter = |kind: TokenKind| is_identifier(kind) || kind == TokenKind::This;
+        // `asserts is T` tests a parameter named `asserts`
+        let asserts = self.at(TokenKind::Asserts)
+            && is_parameter(self.nth(1))
+            && self.nth(1) != TokenKind::Is
+            && !self.nth_has_preceding_line_break(1);
+        let offset = usize::from(asserts);
+        let is = is_parameter(self.nth(offset))
+            && self.nth(offset + 1) == TokenKind::Is
+            && !self.nth_has_preceding_line_break(offset + 1);
+        if !asserts && !is {
+            self.ty();
+            return;
+        }
+
+        self.start_node(NodeKind::TypePredicate);
+        if asserts {
+            self.bump();
+        }
+        self.bump();
+        if is {
+            self.bump();
+            self.ty();
+        }
+        self.finish_node();
+    }
+
+    fn union_type(&mut self) {
+        self.union_or_intersection_type(
+            TokenKind::Bar,
+            NodeKind::UnionType,
+            Self::intersection_type,
+        );
+    }
+
+    fn intersection_type(&mut self) {
+        self.union_or_intersection_type(
+            TokenKind::Ampersand,
+            NodeKind::IntersectionType,
+            Self::type_operator,
+        );
+    }
+
+    /// Members separated by `operator`, with an optional leading one as in
+    /// `| A | B`.
+    fn union_or_intersection_type(
+        &mut self,
+        operator: TokenKind,
+        node: NodeKind,
+        member: fn(&mut Self),
+    ) {
+        let checkpoint = self.checkpoint();
+        let leading = self.eat(operator);
+        member(self);
+        if leading || self.at(operator) {
+            while self.eat(operator) {
+                member(self);
+            }
+            self.start_node_at(checkpoint, node);
+            self.finish_node();
+        }
+    }
+
+    fn type_operator(&mut self) {
+        self.nested(Self::type_operator_inner);
+    }
+
+    fn type_operator_inner(&mut self) {
+        match self.current() {
+            TokenKind::KeyOf | TokenKind::Unique | TokenKind::Readonly => {
+                self.start_node(NodeKind::TypeOperator);
+                self.bump();
+                self.type_operator();
+                self.finish_node();
+            }
+            _ => self.postfix_type(),
+        }
+    }
+
+    /// A type followed by `[]` or `[K]`.
+    fn postfix_type(&mut self) {
+        let checkpoint = self.checkpoint();
+        self.primary_type();
+        while self.at(TokenKind::OpenBracket) && !self.has_preceding_line_break() {
+            if self.nth(1) == TokenKind::CloseBracket {
+                self.start_node_at(checkpoint, NodeKind::ArrayType);
+                self.bump();
+                self.bump();
+            } else {
+                self.start_node_at(checkpoint, NodeKind::IndexedAccessType);
+                self.bump();
+                self.ty();
+                self.expect(TokenKind::CloseBracket);
+            }
+            self.finish_node();
+        }
+    }
+
+    fn primary_type(&mut self) {
+        match self.current() {
+            kind if KEYWORD_TYPES.contains(kind) && self.nth(1) != TokenKind::Dot => {
+                self.token_node(NodeKind::KeywordType);
+            }
+            TokenKind::Null
+            | TokenKind::True
+            | TokenKind::False
+            | TokenKind::StringLiteral
+            | TokenKind::NumericLiteral
+            | TokenKind::BigIntLiteral
+            | TokenKind::NoSubstitutionTemplateLiteral => self.token_node(NodeKind::LiteralType),
+            TokenKind::Minus
+                if matches!(
+                    self.nth(1),
+                    TokenKind::NumericLiteral | TokenKind::BigIntLiteral
+                ) =>
+            {
+                self.start_node(NodeKind::LiteralType);
+                self.bump();
+                self.bump();
+                self.finish_node();
+            }
+            TokenKind::TypeOf => {
+                self.start_node(NodeKind::TypeQuery);
+                self.bump();
+                self.entity_name();
+                if self.at(TokenKind::LessThan) && !self.has_preceding_line_break() {
+                    self.type_argument_list();
+                }
+                self.finish_node();
+            }
+            TokenKind::OpenParen => {
+                self.start_node(NodeKind::ParenthesizedType);
+                self.bump();
+                self.ty();
+                self.expect(TokenKind::CloseParen);
+                self.finish_node();
+            }
+            TokenKind::OpenBracket => self.tuple_type(),
+            TokenKind::OpenBrace => self.type_literal(),
+            kind if is_identifier(kind) || kind == TokenKind::This => {
+                self.start_node(NodeKind::TypeReference);
+                self.entity_name();
+                if self.at(TokenKind::LessThan) && !self.has_preceding_line_break() {
+                    self.type_argument_list();
+                }
+                self.finish_node();
+            }
+            _ => self.error_and_recover(1110, "Type expected."),
+        }
+    }
+
+    /// A possibly dotted name, as in `ns.Type`.
+    fn entity_name(&mut self) {
+        let kind = self.current();
+        if is_identifier(kind) || kind == TokenKind::This {
+            self.bump();
+        } else {
+            self.error(1003, "Identifier expected.");
+            return;
+        }
+        while self.eat(TokenKind::Dot) {
+            self.property_name_token();
+        }
+    }
+
+    fn tuple_type(&mut self) {
+        self.start_node(NodeKind::TupleType);
+        self.bump();
+        while !self.at(TokenKind::CloseBracket) && !self.at(TokenKind::Eof) {
+            self.eat(TokenKind::DotDotDot);
+            // A named member, as in `[first: string, rest?: number]`
+            let named = is_identifier(self.current())
+                && (self.nth(1) == TokenKind::Colon
+                    || (self.nth(1) == TokenKind::Question && self.nth(2) == TokenKind::Colon));
+            if named {
+                self.bump();
+                self.eat(TokenKind::Question);
+                self.bump();
+            }
+            self.ty();
+            self.eat(TokenKind::Question);
+            if !self.eat(TokenKind::Comma) {
+                break;
+            }
+        }
+        self.expect(TokenKind::CloseBracket);
+        self.finish_node();
+    }
+
+    fn type_literal(&mut self) {
+        self.start_node(NodeKind::TypeLiteral);
+        self.bump();
+        while !self.at(TokenKind::CloseBrace) && !self.at(TokenKind::Eof) {
+            let start = self.pos;
+            self.type_member();
+            if !self.eat(TokenKind::Comma)
+                && !self.eat(TokenKind::Semicolon)
+                && !self.at(TokenKind::CloseBrace)
+                && !self.has_preceding_line_break()
+            {
+                self.error_expected(";");
+            }
+            if self.pos == start {
+                self.token_node(NodeKind::Error);
+            }
+        }
+        self.expect(TokenKind::CloseBrace);
+        self.finish_node();
+    }
+
+    fn type_member(&mut self) {
+        match self.current() {
+            TokenKind::OpenParen | TokenKind::LessThan => {
+                self.start_node(NodeKind::CallSignature);
+                self.signature_rest();
+                self.finish_node();
+                return;
+            }
+            TokenKind::OpenBracket if self.at_index_signature() => {
+                self.start_node(NodeKind::IndexSignature);
+                self.index_signature_rest();
+                self.finish_node();
+                return;
+            }
+            _ => {}
+        }
+
+        let checkpoint = self.checkpoint();
+        if self.at(TokenKind::Readonly) && is_start_of_property_name(self.nth(1)) {
+            self.bump();
+        }
+        if !self.property_name() {
+            self.error_and_recover(1131, "Property or signature expected.");
+            return;
+        }
+        self.eat(TokenKind::Question);
+        if matches!(self.current(), TokenKind::OpenParen | TokenKind::LessThan) {
+            self.start_node_at(checkpoint, NodeKind::MethodSignature);
+            self.signature_rest();
+        } else {
+            self.start_node_at(checkpoint, NodeKind::PropertySignature);
+            if self.at(TokenKind::Colon) {
+                self.type_annotation();
+            }
+        }
+        self.finish_node();
+    }
+
+    /// Type parameters, parameters and return type of a signature without a
+    /// body.
+    fn signature_rest(&mut self) {
+        if self.at(TokenKind::LessThan) {
+            self.type_parameter_list();
+        }
+        self.parameter_list();
+        if self.at(TokenKind::Colon) {
+            self.return_type_annotation();
+        }
+    }
+
+    fn type_argument_list(&mut self) {
+        self.start_node(NodeKind::TypeArgumentList);
+        self.bump();
+        while !self.at_greater_than() && !self.at(TokenKind::Eof) {
+            self.ty();
+            if !self.eat(TokenKind::Comma) {
+                break;
+            }
+        }
+        self.expect_greater_than();
+        self.finish_node();
+    }
+
+    fn type_parameter_list(&mut self) {
+        self.start_node(NodeKind::TypeParameterList);
+        self.bump();
+        while !self.at_greater_than() && !self.at(TokenKind::Eof) {
+            self.start_node(NodeKind::TypeParameter);
+            while matches!(self.current(), TokenKind::Const | TokenKind::In)
+                && is_identifier(self.nth(1))
+            {
+                self.bump();
+            }
+            if is_identifier(self.current()) {
+                self.bump();
+            } else {
+                self.error(1003, "Identifier expected.");
+            }
+            if self.eat(TokenKind::Extends) {
+                self.ty();
+            }
+            if self.eat(TokenKind::Equals) {
+                self.ty();
+            }
+            self.finish_node();
+            if !self.eat(TokenKind::Comma) {
+                break;
+            }
+        }
+        self.expect_greater_than();
+        self.finish_node();
+    }
+}
+
+/// Whether `kind` can be used as an identifier: a plain identifier, or a
+/// keyword that is not reserved.
+fn is_identifier(kind: TokenKind) -> bool {
+    kind == TokenKind::Identifier || (kind.is_keyword() && !kind.is_reserved_word())
+}
+
+fn is_identifier_or_keyword(kind: TokenKind) -> bool {
+    kind == TokenKind::Identifier || kind.is_keyword()
+}
+
+fn is_module_export_name(kind: TokenKind) -> bool {
+    is_identifier_or_keyword(kind) || kind == TokenKind::StringLiteral
 }
 
+fn is_start_of_property_name(kind: TokenKind) -> bool {
+    is_identifier_or_keyword(kind)
+        || matches!(
+            kind,
+            TokenKind::StringLiteral
+                | TokenKind::NumericLiteral
+                | TokenKind::BigIntLiteral
+                | TokenKind::PrivateIdentifier
+                | TokenKind::OpenBracket
+        )
+}
+
+fn is_start_of_binding(kind: TokenKind) -> bool {
+    is_identifier(kind)
+        || matches!(
+            kind,
+            TokenKind::OpenBrace | TokenKind::OpenBracket | TokenKind::This | TokenKind::DotDotDot
+        )
+}
+
+fn is_start_of_expression(kind: TokenKind) -> bool {
+    is_identifier(kind)
+        || matches!(
+            kind,
+            TokenKind::This
+                | TokenKind::Super
+                | TokenKind::Null
+                | TokenKind::True
+                | TokenKind::False
+                | TokenKind::NumericLiteral
+                | TokenKind::BigIntLiteral
+                | TokenKind::StringLiteral
+                | TokenKind::RegularExpressionLiteral
+                | TokenKind::NoSubstitutionTemplateLiteral
+                | TokenKind::TemplateHead
+                | TokenKind::PrivateIdentifier
+                | TokenKind::OpenParen
+                | TokenKind::OpenBracket
+                | TokenKind::OpenBrace
+                | TokenKind::Function
+                | TokenKind::Class
+                | TokenKind::New
+                | TokenKind::Slash
+                | TokenKind::SlashEquals
+                | TokenKind::Plus
+                | TokenKind::Minus
+                | TokenKind::Tilde
+                | TokenKind::Bang
+                | TokenKind::Delete
+                | TokenKind::TypeOf
+                | TokenKind::Void
+                | TokenKind::PlusPlus
+                | TokenKind::MinusMinus
+                | TokenKind::LessThan
+                | TokenKind::Await
+                | TokenKind::Yield
+                | TokenKind::Import
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::syntax::SyntaxElement;
+
+    /// The tree as an S-expression of node kinds and token texts, without
+    /// trivia or the `Eof` token.
+    fn sexp(node: &SyntaxNode) -> String {
+        let children: Vec<String> = node
+            .children_with_tokens()
+            .filter_map(|element| match element {
+                SyntaxElement::Node(node) => Some(sexp(&node)),
+                SyntaxElement::Token(token)
+                    if token.kind().is_trivia() || token.kind() == TokenKind::Eof =>
+                {
+                    None
+                }
+                SyntaxElement::Token(token) => Some(token.text().to_string()),
+            })
+            .collect();
+        format!("({:?} {})", node.kind(), children.join(" "))
+    }
+
+    /// The S-expression of the only statement in `source`, which must parse
+    /// without errors.
+    fn parse_statement(source: &str) -> String {
+        let parse = Parser::new(source).parse();
+        assert_eq!(parse.diagnostics(), &[], "{source}");
+        let root = parse.syntax();
+        let mut statements = root.children();
+        let statement = statements.next().unwrap();
+        assert!(statements.next().is_none(), "{source}");
+        sexp(&statement)
+    }
+
+    /// The S-expression of the expression in the statement `source`.
+    fn parse_expression(source: &str) -> String {
+        let parse = Parser::new(source).parse();
+        assert_eq!(parse.diagnostics(), &[], "{source}");
+        let statement = parse.syntax().children().next().unwrap();
+        assert_eq!(statement.kind(), NodeKind::ExpressionStatement);
+        sexp(&statement.children().next().unwrap())
+    }
+
+    #[test]
+    fn binary_operators_follow_precedence() {
+        assert_eq!(
+            parse_expression("a + b * c - d"),
+            "(BinaryExpression (BinaryExpression (IdentifierExpression a) + \
+             (BinaryExpression (IdentifierExpression b) * (IdentifierExpression c))) - \
+             (IdentifierExpression d))"
+        );
+        assert_eq!(
+            parse_expression("a ** b ** c"),
+            "(BinaryExpression (IdentifierExpression a) ** \
+             (BinaryExpression (IdentifierExpression b) ** (IdentifierExpression c)))"
+        );
+        assert_eq!(
+            parse_expression("a = b ?? c ? d : e"),
+            "(BinaryExpression (IdentifierExpression a) = \
+             (ConditionalExpression (BinaryExpression (IdentifierExpression b) ?? \
+             (IdentifierExpression c)) ? (IdentifierExpression d) : (IdentifierExpression e)))"
+        );
+        assert_eq!(
+            parse_expression("-x++ + !y as T"),
+            "(AsExpression (BinaryExpression (PrefixUnaryExpression - \
+             (PostfixUnaryExpression (IdentifierExpression x) ++)) + \
+             (PrefixUnaryExpression ! (IdentifierExpression y))) as \
+             (TypeReference T))"
+        );
+        assert_eq!(
+            parse_expression("x satisfies Y as const"),
+            "(AsExpression (SatisfiesExpression (IdentifierExpression x) satisfies \
+             (TypeReference Y)) as (TypeReference const))"
+        );
+    }
+
+    #[test]
+    fn member_access_and_calls() {
+        assert_eq!(
+            parse_expression("a?.b[c]!.d?.(e)?.[f]"),
+            "(ElementAccessExpression (CallExpression (PropertyAccessExpression \
+             (NonNullExpression (ElementAccessExpression (PropertyAccessExpression \
+             (IdentifierExpression a) ?. b) [ (IdentifierExpression c) ]) !) . d) ?. \
+             (ArgumentList ( (IdentifierExpression e) ))) ?. [ (IdentifierExpression f) ])"
+        );
+        assert_eq!(
+            parse_expression("new a.B<T>(...c)"),
+            "(NewExpression new (PropertyAccessExpression (IdentifierExpression a) . B) \
+             (TypeArgumentList < (TypeReference T) >) \
+             (ArgumentList ( (SpreadElement ... (IdentifierExpression c)) )))"
+        );
+        assert_eq!(
+            parse_expression("new.target"),
+            "(MetaProperty new . target)"
+        );
+        assert_eq!(
+            parse_expression("await import('./m')"),
+            "(AwaitExpression await (CallExpression (ImportExpression import) (ArgumentList \
+             ( (LiteralExpression './m') ))))"
+        );
+        assert_eq!(
+            parse_expression("import.meta.url"),
+            "(PropertyAccessExpression (MetaProperty import . meta) . url)"
+        );
+        assert_eq!(
+            parse_expression("tag`a${b}c`"),
+            "(TaggedTemplateExpression (IdentifierExpression tag) (TemplateExpression \
+             `a${ (TemplateSpan (IdentifierExpression b) }c`)))"
+        );
+    }
+
+    #[test]
+    fn type_arguments_or_comparisons() {
+        assert_eq!(
+            parse_expression("f<A, B<C>>(x)"),
+            "(CallExpression (IdentifierExpression f) (TypeArgumentList < \
+             (TypeReference A) , (TypeReference B (TypeArgumentList < (TypeReference C) >)) >) \
+             (ArgumentList ( (IdentifierExpression x) )))"
+        );
+        assert_eq!(
+            parse_expression("a < b > c"),
+            "(BinaryExpression (BinaryExpression (IdentifierExpression a) < \
+             (IdentifierExpression b)) > (IdentifierExpression c))"
+        );
+        assert_eq!(
+            parse_expression("f<T>"),
+            "(ExpressionWithTypeArguments (IdentifierExpression f) \
+             (TypeArgumentList < (TypeReference T) >))"
+        );
+        // Trying `<b>` as type arguments splits the `>>`, which is undone
+        assert_eq!(
+            parse_expression("a < b >> c"),
+            "(BinaryExpression (IdentifierExpression a) < (BinaryExpression \
+             (IdentifierExpression b) >> (IdentifierExpression c)))"
+        );
+    }
+
+    #[test]
+    fn rescans_slashes_as_regular_expressions() {
+        assert_eq!(
+            parse_statements("if (x) /re/.test(y)"),
+            [
+                "(IfStatement if ( (IdentifierExpression x) ) (ExpressionStatement \
+              (CallExpression (PropertyAccessExpression (LiteralExpression /re/) . test) \
+              (ArgumentList ( (IdentifierExpression y) )))))"
+            ]
+        );
+        // The tokens after the regex are scanned again
+        assert_eq!(
+            parse_statements("{ if (x) /'}/g.test(y) }\nz;"),
+            [
+                "(Block { (IfStatement if ( (IdentifierExpression x) ) (ExpressionStatement \
+                 (CallExpression (PropertyAccessExpression (LiteralExpression /'}/g) . test) \
+                 (ArgumentList ( (IdentifierExpression y) ))))) })",
+                "(ExpressionStatement (IdentifierExpression z) ;)"
+            ]
+        );
+
+        let parse = Parser::new("x = /abc\ny").parse();
+        let reported: Vec<(u32, usize)> = parse
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| (diagnostic.code, diagnostic.start))
+            .collect();
+        assert_eq!(reported, [(1161, 4)]);
+    }
+
+    #[test]
+    fn arrow_functions() {
+        assert_eq!(
+            parse_expression("async (a: number, { b } = c): T => await a"),
+            "(ArrowFunction async (ParameterList ( (Parameter a (TypeAnnotation : \
+             (KeywordType number))) , (Parameter (ObjectBindingPattern { \
+             (BindingElement b) }) = (IdentifierExpression c)) )) (TypeAnnotation : \
+             (TypeReference T)) => (AwaitExpression await (IdentifierExpression a)))"
+        );
+        assert_eq!(
+            parse_expression("x => y => ({})"),
+            "(ArrowFunction (Parameter x) => (ArrowFunction (Parameter y) => \
+             (ParenthesizedExpression ( (ObjectLiteralExpression { }) ))))"
+        );
+        assert_eq!(
+            parse_expression("<T,>(x: T[]) => {}"),
+            "(ArrowFunction (TypeParameterList < (TypeParameter T) , >) (ParameterList ( \
+             (Parameter x (TypeAnnotation : (ArrayType (TypeReference T) [ ]))) )) => \
+             (Block { }))"
+        );
+        assert_eq!(
+            parse_expression("a ? (b) : c => d"),
+            "(ConditionalExpression (IdentifierExpression a) ? (ParenthesizedExpression ( \
+             (IdentifierExpression b) )) : (ArrowFunction (Parameter c) => \
+             (IdentifierExpression d)))"
+        );
+        assert_eq!(
+            parse_expression("a ? (b): c => d : e"),
+            "(ConditionalExpression (IdentifierExpression a) ? (ArrowFunction (ParameterList \
+             ( (Parameter b) )) (TypeAnnotation : (TypeReference c)) => \
+             (IdentifierExpression d)) : (IdentifierExpression e))"
+        );
+        assert_eq!(
+            parse_expression("(a, b)"),
+            "(ParenthesizedExpression ( (BinaryExpression (IdentifierExpression a) , \
+             (IdentifierExpression b)) ))"
+        );
+    }
+
+    #[test]
+    fn object_and_array_literals() {
+        assert_eq!(
+            parse_expression("({ a, b: 1, [c]: d, ...e, f() {}, get g() {}, async *h() {} })"),
+            "(ParenthesizedExpression ( (ObjectLiteralExpression { \
+             (ShorthandPropertyAssignment a) , \
+             (PropertyAssignment b : (LiteralExpression 1)) , \
+             (PropertyAssignment (ComputedPropertyName [ (IdentifierExpression c) ]) : \
+             (IdentifierExpression d)) , \
+             (SpreadAssignment ... (IdentifierExpression e)) , \
+             (MethodDeclaration f (ParameterList ( )) (Block { })) , \
+             (GetAccessor get g (ParameterList ( )) (Block { })) , \
+             (MethodDeclaration async * h (ParameterList ( )) (Block { })) }) ))"
+        );
+        assert_eq!(
+            parse_expression("[a, , ...b,]"),
+            "(ArrayLiteralExpression [ (IdentifierExpression a) , , \
+             (SpreadElement ... (IdentifierExpression b)) , ])"
+        );
+    }
+
+    #[test]
+    fn types() {
+        assert_eq!(
+            parse_statement("x as keyof T[K] | ((a?: 1) => -1) & { [k: string]: U; m<V>(): void }"),
+            "(ExpressionStatement (AsExpression (IdentifierExpression x) as (UnionType \
+             (TypeOperator keyof (IndexedAccessType (TypeReference T) [ (TypeReference K) ])) | \
+             (IntersectionType (ParenthesizedType ( (FunctionType (ParameterList ( \
+             (Parameter a ? (TypeAnnotation : (LiteralType 1))) )) => (LiteralType - 1)) )) & \
+             (TypeLiteral { (IndexSignature [ (Parameter k (TypeAnnotation : \
+             (KeywordType string))) ] (TypeAnnotation : (TypeReference U))) ; \
+             (MethodSignature m (TypeParameterList < (TypeParameter V) >) (ParameterList ( )) \
+             (TypeAnnotation : (KeywordType void))) })))))"
+        );
+        assert_eq!(
+            parse_expression("x as T extends [infer, ...U[]] ? typeof a.b : never"),
+            "(AsExpression (IdentifierExpression x) as (ConditionalType (TypeReference T) \
+             extends (TupleType [ (TypeReference infer) , ... (ArrayType (TypeReference U) [ ]) \
+             ]) ? (TypeQuery typeof a . b) : (KeywordType never)))"
+        );
+    }
+
+    #[test]
+    fn type_predicates() {
+        assert_eq!(
+            parse_statements(
+                "function isStr(x): x is string {}\n\
+                 function check(v): asserts v {}\n\
+                 let f: (x) => asserts x is T = (x): x is T => true\n\
+                 class C { isD(): this is D; is(): is }"
+            ),
+            [
+                "(FunctionDeclaration function isStr (ParameterList ( (Parameter x) )) \
+                 (TypeAnnotation : (TypePredicate x is (KeywordType string))) (Block { }))",
+                "(FunctionDeclaration function check (ParameterList ( (Parameter v) )) \
+                 (TypeAnnotation : (TypePredicate asserts v)) (Block { }))",
+                "(VariableStatement (VariableDeclarationList let (VariableDeclaration f \
+                 (TypeAnnotation : (FunctionType (ParameterList ( (Parameter x) )) => \
+                 (TypePredicate asserts x is (TypeReference T)))) = (ArrowFunction \
+                 (ParameterList ( (Parameter x) )) (TypeAnnotation : (TypePredicate x is \
+                 (TypeReference T))) => (LiteralExpression true)))))",
+                "(ClassDeclaration class C { (MethodDeclaration isD (ParameterList ( )) \
+                 (TypeAnnotation : (TypePredicate this is (TypeReference D))) ;) \
+                 (MethodDeclaration is (ParameterList ( )) (TypeAnnotation : \
+                 (TypeReference is))) })",
+            ]
+        );
+    }
+
+    #[test]
+    fn line_breaks_end_postfix_operators() {
+        let parse = Parser::new("a\n++b\nc\n!d").parse();
+        let statements: Vec<String> = parse.syntax().children().map(|s| sexp(&s)).collect();
+        assert_eq!(
+            statements,
+            [
+                "(ExpressionStatement (IdentifierExpression a))",
+                "(ExpressionStatement (PrefixUnaryExpression ++ (IdentifierExpression b)))",
+                "(ExpressionStatement (IdentifierExpression c))",
+                "(ExpressionStatement (PrefixUnaryExpression ! (IdentifierExpression d)))",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_and_recovers_from_errors() {
+        let parse = Parser::new("f(a b)\n) + ;").parse();
+        let messages: Vec<(u32, &str)> = parse
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| (diagnostic.code, diagnostic.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                (1005, "')' expected."),
+                (1005, "';' expected."),
+                (1128, "Declaration or statement expected."),
+                (1109, "Expression expected."),
+            ]
+        );
+        assert_eq!(parse.syntax().text(), "f(a b)\n) + ;");
+    }
+
+    /// The S-expressions of the top-level statements in `source`, which
+    /// must parse without errors.
+    fn parse_statements(source: &str) -> Vec<String> {
+        let parse = Parser::new(source).parse();
+        assert_eq!(parse.diagnostics(), &[], "{source}");
+        parse.syntax().children().map(|node| sexp(&node)).collect()
+    }
+
+    #[test]
+    fn variable_declarations() {
+        assert_eq!(
+            parse_statements(
+                "let [a, b] = c, d!: T\nconst { e = 1 } = f\nusing g = h\nawait using i = j\nusing(x)"
+            ),
+            [
+                "(VariableStatement (VariableDeclarationList let (VariableDeclaration \
+                 (ArrayBindingPattern [ (BindingElement a) , (BindingElement b) ]) = \
+                 (IdentifierExpression c)) , (VariableDeclaration d ! (TypeAnnotation : \
+                 (TypeReference T)))))",
+                "(VariableStatement (VariableDeclarationList const (VariableDeclaration \
+                 (ObjectBindingPattern { (BindingElement e = (LiteralExpression 1)) }) = \
+                 (IdentifierExpression f))))",
+                "(VariableStatement (VariableDeclarationList using (VariableDeclaration g = \
+                 (IdentifierExpression h))))",
+                "(VariableStatement (VariableDeclarationList await using (VariableDeclaration \
+                 i = (IdentifierExpression j))))",
+                "(ExpressionStatement (CallExpression (IdentifierExpression using) (ArgumentList \
+                 ( (IdentifierExpression x) ))))",
+            ]
+        );
+    }
+
+    #[test]
+    fn automatic_semicolon_insertion() {
+        assert_eq!(
+            parse_statements("a = b\n(c)\nreturn\nd\nx: for (;;) { break\nx; continue x }"),
+            [
+                // A line break does not end a statement the next line continues
+                "(ExpressionStatement (BinaryExpression (IdentifierExpression a) = \
+                 (CallExpression (IdentifierExpression b) (ArgumentList ( \
+                 (IdentifierExpression c) )))))",
+                "(ReturnStatement return)",
+                "(ExpressionStatement (IdentifierExpression d))",
+                "(LabeledStatement x : (ForStatement for ( ; ; ) (Block { \
+                 (BreakStatement break) (ExpressionStatement (IdentifierExpression x) ;) \
+                 (ContinueStatement continue x) })))",
+            ]
+        );
+
+        let parse = Parser::new("a b\nthrow\nc").parse();
+        let messages: Vec<&str> = parse
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            ["';' expected.", "Line break not permitted here."]
+        );
+    }
+
+    #[test]
+    fn control_flow() {
+        assert_eq!(
+            parse_statements(
+                "if (a) b; else if (c) {} else d\n\
+                 for (const x of y) ;\n\
+                 for await (let x of y) {}\n\
+                 for (var k = 'a' in o) {}\n\
+                 for (i = 0, n = 1; i < n; i++) {}\n\
+                 do x(); while (y) z\n\
+                 while (a) {}"
+            ),
+            [
+                "(IfStatement if ( (IdentifierExpression a) ) (ExpressionStatement \
+                 (IdentifierExpression b) ;) else (IfStatement if ( (IdentifierExpression c) ) \
+                 (Block { }) else (ExpressionStatement (IdentifierExpression d))))",
+                "(ForOfStatement for ( (VariableDeclarationList const (VariableDeclaration x)) \
+                 of (IdentifierExpression y) ) (EmptyStatement ;))",
+                "(ForOfStatement for await ( (VariableDeclarationList let (VariableDeclaration \
+                 x)) of (IdentifierExpression y) ) (Block { }))",
+                "(ForInStatement for ( (VariableDeclarationList var (VariableDeclaration k = \
+                 (LiteralExpression 'a'))) in (IdentifierExpression o) ) (Block { }))",
+                "(ForStatement for ( (BinaryExpression (BinaryExpression (IdentifierExpression \
+                 i) = (LiteralExpression 0)) , (BinaryExpression (IdentifierExpression n) = \
+                 (LiteralExpression 1))) ; (BinaryExpression (IdentifierExpression i) < \
+                 (IdentifierExpression n)) ; (PostfixUnaryExpression (IdentifierExpression i) \
+                 ++) ) (Block { }))",
+                "(DoStatement do (ExpressionStatement (CallExpression (IdentifierExpression x) \
+                 (ArgumentList ( ))) ;) while ( (IdentifierExpression y) ))",
+                "(ExpressionStatement (IdentifierExpression z))",
+                "(WhileStatement while ( (IdentifierExpression a) ) (Block { }))",
+            ]
+        );
+    }
+
+    #[test]
+    fn switch_and_try() {
+        assert_eq!(
+            parse_statement("switch (x) { case 1: case 2: y(); break; default: throw e }"),
+            "(SwitchStatement switch ( (IdentifierExpression x) ) (CaseBlock { \
+             (CaseClause case (LiteralExpression 1) :) (CaseClause case (LiteralExpression 2) \
+             : (ExpressionStatement (CallExpression (IdentifierExpression y) (ArgumentList \
+             ( ))) ;) (BreakStatement break ;)) (DefaultClause default : (ThrowStatement throw \
+             (IdentifierExpression e))) }))"
+        );
+        assert_eq!(
+            parse_statement("try {} catch ({ message }: unknown) {} finally {}"),
+            "(TryStatement try (Block { }) (CatchClause catch ( (VariableDeclaration \
+             (ObjectBindingPattern { (BindingElement message) }) (TypeAnnotation : \
+             (KeywordType unknown))) ) (Block { })) finally (Block { }))"
+        );
+        assert_eq!(
+            parse_statement("try {} catch {}"),
+            "(TryStatement try (Block { }) (CatchClause catch (Block { })))"
+        );
+    }
+
+    #[test]
+    fn functions_and_classes() {
+        assert_eq!(
+            parse_statements(
+                "function f(x: number): void\n\
+                 async function* g<T>() { yield* h(await 1) }\n\
+                 const k = function () {}"
+            ),
+            [
+                "(FunctionDeclaration function f (ParameterList ( (Parameter x (TypeAnnotation \
+                 : (KeywordType number))) )) (TypeAnnotation : (KeywordType void)))",
+                "(FunctionDeclaration async function * g (TypeParameterList < (TypeParameter \
+                 T) >) (ParameterList ( )) (Block { (ExpressionStatement (YieldExpression yield \
+                 * (CallExpression (IdentifierExpression h) (ArgumentList ( (AwaitExpression \
+                 await (LiteralExpression 1)) ))))) }))",
+                "(VariableStatement (VariableDeclarationList const (VariableDeclaration k = \
+                 (FunctionExpression function (ParameterList ( )) (Block { })))))",
+            ]
+        );
+        assert_eq!(
+            parse_statement(
+                "abstract class A<T> extends B<T> implements C {\n\
+                 static x = 1\n\
+                 private readonly y?: string\n\
+                 readonly: boolean\n\
+                 [key: string]: unknown;\n\
+                 constructor(public z) { super() }\n\
+                 static { init() }\n\
+                 abstract m(): void\n\
+                 async *n() {}\n\
+                 get #p() { return 1 }\n\
+                 static() {}\n\
+                 }"
+            ),
+            "(ClassDeclaration abstract class A (TypeParameterList < (TypeParameter T) >) \
+             (HeritageClause extends (ExpressionWithTypeArguments (IdentifierExpression B) \
+             (TypeArgumentList < (TypeReference T) >))) (HeritageClause implements \
+             (IdentifierExpression C)) { \
+             (PropertyDeclaration static x = (LiteralExpression 1)) \
+             (PropertyDeclaration private readonly y ? (TypeAnnotation : (KeywordType string))) \
+             (PropertyDeclaration readonly (TypeAnnotation : (KeywordType boolean))) \
+             (IndexSignature [ (Parameter key (TypeAnnotation : (KeywordType string))) ] \
+             (TypeAnnotation : (KeywordType unknown)) ;) \
+             (Constructor constructor (ParameterList ( (Parameter public z) )) (Block { \
+             (ExpressionStatement (CallExpression (SuperExpression super) (ArgumentList ( )))) \
+             })) \
+             (ClassStaticBlockDeclaration static (Block { (ExpressionStatement \
+             (CallExpression (IdentifierExpression init) (ArgumentList ( )))) })) \
+             (MethodDeclaration abstract m (ParameterList ( )) (TypeAnnotation : \
+             (KeywordType void))) \
+             (MethodDeclaration async * n (ParameterList ( )) (Block { })) \
+             (GetAccessor get #p (ParameterList ( )) (Block { (ReturnStatement return \
+             (LiteralExpression 1)) })) \
+             (MethodDeclaration static (ParameterList ( )) (Block { })) })"
+        );
+    }
+
+    #[test]
+    fn imports_and_exports() {
+        assert_eq!(
+            parse_statements(
+                "import x from \"y\"\n\
+                 import type, { a as b, type c, type as } from './m'\n\
+                 import * as ns from 'm' with { type: 'json' }\n\
+                 import 'side-effect'\n\
+                 import fs = require('fs')"
+            ),
+            [
+                "(ImportDeclaration import (ImportClause x) from \"y\")",
+                "(ImportDeclaration import (ImportClause type , (NamedImports { (ImportSpecifier \
+                 a as b) , (ImportSpecifier type c) , (ImportSpecifier type as) })) from './m')",
+                "(ImportDeclaration import (ImportClause (NamespaceImport * as ns)) from 'm' \
+                 (ImportAttributes with { (ImportAttribute type : (LiteralExpression 'json')) }))",
+                "(ImportDeclaration import 'side-effect')",
+                "(ImportEqualsDeclaration import fs = (ExternalModuleReference require ( 'fs' )))",
+            ]
+        );
+        assert_eq!(
+            parse_statements(
+                "export function f() {}\n\
+                 export const a = 1, b = 2\n\
+                 export default async function () {}\n\
+                 export default class {}\n\
+                 export default a + b;\n\
+                 export { a, b as default } from 'm'\n\
+                 export type * as types from 'm'\n\
+                 export = f"
+            ),
+            [
+                "(FunctionDeclaration export function f (ParameterList ( )) (Block { }))",
+                "(VariableStatement export (VariableDeclarationList const (VariableDeclaration \
+                 a = (LiteralExpression 1)) , (VariableDeclaration b = (LiteralExpression 2))))",
+                "(FunctionDeclaration export default async function (ParameterList ( )) \
+                 (Block { }))",
+                "(ClassDeclaration export default class { })",
+                "(ExportAssignment export default (BinaryExpression (IdentifierExpression a) + \
+                 (IdentifierExpression b)) ;)",
+                "(ExportDeclaration export (NamedExports { (ExportSpecifier a) , \
+                 (ExportSpecifier b as default) }) from 'm')",
+                "(ExportDeclaration export type (NamespaceExport * as types) from 'm')",
+                "(ExportAssignment export = (IdentifierExpression f))",
+            ]
+        );
+
+        let parse = Parser::new("export if (x) {}\nexport * ;").parse();
+        let messages: Vec<&str> = parse
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            ["Declaration or statement expected.", "'from' expected."]
+        );
+        assert_eq!(parse.syntax().text(), "export if (x) {}\nexport * ;");
+    }
+
+    #[test]
+    fn nested_parentheses_are_tried_as_parameters_once() {
+        let source = "(a = ".repeat(64);
+        let parse = Parser::new(source.as_str()).parse();
+        assert_eq!(parse.syntax().text(), source);
+    }
+
+    #[test]
+    fn deep_nesting_is_reported_without_overflowing() {
+        for (prefix, open, close) in [
+            ("", "(", ")"),
+            ("", "[", "]"),
+            ("", "{", "}"),
+            ("", "{", ")"),
+            ("", "!", ""),
+            ("", "a = ", ""),
+            ("", "x => ", ""),
+            ("", "c ? d : ", ""),
+            ("let ", "[", "]"),
+            ("let t: ", "(", ")"),
+            ("let t: ", "keyof ", ""),
+            ("", "if (a) ", ""),
+        ] {
+            for closed in [true, false] {
+                let source = format!(
+                    "{prefix}{}x{}",
+                    open.repeat(20_000),
+                    if closed {
+                        close.repeat(20_000)
+                    } else {
+                        String::new()
+                    }
+                );
+                let parse = Parser::new(source.as_str()).parse();
+                assert_eq!(parse.syntax().text(), source);
+                assert!(
+                    parse
+                        .diagnostics()
+                        .iter()
+                        .any(|diagnostic| diagnostic.message == "Nesting is too deep."),
+                    "{open:?}"
+                );
+            }
+        }
+    }
+
+    /// Source made of tokens that exercise the parser's ambiguous corners.
+    fn token_soup() -> impl Strategy<Value = String> {
+        let token = prop_oneof![
+            Just("a"),
+            Just("async"),
+            Just("await"),
+            Just("yield"),
+            Just("let"),
+            Just("using"),
+            Just("for"),
+            Just("if"),
+            Just("return"),
+            Just("class"),
+            Just("function"),
+            Just("static"),
+            Just("case"),
+            Just("new"),
+            Just("as"),
+            Just("const"),
+            Just("keyof"),
+            Just("typeof"),
+            Just("get"),
+            Just("extends"),
+            Just("=>"),
+            Just("("),
+            Just(")"),
+            Just("{"),
+            Just("}"),
+            Just("["),
+            Just("]"),
+            Just("<"),
+            Just(">"),
+            Just(">>"),
+            Just("?."),
+            Just("?"),
+            Just(":"),
+            Just(","),
+            Just(";"),
+            Just("..."),
+            Just("."),
+            Just("!"),
+            Just("="),
+            Just("+"),
+            Just("++"),
+            Just("*"),
+            Just("`a${"),
+            Just("}b`"),
+            Just("1"),
+            Just(" "),
+            Just("\n"),
+        ];
+        proptest::collection::vec(token, 0..40).prop_map(|tokens| tokens.join(" "))
+    }
+
+    proptest! {
+        #[test]
+        fn tree_is_lossless_for_any_input(source in "[a-z(){}\\[\\]<>=+*?:.,;!`$ \n'\"/0-9-]{0,40}") {
+            let parse = Parser::new(source.as_str()).parse();
+            prop_assert_eq!(parse.syntax().text(), source);
+        }
+
+        #[test]
+        fn tree_is_lossless_for_token_soup(source in token_soup()) {
+            let parse = Parser::new(source.as_str()).parse();
+            prop_assert_eq!(parse.syntax().text(), source);
+        }
+    }
+}
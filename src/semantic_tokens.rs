@@ -0,0 +1,501 @@
+//! Semantic tokens module — classifies tokens for LSP semantic highlighting
+//! and encodes them in the protocol's relative format.
+//!
+//! This is the lexical tier: it only knows what the lexer knows, so every
+//! identifier is a variable unless it follows a `.`, and a contextual keyword
+//! such as `type` or `of` is a keyword only where the tokens around it show it
+//! is used as one. The analyzer can refine identifiers into classes,
+//! parameters and properties with the modifiers declared in the legend.
+
+use std::ops::Range;
+
+use tower_lsp::lsp_types::{
+    SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokensLegend,
+};
+
+use crate::line_index::{LineIndex, PositionEncoding};
+use crate::token::SpannedToken;
+use crate::token_kind::TokenKind;
+
+/// The token types this server reports. The discriminants are indices into
+/// [`legend`]'s token types, so the order of the two must match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum SemanticTokenKind {
+    Keyword,
+    String,
+    Number,
+    Regexp,
+    Comment,
+    Operator,
+    Variable,
+    Property,
+    Parameter,
+    Class,
+    Interface,
+    Enum,
+    Type,
+    TypeParameter,
+    Function,
+    Method,
+    Namespace,
+}
+
+impl SemanticTokenKind {
+    const ALL: [Self; 17] = [
+        Self::Keyword,
+        Self::String,
+        Self::Number,
+        Self::Regexp,
+        Self::Comment,
+        Self::Operator,
+        Self::Variable,
+        Self::Property,
+        Self::Parameter,
+        Self::Class,
+        Self::Interface,
+        Self::Enum,
+        Self::Type,
+        Self::TypeParameter,
+        Self::Function,
+        Self::Method,
+        Self::Namespace,
+    ];
+
+    fn token_type(self) -> SemanticTokenType {
+        match self {
+            Self::Keyword => SemanticTokenType::KEYWORD,
+            Self::String => SemanticTokenType::STRING,
+            Self::Number => SemanticTokenType::NUMBER,
+            Self::Regexp => SemanticTokenType::REGEXP,
+            Self::Comment => SemanticTokenType::COMMENT,
+            Self::Operator => SemanticTokenType::OPERATOR,
+            Self::Variable => SemanticTokenType::VARIABLE,
+            Self::Property => SemanticTokenType::PROPERTY,
+            Self::Parameter => SemanticTokenType::PARAMETER,
+            Self::Class => SemanticTokenType::CLASS,
+            Self::Interface => SemanticTokenType::INTERFACE,
+            Self::Enum => SemanticTokenType::ENUM,
+            Self::Type => SemanticTokenType::TYPE,
+            Self::TypeParameter => SemanticTokenType::TYPE_PARAMETER,
+            Self::Function => SemanticTokenType::FUNCTION,
+            Self::Method => SemanticTokenType::METHOD,
+            Self::Namespace => SemanticTokenType::NAMESPACE,
+        }
+    }
+
+    /// The lexical classification of a token, or `None` for tokens left to
+    /// the editor's own highlighting: punctuation such as brackets and
+    /// separators, whitespace and JSX text. Only reserved words, strict mode
+    /// ones included, are keywords whatever surrounds them; contextual
+    /// keywords are variables here, see [`SemanticTokenKind::in_context`].
+    pub fn of(kind: TokenKind) -> Option<Self> {
+        let kind = match kind {
+            _ if kind.is_reserved_word() || kind.is_future_reserved_word() => Self::Keyword,
+            _ if kind.is_contextual_keyword() => Self::Variable,
+            TokenKind::StringLiteral
+            | TokenKind::NoSubstitutionTemplateLiteral
+            | TokenKind::TemplateHead
+            | TokenKind::TemplateMiddle
+            | TokenKind::TemplateTail => Self::String,
+            TokenKind::NumericLiteral | TokenKind::BigIntLiteral => Self::Number,
+            TokenKind::RegularExpressionLiteral => Self::Regexp,
+            TokenKind::SingleLineCommentTrivia
+            | TokenKind::MultiLineCommentTrivia
+            | TokenKind::ShebangTrivia => Self::Comment,
+            TokenKind::Identifier => Self::Variable,
+            TokenKind::PrivateIdentifier => Self::Property,
+            TokenKind::OpenBrace
+            | TokenKind::CloseBrace
+            | TokenKind::OpenParen
+            | TokenKind::CloseParen
+            | TokenKind::OpenBracket
+            | TokenKind::CloseBracket
+            | TokenKind::Dot
+            | TokenKind::DotDotDot
+            | TokenKind::Semicolon
+            | TokenKind::Comma
+            | TokenKind::QuestionDot
+            | TokenKind::LessThanSlash
+            | TokenKind::Colon
+            | TokenKind::At
+            | TokenKind::Hash => return None,
+            _ if kind.is_punctuation() => Self::Operator,
+            _ => return None,
+        };
+        Some(kind)
+    }
+
+    /// The classification of `tokens[index]` given the tokens around it: a
+    /// name after `.` or `?.` is a property, a primitive type after `:` is a
+    /// keyword, and any other contextual keyword is a keyword unless it is
+    /// used as a name.
+    pub fn in_context(tokens: &[SpannedToken], index: usize) -> Option<Self> {
+        let kind = tokens[index].kind;
+        let previous = tokens[..index]
+            .iter()
+            .rev()
+            .find(|token| !token.is_trivia())
+            .map(|token| token.kind);
+        let is_name = kind == TokenKind::Identifier || kind.is_keyword();
+        if is_name && matches!(previous, Some(TokenKind::Dot | TokenKind::QuestionDot)) {
+            return Some(Self::Property);
+        }
+        // The annotation in `let n: number = 1`, which the `=` would
+        // otherwise make look like an assigned name
+        if previous == Some(TokenKind::Colon) && is_primitive_type(kind) {
+            return Some(Self::Keyword);
+        }
+        if kind.is_contextual_keyword() && is_used_as_keyword(kind, &tokens[index + 1..]) {
+            return Some(Self::Keyword);
+        }
+        Self::of(kind)
+    }
+}
+
+/// Whether `kind` is a keyword naming a primitive type.
+fn is_primitive_type(kind: TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::Number
+            | TokenKind::String
+            | TokenKind::Boolean
+            | TokenKind::Any
+            | TokenKind::Unknown
+            | TokenKind::Never
+            | TokenKind::Object
+            | TokenKind::Symbol
+            | TokenKind::BigInt
+            | TokenKind::Undefined
+    )
+}
+
+/// Whether a contextual keyword of `kind` followed by `rest` is used as a
+/// keyword rather than as a name that is called, assigned, accessed or
+/// used as a key, as in `async(x)`, `type = 1`, `of.length` or `{ get: f }`.
+fn is_used_as_keyword(kind: TokenKind, rest: &[SpannedToken]) -> bool {
+    let mut significant = rest.iter().filter(|token| !token.is_trivia());
+    let Some(next) = significant.next() else {
+        return true;
+    };
+    match next.kind {
+        next if next.is_assignment_operator() => false,
+        TokenKind::Dot
+        | TokenKind::QuestionDot
+        | TokenKind::EqualsGreaterThan
+        | TokenKind::Colon
+        | TokenKind::PlusPlus
+        | TokenKind::MinusMinus => false,
+        // `async (x) => y` against `async(x)`
+        TokenKind::OpenParen if kind == TokenKind::Async => {
+            let mut depth = 0usize;
+            let mut close = significant.skip_while(|token| {
+                match token.kind {
+                    TokenKind::OpenParen => depth += 1,
+                    TokenKind::CloseParen if depth == 0 => return false,
+                    TokenKind::CloseParen => depth -= 1,
+                    _ => {}
+                }
+                true
+            });
+            close.nth(1).map(|token| token.kind) == Some(TokenKind::EqualsGreaterThan)
+        }
+        // Keywords that take an operand, as in `await (p)` or `x as (A | B)`
+        TokenKind::OpenParen => matches!(
+            kind,
+            TokenKind::Await
+                | TokenKind::Of
+                | TokenKind::As
+                | TokenKind::Satisfies
+                | TokenKind::KeyOf
+                | TokenKind::Readonly
+                | TokenKind::Unique
+                | TokenKind::Infer
+                | TokenKind::Is
+                | TokenKind::Asserts
+        ),
+        _ => true,
+    }
+}
+
+/// Modifier bits, as indices into [`legend`]'s token modifiers.
+pub mod modifiers {
+    pub const DECLARATION: u32 = 1 << 0;
+    pub const READONLY: u32 = 1 << 1;
+    pub const STATIC: u32 = 1 << 2;
+    pub const DEPRECATED: u32 = 1 << 3;
+    pub const ASYNC: u32 = 1 << 4;
+    pub const DEFAULT_LIBRARY: u32 = 1 << 5;
+}
+
+/// The legend announced in `initialize`.
+pub fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: SemanticTokenKind::ALL
+            .iter()
+            .map(|kind| kind.token_type())
+            .collect(),
+        token_modifiers: vec![
+            SemanticTokenModifier::DECLARATION,
+            SemanticTokenModifier::READONLY,
+            SemanticTokenModifier::STATIC,
+            SemanticTokenModifier::DEPRECATED,
+            SemanticTokenModifier::ASYNC,
+            SemanticTokenModifier::DEFAULT_LIBRARY,
+        ],
+    }
+}
+
+/// Classify the tokens overlapping the byte `range` and encode them relative
+/// to one another as LSP expects. `tokens` is the whole document in source
+/// order, so that tokens at the edges of `range` are classified by their
+/// neighbours outside it just as in a full request. Tokens spanning several
+/// lines, such as block comments and templates, are split into one token
+/// per line because clients need not support multi-line tokens.
+pub fn encode(
+    text: &str,
+    line_index: &LineIndex,
+    tokens: &[SpannedToken],
+    range: Range<usize>,
+    encoding: PositionEncoding,
+) -> Vec<SemanticToken> {
+    let mut encoded = Vec::new();
+    let mut previous_line = 0;
+    let mut previous_start = 0;
+    for (index, token) in tokens.iter().enumerate() {
+        if token.end <= range.start {
+            continue;
+        }
+        if token.start >= range.end {
+            break;
+        }
+        let Some(kind) = SemanticTokenKind::in_context(tokens, index) else {
+            continue;
+        };
+
+        for (start, end) in line_segments(text, token.start, token.end) {
+            let position = line_index.position(text, start, encoding);
+            let length = line_index.position(text, end, encoding).character - position.character;
+            let delta_line = position.line - previous_line;
+            let delta_start = if delta_line == 0 {
+                position.character - previous_start
+            } else {
+                position.character
+            };
+
+            encoded.push(SemanticToken {
+                delta_line,
+                delta_start,
+                length,
+                token_type: kind as u32,
+                token_modifiers_bitset: 0,
+            });
+            previous_line = position.line;
+            previous_start = position.character;
+        }
+    }
+    encoded
+}
+
+/// The non-empty pieces of `start..end` on each line, split at the line
+/// breaks LSP recognises.
+fn line_segments(text: &str, start: usize, end: usize) -> impl Iterator<Item = (usize, usize)> {
+    let mut segment_start = start;
+    let mut segments = Vec::new();
+    let bytes = text.as_bytes();
+    let mut index = start;
+    while index < end {
+        if matches!(bytes[index], b'\r' | b'\n') {
+            segments.push((segment_start, index));
+            if bytes[index] == b'\r' && bytes.get(index + 1) == Some(&b'\n') {
+                index += 1;
+            }
+            segment_start = index + 1;
+        }
+        index += 1;
+    }
+    segments.push((segment_start, end.max(segment_start)));
+    segments.into_iter().filter(|(start, end)| start < end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    /// Decode `encoded` back to `(line, character, length, kind)` tuples.
+    fn decode(encoded: &[SemanticToken]) -> Vec<(u32, u32, u32, SemanticTokenKind)> {
+        let mut line = 0;
+        let mut character = 0;
+        encoded
+            .iter()
+            .map(|token| {
+                if token.delta_line > 0 {
+                    character = 0;
+                }
+                line += token.delta_line;
+                character += token.delta_start;
+                (
+                    line,
+                    character,
+                    token.length,
+                    SemanticTokenKind::ALL[token.token_type as usize],
+                )
+            })
+            .collect()
+    }
+
+    fn highlight(
+        text: &str,
+        encoding: PositionEncoding,
+    ) -> Vec<(u32, u32, u32, SemanticTokenKind)> {
+        let tokens = Lexer::new(text).lex();
+        decode(&encode(
+            text,
+            &LineIndex::new(text),
+            &tokens,
+            0..text.len(),
+            encoding,
+        ))
+    }
+
+    #[test]
+    fn legend_matches_kinds() {
+        let legend = legend();
+        for (index, kind) in SemanticTokenKind::ALL.iter().enumerate() {
+            assert_eq!(*kind as usize, index);
+            assert_eq!(legend.token_types[index], kind.token_type());
+        }
+        assert_eq!(
+            legend.token_modifiers[modifiers::DEPRECATED.trailing_zeros() as usize],
+            SemanticTokenModifier::DEPRECATED
+        );
+    }
+
+    #[test]
+    fn classifies_lexical_tokens() {
+        use SemanticTokenKind::*;
+
+        let text = "const re = /a+/g; // note\nlet n = 1n + x['s'] ?? #p < b;";
+        assert_eq!(
+            highlight(text, PositionEncoding::Utf16),
+            [
+                (0, 0, 5, Keyword),
+                (0, 6, 2, Variable),
+                (0, 9, 1, Operator),
+                (0, 11, 5, Regexp),
+                (0, 18, 7, Comment),
+                (1, 0, 3, Keyword),
+                (1, 4, 1, Variable),
+                (1, 6, 1, Operator),
+                (1, 8, 2, Number),
+                (1, 11, 1, Operator),
+                (1, 13, 1, Variable),
+                (1, 15, 3, String),
+                (1, 20, 2, Operator),
+                (1, 23, 2, Property),
+                (1, 26, 1, Operator),
+                (1, 28, 1, Variable),
+            ]
+        );
+    }
+
+    #[test]
+    fn classifies_names_by_their_neighbours() {
+        use SemanticTokenKind::*;
+
+        let kinds = |text: &str| -> Vec<SemanticTokenKind> {
+            highlight(text, PositionEncoding::Utf16)
+                .into_iter()
+                .map(|(_, _, _, kind)| kind)
+                .collect()
+        };
+        assert_eq!(kinds("a.delete()"), [Variable, Property]);
+        assert_eq!(kinds("let of = 1"), [Keyword, Variable, Operator, Number]);
+        assert_eq!(kinds("let type = 1"), [Keyword, Variable, Operator, Number]);
+        assert_eq!(
+            kinds("let n: number = 1"),
+            [Keyword, Variable, Keyword, Operator, Number]
+        );
+        assert_eq!(
+            kinds("const s: string = \"\""),
+            [Keyword, Variable, Keyword, Operator, String]
+        );
+        assert_eq!(
+            kinds("map.set(k); promise?.catch(f); obj.type"),
+            [
+                Variable, Property, Variable, Variable, Property, Variable, Variable, Property
+            ]
+        );
+        assert_eq!(kinds("async(x)"), [Variable, Variable]);
+        assert_eq!(
+            kinds("async (x) => x"),
+            [Keyword, Variable, Operator, Variable]
+        );
+        assert_eq!(
+            kinds("type T = string; for (x of xs) await (p);"),
+            [
+                Keyword, Variable, Operator, Keyword, Keyword, Variable, Keyword, Variable,
+                Keyword, Variable
+            ]
+        );
+    }
+
+    #[test]
+    fn classifies_ranges_like_the_whole_document() {
+        let text = "a.\nfoo; let type = 1;";
+        let tokens = Lexer::new(text).lex();
+        let line_index = LineIndex::new(text);
+        let full = highlight(text, PositionEncoding::Utf16);
+
+        // From `foo`, after the `.` before it, up to `type`, before the `=`
+        let range = text.find("foo").unwrap()..text.find(" =").unwrap();
+        let start = line_index.position(text, range.start, PositionEncoding::Utf16);
+        let end = line_index.position(text, range.end, PositionEncoding::Utf16);
+        let expected: Vec<_> = full
+            .into_iter()
+            .filter(|&(line, character, _, _)| {
+                (start.line, start.character) <= (line, character)
+                    && (line, character) < (end.line, end.character)
+            })
+            .collect();
+        let ranged = decode(&encode(
+            text,
+            &line_index,
+            &tokens,
+            range,
+            PositionEncoding::Utf16,
+        ));
+        assert_eq!(ranged, expected);
+        assert_eq!(
+            ranged.first().map(|&(_, _, _, kind)| kind),
+            Some(SemanticTokenKind::Property)
+        );
+        assert_eq!(
+            ranged.last().map(|&(_, _, _, kind)| kind),
+            Some(SemanticTokenKind::Variable)
+        );
+    }
+
+    #[test]
+    fn splits_multi_line_tokens() {
+        use SemanticTokenKind::*;
+
+        let text = "/* a\r\n\r\n  é */ `x\ny${z}`";
+        assert_eq!(
+            highlight(text, PositionEncoding::Utf16),
+            [
+                (0, 0, 4, Comment),
+                (2, 0, 6, Comment),
+                (2, 7, 2, String),
+                (3, 0, 3, String),
+                (3, 3, 1, Variable),
+                (3, 4, 2, String),
+            ]
+        );
+        assert_eq!(
+            highlight(text, PositionEncoding::Utf8)[1],
+            (2, 0, 7, Comment)
+        );
+    }
+}
@@ -5,20 +5,253 @@ use log::{info, warn};
 use tokio::sync::Mutex;
 use tower_lsp::jsonrpc::Result as JsonResult;
 use tower_lsp::lsp_types::{
-    CompletionOptions, CompletionParams, CompletionResponse, DidChangeTextDocumentParams,
-    DidCloseTextDocumentParams, DidOpenTextDocumentParams, DidSaveTextDocumentParams, Hover,
-    HoverParams, HoverProviderCapability, InitializeParams, InitializeResult, InitializedParams,
-    MessageType, ServerCapabilities, ServerInfo, TextDocumentSyncCapability, TextDocumentSyncKind,
+    CompletionOptions, CompletionParams, CompletionResponse, Diagnostic as LspDiagnostic,
+    DiagnosticSeverity, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DidSaveTextDocumentParams, Hover, HoverParams,
+    HoverProviderCapability, InitializeParams, InitializeResult, InitializedParams, MessageType,
+    NumberOrString, Position, Range, SemanticTokens, SemanticTokensFullOptions,
+    SemanticTokensOptions, SemanticTokensParams, SemanticTokensRangeParams,
+    SemanticTokensRangeResult, SemanticTokensResult, SemanticTokensServerCapabilities,
+    ServerCapabilities, ServerInfo, TextDocumentContentChangeEvent, TextDocumentSyncCapability,
+    TextDocumentSyncKind, Url,
 };
 use tower_lsp::{
     Client, ClientSocket, LanguageServer, LspService, Server as LspServer, async_trait,
 };
 
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::jsx::LanguageVariant;
+use crate::lexer::{Lexer, LexerOptions, TextEdit};
+use crate::line_index::{LineIndex, PositionEncoding};
+use crate::semantic_tokens;
+use crate::symbol::Interner;
+use crate::token::SpannedToken;
+use crate::trivia::TokenList;
+
 type SharedState = Arc<Mutex<ServerState>>;
 
+/// Identifier names of all documents are interned into the one `interner`,
+/// so symbols compare across files.
 #[derive(Default)]
 struct ServerState {
-    documents: HashMap<String, String>,
+    documents: HashMap<String, Document>,
+    position_encoding: PositionEncoding,
+    interner: Arc<Interner>,
+    /// How many names `interner` held when it was last rebuilt.
+    live_names: usize,
+}
+
+impl ServerState {
+    /// Replace the interner with one holding only the names of the open
+    /// documents, once stale names outnumber them. Names stay interned after
+    /// the document using them closes or the edit removing them, and typing
+    /// an identifier interns each of its prefixes, so without this the
+    /// table would grow for as long as the server runs.
+    fn collect_names(&mut self) {
+        let stale = if self.documents.is_empty() {
+            !self.interner.is_empty()
+        } else {
+            self.interner.len() > 2 * self.live_names + INTERNER_SLACK
+        };
+        if !stale {
+            return;
+        }
+
+        self.interner = Arc::default();
+        for document in self.documents.values_mut() {
+            *document = Document::from_text(
+                Arc::clone(&document.text),
+                document.language_variant,
+                Arc::clone(&self.interner),
+            );
+        }
+        self.live_names = self.interner.len();
+    }
+}
+
+/// The longest document the server lexes, since token payloads store their
+/// offsets in 32 bits. Longer documents are kept but get no tokens.
+const MAX_DOCUMENT_LEN: usize = u32::MAX as usize;
+
+/// How many stale names the interner may hold beyond twice the live ones
+/// before [`ServerState::collect_names`] rebuilds it.
+const INTERNER_SLACK: usize = 1 << 16;
+
+/// An open text document together with the index used to translate LSP
+/// positions into byte offsets, whether it is lexed as JSX, and its tokens,
+/// which are kept up to date incrementally as the document is edited.
+struct Document {
+    text: Arc<str>,
+    line_index: LineIndex,
+    language_variant: LanguageVariant,
+    tokens: TokenList,
+    diagnostics: Vec<Diagnostic>,
+    /// The server's shared interner.
+    interner: Arc<Interner>,
+}
+
+impl Document {
+    fn new(text: String, language_variant: LanguageVariant, interner: Arc<Interner>) -> Self {
+        Self::from_text(text.into(), language_variant, interner)
+    }
+
+    fn from_text(
+        text: Arc<str>,
+        language_variant: LanguageVariant,
+        interner: Arc<Interner>,
+    ) -> Self {
+        if !Self::fits(&text, &interner) {
+            // tsc has no code for this
+            let diagnostic = Diagnostic::error(1000, "File is too large to be analyzed.", 0, 0);
+            return Self {
+                line_index: LineIndex::new(&text),
+                text,
+                language_variant,
+                tokens: TokenList::default(),
+                diagnostics: vec![diagnostic],
+                interner,
+            };
+        }
+
+        let mut lexer = Self::lexer(&text, language_variant, &interner);
+        let (tokens, diagnostics) = lexer.lex_with_diagnostics();
+        Self {
+            line_index: LineIndex::new(&text),
+            text,
+            language_variant,
+            tokens: TokenList::new(tokens),
+            diagnostics,
+            interner,
+        }
+    }
+
+    /// Whether `text` can be lexed: its offsets fit in 32 bits, and the
+    /// interner has room for its names, of which there are at most one per
+    /// byte.
+    fn fits(text: &str, interner: &Interner) -> bool {
+        text.len() <= MAX_DOCUMENT_LEN && text.len() <= interner.remaining()
+    }
+
+    fn lexer(
+        text: &Arc<str>,
+        language_variant: LanguageVariant,
+        interner: &Arc<Interner>,
+    ) -> Lexer {
+        let options = LexerOptions {
+            language_variant,
+            ..LexerOptions::default()
+        };
+        Lexer::with_options(Arc::clone(text), options).with_interner(Arc::clone(interner))
+    }
+
+    /// Apply one content change from `didChange`. Ranged changes relex only
+    /// the edited region; a change without a range replaces the document.
+    fn apply_change(&mut self, change: TextDocumentContentChangeEvent, encoding: PositionEncoding) {
+        let Some(range) = change.range else {
+            *self = Self::new(
+                change.text,
+                self.language_variant,
+                Arc::clone(&self.interner),
+            );
+            return;
+        };
+
+        let start = self.offset_at(range.start, encoding);
+        let old_end = self.offset_at(range.end, encoding).max(start);
+        let mut text =
+            String::with_capacity(self.text.len() - (old_end - start) + change.text.len());
+        text.push_str(&self.text[..start]);
+        text.push_str(&change.text);
+        text.push_str(&self.text[old_end..]);
+        let text: Arc<str> = text.into();
+        // A document too large to lex has no tokens to relex, before or after
+        if self.tokens.all().is_empty() || !Self::fits(&text, &self.interner) {
+            *self = Self::from_text(text, self.language_variant, Arc::clone(&self.interner));
+            return;
+        }
+
+        let edit = TextEdit {
+            start,
+            old_end,
+            new_end: start + change.text.len(),
+        };
+        let mut lexer = Self::lexer(&text, self.language_variant, &self.interner);
+        self.tokens.update(|tokens| {
+            lexer.relex(&self.text, tokens, &mut self.diagnostics, &edit);
+        });
+        self.line_index.apply_edit(&text, &edit);
+        self.text = text;
+    }
+
+    fn offset_at(&self, position: Position, encoding: PositionEncoding) -> usize {
+        self.line_index.offset(&self.text, position, encoding)
+    }
+
+    /// Syntax diagnostics for the document, with ranges converted to LSP
+    /// positions in the negotiated encoding.
+    fn diagnostics(&self, encoding: PositionEncoding) -> Vec<LspDiagnostic> {
+        self.diagnostics
+            .iter()
+            .map(|diagnostic| self.to_lsp_diagnostic(diagnostic, encoding))
+            .collect()
+    }
+
+    fn to_lsp_diagnostic(
+        &self,
+        diagnostic: &Diagnostic,
+        encoding: PositionEncoding,
+    ) -> LspDiagnostic {
+        let range = Range::new(
+            self.line_index
+                .position(&self.text, diagnostic.start, encoding),
+            self.line_index
+                .position(&self.text, diagnostic.end, encoding),
+        );
+        let severity = match diagnostic.severity {
+            Severity::Error => DiagnosticSeverity::ERROR,
+            Severity::Warning => DiagnosticSeverity::WARNING,
+        };
+
+        LspDiagnostic {
+            range,
+            severity: Some(severity),
+            code: Some(NumberOrString::Number(diagnostic.code as i32)),
+            source: Some("ts".to_string()),
+            message: diagnostic.message.clone(),
+            ..LspDiagnostic::default()
+        }
+    }
+
+    /// Semantic tokens for the whole document, or for the tokens that
+    /// overlap `range`.
+    fn semantic_tokens(&self, range: Option<Range>, encoding: PositionEncoding) -> SemanticTokens {
+        let (start, end) = match range {
+            Some(range) => (
+                self.offset_at(range.start, encoding),
+                self.offset_at(range.end, encoding),
+            ),
+            None => (0, self.text.len()),
+        };
+
+        SemanticTokens {
+            result_id: None,
+            data: semantic_tokens::encode(
+                &self.text,
+                &self.line_index,
+                self.tokens.all(),
+                start..end,
+                encoding,
+            ),
+        }
+    }
+
+    /// The non-trivia token under the byte `offset`, if any.
+    fn token_at(&self, offset: usize) -> Option<SpannedToken> {
+        self.tokens
+            .iter()
+            .find(|token| token.start < token.end && token.contains_offset(offset))
+            .cloned()
+    }
 }
 
 trait ServeOutcome {
@@ -53,6 +286,24 @@ impl Backend {
             .log_message(MessageType::INFO, message.to_string())
             .await;
     }
+
+    /// Send the diagnostics stored with the document, which were found when
+    /// it was last opened or changed, to the client. A document that is no
+    /// longer open gets its diagnostics cleared.
+    async fn publish_diagnostics(&self, uri: Url, version: Option<i32>) {
+        let diagnostics = {
+            let state = self.state.lock().await;
+            state
+                .documents
+                .get(uri.as_str())
+                .map(|document| document.diagnostics(state.position_encoding))
+                .unwrap_or_default()
+        };
+
+        self.client
+            .publish_diagnostics(uri, diagnostics, version)
+            .await;
+    }
 }
 
 #[async_trait]
@@ -61,10 +312,30 @@ impl LanguageServer for Backend {
         info!("Received initialize request: {params:?}");
         self.log("Language server initialization started.").await;
 
+        let position_encoding = PositionEncoding::negotiate(
+            params
+                .capabilities
+                .general
+                .as_ref()
+                .and_then(|general| general.position_encodings.as_deref()),
+        );
+        self.state.lock().await.position_encoding = position_encoding;
+
         let capabilities = ServerCapabilities {
-            text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+            position_encoding: Some(position_encoding.kind()),
+            text_document_sync: Some(TextDocumentSyncCapability::Kind(
+                TextDocumentSyncKind::INCREMENTAL,
+            )),
             completion_provider: Some(CompletionOptions::default()),
             hover_provider: Some(HoverProviderCapability::Simple(true)),
+            semantic_tokens_provider: Some(
+                SemanticTokensServerCapabilities::SemanticTokensOptions(SemanticTokensOptions {
+                    legend: semantic_tokens::legend(),
+                    range: Some(true),
+                    full: Some(SemanticTokensFullOptions::Bool(true)),
+                    ..SemanticTokensOptions::default()
+                }),
+            ),
             ..ServerCapabilities::default()
         };
 
@@ -74,7 +345,6 @@ impl LanguageServer for Backend {
                 name: env!("CARGO_PKG_NAME").to_string(),
                 version: Some(env!("CARGO_PKG_VERSION").to_string()),
             }),
-            ..InitializeResult::default()
         };
 
         Ok(result)
@@ -94,10 +364,21 @@ impl LanguageServer for Backend {
         let text_document = params.text_document;
         let uri = text_document.uri.to_string();
         info!("Opened document: {uri}");
-        let mut state = self.state.lock().await;
-        state.documents.insert(uri.clone(), text_document.text);
+        let language_variant = LanguageVariant::from_language_id(&text_document.language_id)
+            .unwrap_or_else(|| LanguageVariant::from_path(text_document.uri.path()));
+        {
+            let mut state = self.state.lock().await;
+            state.collect_names();
+            let interner = Arc::clone(&state.interner);
+            state.documents.insert(
+                uri.clone(),
+                Document::new(text_document.text, language_variant, interner),
+            );
+        }
 
         self.log(&format!("Document opened: {uri}")).await;
+        self.publish_diagnostics(text_document.uri, Some(text_document.version))
+            .await;
     }
 
     async fn did_change(&self, params: DidChangeTextDocumentParams) {
@@ -111,14 +392,32 @@ impl LanguageServer for Backend {
             content_changes.len()
         );
 
-        let mut state = self.state.lock().await;
-        if let Some(change) = content_changes.into_iter().last() {
-            state.documents.insert(uri.clone(), change.text);
-        } else {
-            warn!("No change content supplied for {uri}");
+        {
+            let mut state = self.state.lock().await;
+            let encoding = state.position_encoding;
+            if content_changes.is_empty() {
+                warn!("No change content supplied for {uri}");
+            }
+            for change in content_changes {
+                state.collect_names();
+                match state.documents.get_mut(&uri) {
+                    Some(document) => document.apply_change(change, encoding),
+                    None if change.range.is_none() => {
+                        let language_variant = LanguageVariant::from_path(text_document.uri.path());
+                        let interner = Arc::clone(&state.interner);
+                        state.documents.insert(
+                            uri.clone(),
+                            Document::new(change.text, language_variant, interner),
+                        );
+                    }
+                    None => warn!("Ranged change for unknown document {uri}"),
+                }
+            }
         }
 
         self.log(&format!("Document changed: {uri}")).await;
+        self.publish_diagnostics(text_document.uri, Some(text_document.version))
+            .await;
     }
 
     async fn did_save(&self, params: DidSaveTextDocumentParams) {
@@ -130,17 +429,59 @@ impl LanguageServer for Backend {
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
         let uri = params.text_document.uri.to_string();
         info!("Closed document: {uri}");
-        let mut state = self.state.lock().await;
-        state.documents.remove(&uri);
+        {
+            let mut state = self.state.lock().await;
+            state.documents.remove(&uri);
+            state.collect_names();
+        }
 
         self.log(&format!("Document closed: {uri}")).await;
+        self.publish_diagnostics(params.text_document.uri, None)
+            .await;
     }
 
     async fn hover(&self, params: HoverParams) -> JsonResult<Option<Hover>> {
         info!("Hover request: {params:?}");
+        let position_params = params.text_document_position_params;
+        let uri = position_params.text_document.uri.to_string();
+
+        let state = self.state.lock().await;
+        if let Some(document) = state.documents.get(&uri) {
+            let offset = document.offset_at(position_params.position, state.position_encoding);
+            info!("Hovered token: {:?}", document.token_at(offset));
+        }
+
         Ok(None)
     }
 
+    async fn semantic_tokens_full(
+        &self,
+        params: SemanticTokensParams,
+    ) -> JsonResult<Option<SemanticTokensResult>> {
+        info!("Semantic tokens request: {params:?}");
+        let uri = params.text_document.uri.to_string();
+
+        let state = self.state.lock().await;
+        Ok(state.documents.get(&uri).map(|document| {
+            SemanticTokensResult::Tokens(document.semantic_tokens(None, state.position_encoding))
+        }))
+    }
+
+    async fn semantic_tokens_range(
+        &self,
+        params: SemanticTokensRangeParams,
+    ) -> JsonResult<Option<SemanticTokensRangeResult>> {
+        info!("Semantic tokens range request: {params:?}");
+        let uri = params.text_document.uri.to_string();
+
+        let state = self.state.lock().await;
+        Ok(state.documents.get(&uri).map(|document| {
+            SemanticTokensRangeResult::Tokens(
+                document.semantic_tokens(Some(params.range), state.position_encoding),
+            )
+        }))
+    }
+
     async fn completion(&self, params: CompletionParams) -> JsonResult<Option<CompletionResponse>> {
         info!("Completion request: {params:?}");
         Ok(Some(CompletionResponse::Array(Vec::new())))
@@ -151,6 +492,12 @@ pub struct Server {
     state: SharedState,
 }
 
+impl Default for Server {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Server {
     pub fn new() -> Self {
         Self {
@@ -170,9 +517,7 @@ impl Server {
         let stdout = tokio::io::stdout();
 
         info!("Starting LSP server");
-        LspServer::new(stdin, stdout, socket)
-            .serve(service)
-            .await
-            .into_json_result()
+        let outcome = LspServer::new(stdin, stdout, socket).serve(service).await;
+        outcome.into_json_result()
     }
 }
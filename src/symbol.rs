@@ -0,0 +1,132 @@
+//! Symbol module — interned identifier names.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+
+/// An interned string. Equal names interned into the same [`Interner`]
+/// always get the same `Symbol`, so comparing and hashing symbols is an
+/// integer operation. The server interns the names of every open document
+/// into one shared interner, so symbols from different files compare too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    /// The symbol's index in its interner.
+    pub fn as_u32(self) -> u32 {
+        self.0
+    }
+}
+
+/// A table of interned names, shared behind an `Arc` by every lexer,
+/// scanner and parser that should agree on symbols. Interning takes `&self`
+/// and is safe from several threads at once. Names are never removed, since
+/// any token still held anywhere may refer to them; an owner that wants the
+/// memory of names no longer used back replaces the interner with a new one
+/// and interns the names it still needs again.
+#[derive(Debug, Default)]
+pub struct Interner {
+    table: RwLock<Table>,
+}
+
+#[derive(Debug, Default)]
+struct Table {
+    ids: HashMap<Arc<str>, Symbol>,
+    names: Vec<Arc<str>>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Intern `name`, returning the existing symbol if it was seen before.
+    /// Panics if the table is full; callers that cannot bound how many names
+    /// they intern check [`Interner::remaining`] first.
+    pub fn intern(&self, name: &str) -> Symbol {
+        if let Some(symbol) = self.get(name) {
+            return symbol;
+        }
+        let mut table = self
+            .table
+            .write()
+            .unwrap_or_else(|error| error.into_inner());
+        // Another thread may have interned `name` between the two locks
+        if let Some(&symbol) = table.ids.get(name) {
+            return symbol;
+        }
+        let index = u32::try_from(table.names.len()).expect("fewer than 2^32 interned names");
+        let symbol = Symbol(index);
+        let name: Arc<str> = name.into();
+        table.names.push(Arc::clone(&name));
+        table.ids.insert(name, symbol);
+        symbol
+    }
+
+    /// The symbol for `name`, if it has been interned.
+    pub fn get(&self, name: &str) -> Option<Symbol> {
+        self.read().ids.get(name).copied()
+    }
+
+    /// The name `symbol` was interned from. Panics if `symbol` comes from a
+    /// different interner.
+    pub fn resolve(&self, symbol: Symbol) -> Arc<str> {
+        Arc::clone(&self.read().names[symbol.0 as usize])
+    }
+
+    /// The number of distinct names interned.
+    pub fn len(&self) -> usize {
+        self.read().names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// How many more distinct names can be interned.
+    pub fn remaining(&self) -> usize {
+        u32::MAX as usize - self.len()
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Table> {
+        // The table is consistent after every write, so a panic elsewhere
+        // while holding the lock leaves nothing to repair
+        self.table.read().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interns_equal_names_to_one_symbol() {
+        let interner = Interner::new();
+        let a = interner.intern("name");
+        let b = interner.intern(String::from("name").as_str());
+
+        assert_eq!(a, b);
+        assert_ne!(a, interner.intern("other"));
+        assert_eq!(&*interner.resolve(a), "name");
+        assert_eq!(interner.get("name"), Some(a));
+        assert_eq!(interner.get("missing"), None);
+        assert_eq!(interner.len(), 2);
+        assert_eq!(interner.remaining(), u32::MAX as usize - 2);
+    }
+
+    #[test]
+    fn shares_symbols_across_threads() {
+        let interner = Arc::new(Interner::new());
+        let symbols: Vec<Symbol> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| scope.spawn(|| interner.intern("shared")))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+
+        assert!(symbols.iter().all(|&symbol| symbol == symbols[0]));
+        assert_eq!(interner.len(), 1);
+    }
+}
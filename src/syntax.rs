@@ -0,0 +1,842 @@
+//! Syntax module — a lossless concrete syntax tree.
+//!
+//! The tree has two layers. Green nodes are immutable and know only their
+//! kind, their children and their length in bytes; they are shared through
+//! `Arc`, so a subtree an edit did not touch can be reused as is. Red nodes
+//! ([`SyntaxNode`] and [`SyntaxToken`]) are created on demand while walking
+//! a green tree and add what depends on position: a parent pointer and an
+//! absolute offset.
+//!
+//! Every token the lexer produces, trivia included, is a leaf of the tree,
+//! so the text of a tree is exactly the source it was built from.
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use crate::text::Text;
+use crate::token::{SpannedToken, TokenPayload};
+use crate::token_kind::TokenKind;
+
+/// The kinds of interior nodes. Leaves are tokens and have a [`TokenKind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(u16)]
+pub enum NodeKind {
+    SourceFile,
+    /// Tokens the parser could not fit anywhere.
+    Error,
+
+    // Statements
+    ExpressionStatement,
+    Block,
+    EmptyStatement,
+    /// `var`, `let`, `const`, `using` and `await using` declarations.
+    VariableStatement,
+    VariableDeclarationList,
+    VariableDeclaration,
+    IfStatement,
+    DoStatement,
+    WhileStatement,
+    ForStatement,
+    ForInStatement,
+    ForOfStatement,
+    ContinueStatement,
+    BreakStatement,
+    ReturnStatement,
+    WithStatement,
+    SwitchStatement,
+    /// The braces of a `switch` and the clauses inside them.
+    CaseBlock,
+    CaseClause,
+    DefaultClause,
+    LabeledStatement,
+    ThrowStatement,
+    TryStatement,
+    CatchClause,
+    DebuggerStatement,
+
+    // Declarations
+    FunctionDeclaration,
+    ClassDeclaration,
+    /// `extends` or `implements` and the types after it.
+    HeritageClause,
+    PropertyDeclaration,
+    Constructor,
+    /// `static { ... }` in a class body.
+    ClassStaticBlockDeclaration,
+    /// A stray `;` in a class body.
+    SemicolonClassElement,
+    ImportDeclaration,
+    /// The default binding, namespace import and named imports of an
+    /// import declaration.
+    ImportClause,
+    /// `* as ns` in an import.
+    NamespaceImport,
+    NamedImports,
+    ImportSpecifier,
+    /// `import x = require("m")` and `import x = ns.y`.
+    ImportEqualsDeclaration,
+    /// `require("m")` in an import-equals declaration.
+    ExternalModuleReference,
+    /// `with { type: "json" }` after a module specifier.
+    ImportAttributes,
+    ImportAttribute,
+    /// Re-exports and export lists. Declarations with an `export` modifier
+    /// are the declaration's own node.
+    ExportDeclaration,
+    /// `* as ns` in a re-export.
+    NamespaceExport,
+    NamedExports,
+    ExportSpecifier,
+    /// `export default value` and `export = value`.
+    ExportAssignment,
+
+    // Expressions
+    IdentifierExpression,
+    ThisExpression,
+    SuperExpression,
+    /// Numbers, strings, regular expressions, templates without
+    /// substitutions, `true`, `false` and `null`.
+    LiteralExpression,
+    TemplateExpression,
+    /// A substitution in a template and the template text after it.
+    TemplateSpan,
+    ArrayLiteralExpression,
+    ObjectLiteralExpression,
+    PropertyAssignment,
+    ShorthandPropertyAssignment,
+    SpreadAssignment,
+    ComputedPropertyName,
+    MethodDeclaration,
+    GetAccessor,
+    SetAccessor,
+    ParenthesizedExpression,
+    PropertyAccessExpression,
+    ElementAccessExpression,
+    CallExpression,
+    NewExpression,
+    /// `new.target` and `import.meta`.
+    MetaProperty,
+    /// `import` as the callee of a dynamic import.
+    ImportExpression,
+    TaggedTemplateExpression,
+    /// An instantiation expression such as `makeBox<string>`.
+    ExpressionWithTypeArguments,
+    NonNullExpression,
+    ArgumentList,
+    PrefixUnaryExpression,
+    PostfixUnaryExpression,
+    DeleteExpression,
+    TypeOfExpression,
+    VoidExpression,
+    AwaitExpression,
+    YieldExpression,
+    /// Binary operators, including assignments and the comma operator.
+    BinaryExpression,
+    ConditionalExpression,
+    AsExpression,
+    SatisfiesExpression,
+    /// `<T>value`
+    TypeAssertionExpression,
+    SpreadElement,
+    ArrowFunction,
+    FunctionExpression,
+    ClassExpression,
+
+    // Parameters and bindings
+    ParameterList,
+    Parameter,
+    ObjectBindingPattern,
+    ArrayBindingPattern,
+    BindingElement,
+    /// `: T` after a binding or a parameter list.
+    TypeAnnotation,
+
+    // Types
+    TypeArgumentList,
+    TypeParameterList,
+    TypeParameter,
+    TypeReference,
+    /// `string`, `void`, `this` and the other types named by a keyword.
+    KeywordType,
+    LiteralType,
+    ArrayType,
+    IndexedAccessType,
+    TupleType,
+    UnionType,
+    IntersectionType,
+    ParenthesizedType,
+    FunctionType,
+    TypeLiteral,
+    PropertySignature,
+    MethodSignature,
+    IndexSignature,
+    CallSignature,
+    /// `typeof x` in a type.
+    TypeQuery,
+    /// `keyof T`, `unique symbol`, `readonly T[]`.
+    TypeOperator,
+    ConditionalType,
+    /// `x is T`, `asserts x` or `asserts x is T` as a return type.
+    TypePredicate,
+}
+
+/// A token in the green tree: the lexer's token kind and payload, and its
+/// exact source text.
+#[derive(Clone, PartialEq, Eq)]
+pub struct GreenToken(Arc<GreenTokenData>);
+
+#[derive(PartialEq, Eq)]
+struct GreenTokenData {
+    kind: TokenKind,
+    payload: TokenPayload,
+    text: Text,
+}
+
+impl GreenToken {
+    pub fn new(kind: TokenKind, payload: TokenPayload, text: Text) -> Self {
+        Self(Arc::new(GreenTokenData {
+            kind,
+            payload,
+            text,
+        }))
+    }
+
+    /// The green token for `token`, lexed from `source`.
+    pub fn from_spanned(token: &SpannedToken, source: &Arc<str>) -> Self {
+        Self::new(
+            token.kind,
+            token.payload.clone(),
+            Text::slice(source, token.start, token.end),
+        )
+    }
+
+    pub fn kind(&self) -> TokenKind {
+        self.0.kind
+    }
+
+    pub fn payload(&self) -> &TokenPayload {
+        &self.0.payload
+    }
+
+    pub fn text(&self) -> &str {
+        &self.0.text
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.text.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl fmt::Debug for GreenToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} {:?}", self.kind(), self.text())
+    }
+}
+
+/// An interior node in the green tree.
+#[derive(Clone, PartialEq, Eq)]
+pub struct GreenNode(Arc<GreenNodeData>);
+
+#[derive(PartialEq, Eq)]
+struct GreenNodeData {
+    kind: NodeKind,
+    len: usize,
+    children: Vec<GreenElement>,
+}
+
+impl GreenNode {
+    pub fn new(kind: NodeKind, children: Vec<GreenElement>) -> Self {
+        let len = children.iter().map(GreenElement::len).sum();
+        Self(Arc::new(GreenNodeData {
+            kind,
+            len,
+            children,
+        }))
+    }
+
+    pub fn kind(&self) -> NodeKind {
+        self.0.kind
+    }
+
+    pub fn children(&self) -> &[GreenElement] {
+        &self.0.children
+    }
+
+    /// The length of the node's text in bytes.
+    pub fn len(&self) -> usize {
+        self.0.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn write_text(&self, out: &mut impl fmt::Write) -> fmt::Result {
+        for child in self.children() {
+            match child {
+                GreenElement::Node(node) => node.write_text(out)?,
+                GreenElement::Token(token) => out.write_str(token.text())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for GreenNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple(&format!("{:?}", self.kind()))
+            .field(&self.children())
+            .finish()
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub enum GreenElement {
+    Node(GreenNode),
+    Token(GreenToken),
+}
+
+impl GreenElement {
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Node(node) => node.len(),
+            Self::Token(token) => token.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl fmt::Debug for GreenElement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Node(node) => node.fmt(f),
+            Self::Token(token) => token.fmt(f),
+        }
+    }
+}
+
+impl From<GreenNode> for GreenElement {
+    fn from(node: GreenNode) -> Self {
+        Self::Node(node)
+    }
+}
+
+impl From<GreenToken> for GreenElement {
+    fn from(token: GreenToken) -> Self {
+        Self::Token(token)
+    }
+}
+
+/// A position among the children being built, to wrap the children added
+/// after it in a node later on with [`GreenNodeBuilder::start_node_at`].
+/// This is how a parser wraps `a` in a binary expression once it sees the
+/// operator in `a + b`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint(usize);
+
+/// The state of a [`GreenNodeBuilder`] to return to if a speculative parse
+/// fails. Nodes opened before the mark must stay open until the rewind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Mark {
+    parents: usize,
+    children: usize,
+}
+
+/// Builds a green tree from the top down, one node and token at a time.
+#[derive(Debug, Default)]
+pub struct GreenNodeBuilder {
+    /// The kind of each open node and where its children start.
+    parents: Vec<(NodeKind, usize)>,
+    children: Vec<GreenElement>,
+}
+
+impl GreenNodeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open a node; tokens and nodes added until the matching
+    /// [`finish_node`](Self::finish_node) become its children.
+    pub fn start_node(&mut self, kind: NodeKind) {
+        self.parents.push((kind, self.children.len()));
+    }
+
+    /// Open a node whose children start at `checkpoint`, taking in what was
+    /// added since.
+    pub fn start_node_at(&mut self, checkpoint: Checkpoint, kind: NodeKind) {
+        debug_assert!(
+            self.parents
+                .last()
+                .is_none_or(|&(_, first)| first <= checkpoint.0),
+            "checkpoint is outside the open node"
+        );
+        self.parents.push((kind, checkpoint.0));
+    }
+
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.children.len())
+    }
+
+    pub(crate) fn mark(&self) -> Mark {
+        Mark {
+            parents: self.parents.len(),
+            children: self.children.len(),
+        }
+    }
+
+    /// Drop everything added since `mark`.
+    pub(crate) fn rewind(&mut self, mark: Mark) {
+        self.parents.truncate(mark.parents);
+        self.children.truncate(mark.children);
+    }
+
+    pub fn token(&mut self, token: GreenToken) {
+        self.children.push(token.into());
+    }
+
+    /// Close the innermost open node.
+    pub fn finish_node(&mut self) {
+        let (kind, first) = self.parents.pop().expect("no open node to finish");
+        let children = self.children.split_off(first);
+        self.children.push(GreenNode::new(kind, children).into());
+    }
+
+    /// The finished tree. Every node must be closed and there must be
+    /// exactly one at the root.
+    pub fn finish(mut self) -> GreenNode {
+        assert!(self.parents.is_empty(), "unfinished nodes");
+        match (self.children.pop(), self.children.is_empty()) {
+            (Some(GreenElement::Node(root)), true) => root,
+            _ => panic!("a tree needs exactly one root node"),
+        }
+    }
+}
+
+/// A node in the red tree: a green node at a known place in the document.
+/// Cloning is cheap, and two `SyntaxNode`s are equal when they are the same
+/// node of the same tree.
+#[derive(Clone)]
+pub struct SyntaxNode(Arc<SyntaxNodeData>);
+
+struct SyntaxNodeData {
+    green: GreenNode,
+    parent: Option<SyntaxNode>,
+    /// Index among the parent's children, tokens included.
+    index: usize,
+    offset: usize,
+}
+
+impl SyntaxNode {
+    /// The root of the tree over `green`, starting at offset 0.
+    pub fn new_root(green: GreenNode) -> Self {
+        Self(Arc::new(SyntaxNodeData {
+            green,
+            parent: None,
+            index: 0,
+            offset: 0,
+        }))
+    }
+
+    pub fn kind(&self) -> NodeKind {
+        self.0.green.kind()
+    }
+
+    pub fn green(&self) -> &GreenNode {
+        &self.0.green
+    }
+
+    pub fn parent(&self) -> Option<SyntaxNode> {
+        self.0.parent.clone()
+    }
+
+    /// This node and then each of its ancestors, up to the root.
+    pub fn ancestors(&self) -> impl Iterator<Item = SyntaxNode> {
+        std::iter::successors(Some(self.clone()), SyntaxNode::parent)
+    }
+
+    /// Byte offset of the node's first byte in the document.
+    pub fn start(&self) -> usize {
+        self.0.offset
+    }
+
+    /// Byte offset just past the node's last byte.
+    pub fn end(&self) -> usize {
+        self.0.offset + self.0.green.len()
+    }
+
+    /// The node's source text, trivia included.
+    pub fn text(&self) -> String {
+        self.to_string()
+    }
+
+    /// The child nodes and tokens, in order.
+    pub fn children_with_tokens(&self) -> impl Iterator<Item = SyntaxElement> + '_ {
+        let mut offset = self.start();
+        self.green()
+            .children()
+            .iter()
+            .enumerate()
+            .map(move |(index, child)| {
+                let element = SyntaxElement::new(child, self, index, offset);
+                offset += child.len();
+                element
+            })
+    }
+
+    /// The child nodes, in order.
+    pub fn children(&self) -> impl Iterator<Item = SyntaxNode> + '_ {
+        self.children_with_tokens()
+            .filter_map(SyntaxElement::into_node)
+    }
+
+    /// This node and every node below it, in preorder.
+    pub fn descendants(&self) -> impl Iterator<Item = SyntaxNode> {
+        let mut stack = vec![self.clone()];
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            let first = stack.len();
+            stack.extend(node.children());
+            stack[first..].reverse();
+            Some(node)
+        })
+    }
+
+    /// Every token below this node, in source order.
+    pub fn tokens(&self) -> impl Iterator<Item = SyntaxToken> {
+        let mut stack = vec![SyntaxElement::Node(self.clone())];
+        std::iter::from_fn(move || {
+            loop {
+                match stack.pop()? {
+                    SyntaxElement::Token(token) => return Some(token),
+                    SyntaxElement::Node(node) => {
+                        let first = stack.len();
+                        stack.extend(node.children_with_tokens());
+                        stack[first..].reverse();
+                    }
+                }
+            }
+        })
+    }
+
+    pub fn first_token(&self) -> Option<SyntaxToken> {
+        self.tokens().next()
+    }
+
+    pub fn last_token(&self) -> Option<SyntaxToken> {
+        let mut node = self.clone();
+        loop {
+            match node.children_with_tokens().last()? {
+                SyntaxElement::Token(token) => return Some(token),
+                SyntaxElement::Node(child) => node = child,
+            }
+        }
+    }
+
+    pub fn next_sibling(&self) -> Option<SyntaxNode> {
+        let parent = self.parent()?;
+        parent
+            .children_with_tokens()
+            .skip(self.0.index + 1)
+            .find_map(SyntaxElement::into_node)
+    }
+
+    pub fn prev_sibling(&self) -> Option<SyntaxNode> {
+        let parent = self.parent()?;
+        parent
+            .children_with_tokens()
+            .take(self.0.index)
+            .filter_map(SyntaxElement::into_node)
+            .last()
+    }
+
+    /// The token that contains byte `offset`. At the boundary between two
+    /// tokens this is the one that starts there; at the end of the node it
+    /// is the last token.
+    pub fn token_at_offset(&self, offset: usize) -> Option<SyntaxToken> {
+        if offset < self.start() || offset > self.end() {
+            return None;
+        }
+        let mut node = self.clone();
+        loop {
+            let child = node
+                .children_with_tokens()
+                .find(|child| offset < child.end())
+                .or_else(|| node.children_with_tokens().last())?;
+            match child {
+                SyntaxElement::Token(token) => return Some(token),
+                SyntaxElement::Node(child) => node = child,
+            }
+        }
+    }
+}
+
+impl PartialEq for SyntaxNode {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.green().0, &other.green().0) && self.start() == other.start()
+    }
+}
+
+impl Eq for SyntaxNode {}
+
+impl Hash for SyntaxNode {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.green().0).hash(state);
+        self.start().hash(state);
+    }
+}
+
+impl fmt::Display for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.green().write_text(f)
+    }
+}
+
+impl fmt::Debug for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}@{}..{}", self.kind(), self.start(), self.end())
+    }
+}
+
+/// A token in the red tree.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct SyntaxToken {
+    parent: SyntaxNode,
+    index: usize,
+    offset: usize,
+}
+
+impl SyntaxToken {
+    pub fn green(&self) -> &GreenToken {
+        match &self.parent.green().children()[self.index] {
+            GreenElement::Token(token) => token,
+            GreenElement::Node(_) => unreachable!("token index points at a node"),
+        }
+    }
+
+    pub fn kind(&self) -> TokenKind {
+        self.green().kind()
+    }
+
+    pub fn payload(&self) -> &TokenPayload {
+        self.green().payload()
+    }
+
+    pub fn text(&self) -> &str {
+        self.green().text()
+    }
+
+    pub fn start(&self) -> usize {
+        self.offset
+    }
+
+    pub fn end(&self) -> usize {
+        self.offset + self.green().len()
+    }
+
+    pub fn parent(&self) -> SyntaxNode {
+        self.parent.clone()
+    }
+
+    /// The nodes containing this token, innermost first.
+    pub fn ancestors(&self) -> impl Iterator<Item = SyntaxNode> {
+        self.parent.ancestors()
+    }
+}
+
+impl fmt::Display for SyntaxToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.text())
+    }
+}
+
+impl fmt::Debug for SyntaxToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?}@{}..{} {:?}",
+            self.kind(),
+            self.start(),
+            self.end(),
+            self.text()
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl SyntaxElement {
+    fn new(green: &GreenElement, parent: &SyntaxNode, index: usize, offset: usize) -> Self {
+        match green {
+            GreenElement::Node(node) => Self::Node(SyntaxNode(Arc::new(SyntaxNodeData {
+                green: node.clone(),
+                parent: Some(parent.clone()),
+                index,
+                offset,
+            }))),
+            GreenElement::Token(_) => Self::Token(SyntaxToken {
+                parent: parent.clone(),
+                index,
+                offset,
+            }),
+        }
+    }
+
+    pub fn start(&self) -> usize {
+        match self {
+            Self::Node(node) => node.start(),
+            Self::Token(token) => token.start(),
+        }
+    }
+
+    pub fn end(&self) -> usize {
+        match self {
+            Self::Node(node) => node.end(),
+            Self::Token(token) => token.end(),
+        }
+    }
+
+    pub fn into_node(self) -> Option<SyntaxNode> {
+        match self {
+            Self::Node(node) => Some(node),
+            Self::Token(_) => None,
+        }
+    }
+
+    pub fn into_token(self) -> Option<SyntaxToken> {
+        match self {
+            Self::Node(_) => None,
+            Self::Token(token) => Some(token),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    /// `a (b c) d`, with the parenthesised tokens in an `Error` node inside
+    /// the source file.
+    fn tree(source: &str) -> SyntaxNode {
+        let source: Arc<str> = source.into();
+        let tokens = Lexer::new(Arc::clone(&source)).lex();
+        let mut builder = GreenNodeBuilder::new();
+        builder.start_node(NodeKind::SourceFile);
+        for token in &tokens {
+            if token.kind == TokenKind::OpenParen {
+                builder.start_node(NodeKind::Error);
+            }
+            builder.token(GreenToken::from_spanned(token, &source));
+            if token.kind == TokenKind::CloseParen {
+                builder.finish_node();
+            }
+        }
+        builder.finish_node();
+        SyntaxNode::new_root(builder.finish())
+    }
+
+    #[test]
+    fn tree_text_round_trips() {
+        let source = "#!/usr/bin/env node\n// lead\nlet \\u0061 = (1 /* c */ + 'x') ;\r\n";
+        let root = tree(source);
+        assert_eq!(root.text(), source);
+        assert_eq!((root.start(), root.end()), (0, source.len()));
+
+        let tokens: Vec<SyntaxToken> = root.tokens().collect();
+        assert_eq!(tokens.first().unwrap().kind(), TokenKind::ShebangTrivia);
+        assert_eq!(tokens.last().unwrap().kind(), TokenKind::Eof);
+        for pair in tokens.windows(2) {
+            assert_eq!(pair[0].end(), pair[1].start());
+        }
+        for token in &tokens {
+            assert_eq!(token.text(), &source[token.start()..token.end()]);
+        }
+    }
+
+    #[test]
+    fn red_nodes_know_parents_and_offsets() {
+        let source = "a (b c) d (e)";
+        let root = tree(source);
+
+        let groups: Vec<SyntaxNode> = root.children().collect();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].text(), "(b c)");
+        assert_eq!((groups[0].start(), groups[0].end()), (2, 7));
+        assert_eq!(groups[0].parent(), Some(root.clone()));
+        assert_eq!(groups[0].next_sibling(), Some(groups[1].clone()));
+        assert_eq!(groups[1].prev_sibling(), Some(groups[0].clone()));
+        assert_eq!(groups[1].next_sibling(), None);
+
+        let c = root.token_at_offset(5).unwrap();
+        assert_eq!((c.text(), c.start()), ("c", 5));
+        assert_eq!(
+            c.ancestors().map(|node| node.kind()).collect::<Vec<_>>(),
+            [NodeKind::Error, NodeKind::SourceFile]
+        );
+        assert_eq!(
+            root.token_at_offset(source.len()).unwrap().kind(),
+            TokenKind::Eof
+        );
+        assert_eq!(groups[1].last_token().unwrap().text(), ")");
+
+        let kinds: Vec<NodeKind> = root.descendants().map(|node| node.kind()).collect();
+        assert_eq!(
+            kinds,
+            [NodeKind::SourceFile, NodeKind::Error, NodeKind::Error]
+        );
+    }
+
+    #[test]
+    fn checkpoints_wrap_earlier_children() {
+        let source: Arc<str> = "a+b".into();
+        let tokens = Lexer::new(Arc::clone(&source)).lex();
+        let mut builder = GreenNodeBuilder::new();
+        builder.start_node(NodeKind::SourceFile);
+        let checkpoint = builder.checkpoint();
+        for token in &tokens[..3] {
+            builder.token(GreenToken::from_spanned(token, &source));
+        }
+        builder.start_node_at(checkpoint, NodeKind::Error);
+        builder.finish_node();
+        builder.token(GreenToken::from_spanned(&tokens[3], &source));
+        builder.finish_node();
+
+        let root = SyntaxNode::new_root(builder.finish());
+        let error = root.children().next().unwrap();
+        assert_eq!(error.text(), "a+b");
+        assert_eq!(error.green().children().len(), 3);
+    }
+
+    #[test]
+    fn green_subtrees_are_shared() {
+        let source: Arc<str> = "x".into();
+        let tokens = Lexer::new(Arc::clone(&source)).lex();
+        let leaf = GreenNode::new(
+            NodeKind::Error,
+            vec![GreenToken::from_spanned(&tokens[0], &source).into()],
+        );
+        let root = GreenNode::new(
+            NodeKind::SourceFile,
+            vec![leaf.clone().into(), leaf.clone().into()],
+        );
+
+        let root = SyntaxNode::new_root(root);
+        let children: Vec<SyntaxNode> = root.children().collect();
+        assert_eq!(root.text(), "xx");
+        assert_ne!(children[0], children[1]);
+        assert_eq!((children[1].start(), children[1].end()), (1, 2));
+        assert!(Arc::ptr_eq(&children[0].green().0, &children[1].green().0));
+    }
+}
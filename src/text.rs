@@ -0,0 +1,162 @@
+//! Text module — cheap, shareable slices of source text.
+
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// A string that references a byte range of a shared buffer. Token payloads
+/// cut from a document all share the document's buffer, so producing them
+/// costs a reference-count increment instead of an allocation. Text that
+/// does not occur verbatim in the source (such as a string literal's value
+/// after escape decoding) gets a buffer of its own.
+#[derive(Clone, Default)]
+pub struct Text {
+    buffer: Arc<str>,
+    start: u32,
+    end: u32,
+}
+
+impl Text {
+    /// Reference `buffer[start..end]`. Both offsets must lie on character
+    /// boundaries. Panics if `end` does not fit in 32 bits, so buffers are
+    /// limited to 4 GiB.
+    pub fn slice(buffer: &Arc<str>, start: usize, end: usize) -> Self {
+        debug_assert!(buffer.is_char_boundary(start) && buffer.is_char_boundary(end));
+        Self {
+            buffer: Arc::clone(buffer),
+            start: offset(start),
+            end: offset(end),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.buffer[self.start as usize..self.end as usize]
+    }
+
+    /// The text at `start..end` of the buffer this text was cut from,
+    /// provided that range covers this text. Recovers the full source text
+    /// of a token from a payload that holds only part of it.
+    pub fn enclosing(&self, start: usize, end: usize) -> Option<&str> {
+        if start <= self.start as usize && self.end as usize <= end {
+            self.buffer.get(start..end)
+        } else {
+            None
+        }
+    }
+
+    /// Move this text from `from` onto `to`, `shift` bytes away from where
+    /// it sits in `from`. Used to move tokens that survive an edit onto the
+    /// new document text so the old one can be freed. Text cut from any
+    /// other buffer, such as a cooked string value, is left alone.
+    pub fn rebase(&mut self, from: &Arc<str>, to: &Arc<str>, shift: isize) {
+        if Arc::ptr_eq(&self.buffer, from) {
+            let start = self.start as isize + shift;
+            let end = self.end as isize + shift;
+            debug_assert!(to.get(start as usize..end as usize) == Some(self.as_str()));
+            self.buffer = Arc::clone(to);
+            self.start = start as u32;
+            self.end = end as u32;
+        }
+    }
+}
+
+/// `offset` as stored in a [`Text`].
+fn offset(offset: usize) -> u32 {
+    u32::try_from(offset).expect("text offsets fit in 32 bits")
+}
+
+impl Deref for Text {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<&str> for Text {
+    fn from(text: &str) -> Self {
+        Self::from(String::from(text))
+    }
+}
+
+impl From<String> for Text {
+    fn from(text: String) -> Self {
+        let end = offset(text.len());
+        Self {
+            buffer: text.into(),
+            start: 0,
+            end,
+        }
+    }
+}
+
+impl PartialEq for Text {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for Text {}
+
+impl PartialEq<str> for Text {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Text {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl std::hash::Hash for Text {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl fmt::Debug for Text {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Text {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slices_share_the_buffer() {
+        let source: Arc<str> = Arc::from("let name = 1;");
+        let name = Text::slice(&source, 4, 8);
+
+        assert_eq!(name, "name");
+        assert_eq!(name, Text::from("name"));
+        assert_eq!(Arc::strong_count(&source), 2);
+        assert_eq!(format!("{name:?}"), "\"name\"");
+    }
+
+    #[test]
+    fn rebases_onto_an_edited_buffer() {
+        let old: Arc<str> = Arc::from("let name = 1;");
+        let new: Arc<str> = Arc::from("const name = 1;");
+        let mut name = Text::slice(&old, 4, 8);
+        name.rebase(&old, &new, 2);
+
+        assert_eq!(name, "name");
+        assert_eq!(Arc::strong_count(&old), 1);
+        assert_eq!(Arc::strong_count(&new), 2);
+
+        let mut cooked = Text::from("\n");
+        cooked.rebase(&old, &new, 2);
+        assert_eq!(cooked, "\n");
+        assert_eq!(Arc::strong_count(&new), 2);
+    }
+}
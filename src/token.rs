@@ -1,40 +1,50 @@
 use std::borrow::Cow;
+use std::sync::Arc;
 
-/// Basic token definitions used by the lexer.
+use crate::symbol::{Interner, Symbol};
+use crate::text::Text;
+use crate::token_kind::TokenKind;
 
+/// A token with its payload, as the scanner produces it. Lexed tokens are
+/// kept as [`SpannedToken`]s, which store the kind and the payload apart;
+/// [`SpannedToken::token`] puts them back together.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Token {
     // Generic / sentinel
-    Illegal,
+    Illegal(Text), // Unrecognised source text, kept for round-tripping
     Eof,
 
     // ===== Trivia (scanner can emit/observe; typically skipped by parser) =====
-    SingleLineCommentTrivia(String), // Full comment including "//" marker
-    MultiLineCommentTrivia(String),  // Full comment including "/* */" markers
-    NewLineTrivia,
-    WhitespaceTrivia(String), // Actual whitespace characters (spaces, tabs)
-    ShebangTrivia,
-    ConflictMarkerTrivia,
+    SingleLineCommentTrivia(Text), // Full comment including "//" marker
+    MultiLineCommentTrivia(Text),  // Full comment including "/* */" markers
+    NewLineTrivia(LineTerminator),
+    WhitespaceTrivia(Text),     // Actual whitespace characters (spaces, tabs)
+    ShebangTrivia(Text),        // Full `#!...` line at the start of the file
+    ConflictMarkerTrivia(Text), // Full merge conflict marker text
+    ByteOrderMark,              // U+FEFF at the very start of the file
 
     // ===== Identifiers =====
-    Identifier(String),
-    PrivateIdentifier(String), // e.g. #x
+    // Payload is the raw spelling, including any `\u` escapes; see
+    // `Token::identifier_name` for the decoded name.
+    Identifier(Text),
+    PrivateIdentifier(Text), // e.g. #x, payload without the leading `#`
 
     // ===== Literals =====
-    NumericLiteral(String),
-    BigIntLiteral(String),
-    StringLiteral(String),
-    RegularExpressionLiteral(String),
+    NumericLiteral(Text),
+    BigIntLiteral(Text),
+    StringLiteral { raw: Text, value: Text }, // raw keeps quotes and escapes
+    RegularExpressionLiteral { body: Text, flags: Text }, // /body/flags, body raw
 
-    // Template literals (split into pieces in TS scanning)
-    NoSubstitutionTemplateLiteral(String),
-    TemplateHead(String),
-    TemplateMiddle(String),
-    TemplateTail(String),
+    // Template literals (split into pieces in TS scanning). Payload is the
+    // raw text between the delimiters, escapes left intact.
+    NoSubstitutionTemplateLiteral(Text),
+    TemplateHead(Text),
+    TemplateMiddle(Text),
+    TemplateTail(Text),
 
     // JSX text tokens (only inside JSX)
-    JsxText(String),
-    JsxTextAllWhiteSpaces(String),
+    JsxText(Text),
+    JsxTextAllWhiteSpaces(Text),
 
     // ===== Punctuators / Delimiters / Operators =====
     // Delimiters
@@ -88,7 +98,7 @@ pub enum Token {
     // ColonToken removed; use Colon above
     At,               // @
     QuestionQuestion, // ??
-    Hash,             // # (used e.g. in private names in certain contexts)
+    Hash,             // # not followed by a name (private names lex as PrivateIdentifier)
 
     // Assignment
     Equals,                                  // =
@@ -194,79 +204,411 @@ pub enum Token {
     Using,
 }
 
+/// How tightly an operator binds, following TypeScript's
+/// `OperatorPrecedence`. Higher values bind tighter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum OperatorPrecedence {
+    Comma,
+    Spread,
+    Yield,
+    Assignment,
+    Conditional,
+    LogicalOr,
+    LogicalAnd,
+    BitwiseOr,
+    BitwiseXor,
+    BitwiseAnd,
+    Equality,
+    Relational,
+    Shift,
+    Additive,
+    Multiplicative,
+    Exponentiation,
+    Unary,
+    Update,
+    LeftHandSide,
+    Member,
+    Primary,
+}
+
+impl OperatorPrecedence {
+    /// `??` shares its precedence with the conditional operator.
+    pub const COALESCE: Self = Self::Conditional;
+    pub const LOWEST: Self = Self::Comma;
+    pub const HIGHEST: Self = Self::Primary;
+}
+
+impl Token {
+    /// The kind of this token, without its payload.
+    pub fn kind(&self) -> TokenKind {
+        TokenKind::from(self)
+    }
+
+    /// Whether this is a keyword of any kind: reserved, strict-mode reserved
+    /// or contextual.
+    pub fn is_keyword(&self) -> bool {
+        self.kind().is_keyword()
+    }
+
+    /// Whether this keyword can never be used as an identifier.
+    pub fn is_reserved_word(&self) -> bool {
+        self.kind().is_reserved_word()
+    }
+
+    /// Whether this keyword is reserved only in strict mode code.
+    pub fn is_future_reserved_word(&self) -> bool {
+        self.kind().is_future_reserved_word()
+    }
+
+    /// Whether this keyword only has meaning in certain positions and is an
+    /// ordinary identifier everywhere else.
+    pub fn is_contextual_keyword(&self) -> bool {
+        self.kind().is_contextual_keyword()
+    }
+
+    /// Whether this is an identifier or any keyword, as allowed for property
+    /// names such as `obj.default`.
+    pub fn is_identifier_or_keyword(&self) -> bool {
+        matches!(self, Token::Identifier(_)) || self.is_keyword()
+    }
+
+    /// Whether this keyword can modify a declaration, as in `export`,
+    /// `private` or `readonly`.
+    pub fn is_modifier(&self) -> bool {
+        self.kind().is_modifier()
+    }
+
+    /// Whether this is a literal token. `true`, `false` and `null` are
+    /// keywords, as in TypeScript.
+    pub fn is_literal(&self) -> bool {
+        self.kind().is_literal()
+    }
+
+    /// Whether this is one of the pieces of a template literal.
+    pub fn is_template_literal(&self) -> bool {
+        self.kind().is_template_literal()
+    }
+
+    /// Whether this token is trivia: comments, whitespace, line breaks and
+    /// other text that carries no meaning for the grammar.
+    pub fn is_trivia(&self) -> bool {
+        self.kind().is_trivia()
+    }
+
+    /// Whether this is a punctuator or operator.
+    pub fn is_punctuation(&self) -> bool {
+        self.kind().is_punctuation()
+    }
+
+    /// Whether this is `=` or a compound assignment such as `+=` or `??=`.
+    pub fn is_assignment_operator(&self) -> bool {
+        self.kind().is_assignment_operator()
+    }
+
+    /// Whether this is an assignment operator that also applies an
+    /// operation, like `+=` or `&&=`.
+    pub fn is_compound_assignment_operator(&self) -> bool {
+        self.kind().is_compound_assignment_operator()
+    }
+
+    /// Whether this token can be a binary operator.
+    pub fn is_binary_operator(&self) -> bool {
+        self.binary_operator_precedence().is_some()
+    }
+
+    /// The precedence of this token as a binary operator, or `None` if it is
+    /// not one. Mirrors TypeScript's `getBinaryOperatorPrecedence`, so `as`
+    /// and `satisfies` rank with the relational operators.
+    pub fn binary_operator_precedence(&self) -> Option<OperatorPrecedence> {
+        self.kind().binary_operator_precedence()
+    }
+
+    /// The decoded name of an identifier token, with `\uXXXX` and `\u{...}`
+    /// escapes resolved. Returns `None` for every other token.
+    pub fn identifier_name(&self) -> Option<Cow<'_, str>> {
+        match self {
+            Token::Identifier(raw) | Token::PrivateIdentifier(raw) => {
+                Some(cook_identifier(raw.as_str()))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Decode the unicode escapes in an identifier's raw spelling. Malformed
+/// escapes are kept verbatim; the lexer has already reported them.
+fn cook_identifier(raw: &str) -> Cow<'_, str> {
+    if !raw.contains('\\') {
+        return Cow::Borrowed(raw);
+    }
+
+    let mut cooked = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(index) = rest.find('\\') {
+        cooked.push_str(&rest[..index]);
+        rest = &rest[index..];
+        let (digits, len) = match rest.strip_prefix("\\u{") {
+            Some(braced) => match braced.find('}') {
+                Some(close) => (&braced[..close], close + 4),
+                None => ("", 0),
+            },
+            None => (rest.get(2..6).unwrap_or(""), 6),
+        };
+        match u32::from_str_radix(digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+        {
+            Some(c) if len > 0 => {
+                cooked.push(c);
+                rest = &rest[len..];
+            }
+            _ => {
+                cooked.push('\\');
+                rest = &rest[1..];
+            }
+        }
+    }
+    cooked.push_str(rest);
+
+    Cow::Owned(cooked)
+}
+
+/// The character sequence that ended a line. ECMAScript recognises four
+/// line terminators, and `\r\n` counts as a single line break.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LineTerminator {
+    Lf,
+    CrLf,
+    Cr,
+    /// U+2028 LINE SEPARATOR
+    LineSeparator,
+    /// U+2029 PARAGRAPH SEPARATOR
+    ParagraphSeparator,
+}
+
+impl LineTerminator {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineTerminator::Lf => "\n",
+            LineTerminator::CrLf => "\r\n",
+            LineTerminator::Cr => "\r",
+            LineTerminator::LineSeparator => "\u{2028}",
+            LineTerminator::ParagraphSeparator => "\u{2029}",
+        }
+    }
+}
+
+/// Whether `c` is an ECMAScript line terminator: LF, CR, LS or PS.
+pub fn is_line_terminator(c: char) -> bool {
+    matches!(c, '\n' | '\r' | '\u{2028}' | '\u{2029}')
+}
+
+/// Whether `c` may start an identifier: `$`, `_` or any Unicode `ID_Start`
+/// code point (approximated by the NFKC-stable `XID_Start` set).
+pub fn is_identifier_start(c: char) -> bool {
+    c == '$' || c == '_' || unicode_ident::is_xid_start(c)
+}
+
+/// Whether `c` may continue an identifier: `$`, ZWNJ, ZWJ or any Unicode
+/// `ID_Continue` code point (approximated by `XID_Continue`).
+pub fn is_identifier_part(c: char) -> bool {
+    matches!(c, '$' | '\u{200C}' | '\u{200D}') || unicode_ident::is_xid_continue(c)
+}
+
+/// What a token carries besides its kind. Punctuators and keywords carry
+/// nothing; their kind says it all.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum TokenPayload {
+    #[default]
+    None,
+    /// The text of trivia, numeric literals, template pieces, JSX text and
+    /// unrecognised input, as in the matching [`Token`] variant.
+    Text(Text),
+    NewLine(LineTerminator),
+    /// An identifier's raw spelling, escapes included, and its decoded name
+    /// interned in the lexer's [`Interner`].
+    Symbol {
+        raw: Text,
+        symbol: Symbol,
+    },
+    /// A string literal with its quotes and escapes, and its cooked value.
+    String {
+        raw: Text,
+        value: Text,
+    },
+    /// A regular expression's raw body and flags.
+    Regex {
+        body: Text,
+        flags: Text,
+    },
+}
+
+impl TokenPayload {
+    /// The payload of `token`, interning identifier names in `interner`.
+    pub fn new(token: Token, interner: &Interner) -> Self {
+        match token {
+            Token::Identifier(raw) | Token::PrivateIdentifier(raw) => {
+                let symbol = interner.intern(&cook_identifier(&raw));
+                TokenPayload::Symbol { raw, symbol }
+            }
+            Token::NewLineTrivia(terminator) => TokenPayload::NewLine(terminator),
+            Token::StringLiteral { raw, value } => TokenPayload::String { raw, value },
+            Token::RegularExpressionLiteral { body, flags } => TokenPayload::Regex { body, flags },
+            Token::Illegal(text)
+            | Token::SingleLineCommentTrivia(text)
+            | Token::MultiLineCommentTrivia(text)
+            | Token::WhitespaceTrivia(text)
+            | Token::ShebangTrivia(text)
+            | Token::ConflictMarkerTrivia(text)
+            | Token::NumericLiteral(text)
+            | Token::BigIntLiteral(text)
+            | Token::NoSubstitutionTemplateLiteral(text)
+            | Token::TemplateHead(text)
+            | Token::TemplateMiddle(text)
+            | Token::TemplateTail(text)
+            | Token::JsxText(text)
+            | Token::JsxTextAllWhiteSpaces(text) => TokenPayload::Text(text),
+            _ => TokenPayload::None,
+        }
+    }
+
+    /// The source text of a token spanning `start..end`, if this payload is
+    /// a slice of that source.
+    fn source_text(&self, start: usize, end: usize) -> Option<&str> {
+        let text = match self {
+            TokenPayload::Text(text)
+            | TokenPayload::Symbol { raw: text, .. }
+            | TokenPayload::String { raw: text, .. }
+            | TokenPayload::Regex { body: text, .. } => text,
+            TokenPayload::None | TokenPayload::NewLine(_) => return None,
+        };
+        text.enclosing(start, end)
+    }
+
+    /// The text of a `Text` payload.
+    pub fn text(&self) -> Option<&Text> {
+        match self {
+            TokenPayload::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Move this payload's text from `from` onto `to`, shifted by `shift`
+    /// bytes; see [`Text::rebase`].
+    pub(crate) fn rebase(&mut self, from: &Arc<str>, to: &Arc<str>, shift: isize) {
+        match self {
+            TokenPayload::Text(text) | TokenPayload::Symbol { raw: text, .. } => {
+                text.rebase(from, to, shift);
+            }
+            TokenPayload::String { raw, value } => {
+                raw.rebase(from, to, shift);
+                value.rebase(from, to, shift);
+            }
+            TokenPayload::Regex { body, flags } => {
+                body.rebase(from, to, shift);
+                flags.rebase(from, to, shift);
+            }
+            TokenPayload::None | TokenPayload::NewLine(_) => {}
+        }
+    }
+}
+
+/// A token together with where it was found. `line`/`column` are 1-based and
+/// counted in `char`s; `start..end` is the byte range of the token in the
+/// source, suitable for slicing and for LSP position conversion via
+/// [`crate::line_index::LineIndex`].
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SpannedToken {
-    pub value: Token,
+    pub kind: TokenKind,
+    pub payload: TokenPayload,
     pub line: u32,
     pub column: u32,
+    pub start: usize,
+    pub end: usize,
 }
 
-pub fn find_match(s: &str) -> Option<Token> {
-    match s {
-        // Arithmetic operators
-        "+" => Some(Token::Plus),
-        "-" => Some(Token::Minus),
-        "*" => Some(Token::Asterisk),
-        "/" => Some(Token::Slash),
-        "%" => Some(Token::Percent),
-        "**" => Some(Token::AsteriskAsterisk),
-        "++" => Some(Token::PlusPlus),
-        "--" => Some(Token::MinusMinus),
-
-        // Assignment operators
-        "=" => Some(Token::Equals),
-        "+=" => Some(Token::PlusEquals),
-        "-=" => Some(Token::MinusEquals),
-        "*=" => Some(Token::AsteriskEquals),
-        "/=" => Some(Token::SlashEquals),
-        "%=" => Some(Token::PercentEquals),
-        "**=" => Some(Token::AsteriskAsteriskEquals),
-        "&=" => Some(Token::AmpersandEquals),
-        "|=" => Some(Token::BarEquals),
-        "^=" => Some(Token::CaretEquals),
-
-        // Comparison operators
-        "==" => Some(Token::EqualsEquals),
-        "!=" => Some(Token::ExclamationEquals),
-        "===" => Some(Token::EqualsEqualsEquals),
-        "!==" => Some(Token::ExclamationEqualsEquals),
-        ">" => Some(Token::GreaterThan),
-        "<" => Some(Token::LessThan),
-        ">=" => Some(Token::GreaterThanEquals),
-        "<=" => Some(Token::LessThanEquals),
-
-        // Logical/bitwise
-        "&&" => Some(Token::AmpersandAmpersand),
-        "||" => Some(Token::BarBar),
-        "!" => Some(Token::Bang),
-        "&" => Some(Token::Ampersand),
-        "|" => Some(Token::Bar),
-        "^" => Some(Token::Caret),
-        "~" => Some(Token::Tilde),
-        "??" => Some(Token::QuestionQuestion),
-
-        // Arrow
-        "=>" => Some(Token::EqualsGreaterThan),
-
-        // Spread/rest, member access
-        "..." => Some(Token::DotDotDot),
-        "?" => Some(Token::Question),
-        "?." => Some(Token::QuestionDot),
-        ":" => Some(Token::Colon),
-
-        // Delimiters
-        "," => Some(Token::Comma),
-        ";" => Some(Token::Semicolon),
-        "." => Some(Token::Dot),
-
-        // Brackets and parenthesis
-        "(" => Some(Token::OpenParen),
-        ")" => Some(Token::CloseParen),
-        "{" => Some(Token::OpenBrace),
-        "}" => Some(Token::CloseBrace),
-        "[" => Some(Token::OpenBracket),
-        "]" => Some(Token::CloseBracket),
+impl SpannedToken {
+    /// The token with its payload, as a [`Token`].
+    pub fn token(&self) -> Token {
+        Token::from(self)
+    }
+
+    pub fn is_trivia(&self) -> bool {
+        self.kind.is_trivia()
+    }
+
+    /// The interned decoded name of an identifier or private identifier.
+    pub fn symbol(&self) -> Option<Symbol> {
+        match self.payload {
+            TokenPayload::Symbol { symbol, .. } => Some(symbol),
+            _ => None,
+        }
+    }
 
+    /// The decoded name of an identifier; see [`Token::identifier_name`].
+    pub fn identifier_name(&self) -> Option<Cow<'_, str>> {
+        match &self.payload {
+            TokenPayload::Symbol { raw, .. } => Some(cook_identifier(raw)),
+            _ => None,
+        }
+    }
+
+    /// Whether the byte `offset` falls within this token. The end offset is
+    /// included so a cursor placed right after a token still hits it.
+    pub fn contains_offset(&self, offset: usize) -> bool {
+        self.start <= offset && offset <= self.end
+    }
+}
+
+impl From<&SpannedToken> for Token {
+    fn from(token: &SpannedToken) -> Self {
+        let text = || token.payload.text().cloned().unwrap_or_default();
+        match (token.kind, &token.payload) {
+            (TokenKind::Identifier, TokenPayload::Symbol { raw, .. }) => {
+                Token::Identifier(raw.clone())
+            }
+            (TokenKind::PrivateIdentifier, TokenPayload::Symbol { raw, .. }) => {
+                Token::PrivateIdentifier(raw.clone())
+            }
+            (_, TokenPayload::NewLine(terminator)) => Token::NewLineTrivia(*terminator),
+            (_, TokenPayload::String { raw, value }) => Token::StringLiteral {
+                raw: raw.clone(),
+                value: value.clone(),
+            },
+            (_, TokenPayload::Regex { body, flags }) => Token::RegularExpressionLiteral {
+                body: body.clone(),
+                flags: flags.clone(),
+            },
+            (TokenKind::Illegal, _) => Token::Illegal(text()),
+            (TokenKind::Eof, _) => Token::Eof,
+            (TokenKind::SingleLineCommentTrivia, _) => Token::SingleLineCommentTrivia(text()),
+            (TokenKind::MultiLineCommentTrivia, _) => Token::MultiLineCommentTrivia(text()),
+            (TokenKind::WhitespaceTrivia, _) => Token::WhitespaceTrivia(text()),
+            (TokenKind::ShebangTrivia, _) => Token::ShebangTrivia(text()),
+            (TokenKind::ConflictMarkerTrivia, _) => Token::ConflictMarkerTrivia(text()),
+            (TokenKind::ByteOrderMark, _) => Token::ByteOrderMark,
+            (TokenKind::NumericLiteral, _) => Token::NumericLiteral(text()),
+            (TokenKind::BigIntLiteral, _) => Token::BigIntLiteral(text()),
+            (TokenKind::NoSubstitutionTemplateLiteral, _) => {
+                Token::NoSubstitutionTemplateLiteral(text())
+            }
+            (TokenKind::TemplateHead, _) => Token::TemplateHead(text()),
+            (TokenKind::TemplateMiddle, _) => Token::TemplateMiddle(text()),
+            (TokenKind::TemplateTail, _) => Token::TemplateTail(text()),
+            (TokenKind::JsxText, _) => Token::JsxText(text()),
+            (TokenKind::JsxTextAllWhiteSpaces, _) => Token::JsxTextAllWhiteSpaces(text()),
+            (TokenKind::LessThanSlash, _) => Token::LessThanSlash,
+            (kind, _) => kind
+                .text()
+                .and_then(find_match)
+                .unwrap_or_else(|| Token::Illegal(text())),
+        }
+    }
+}
+
+/// The keyword spelled `s`, reserved or contextual, if any.
+pub fn keyword_from_str(s: &str) -> Option<Token> {
+    match s {
         // Keywords
         "break" => Some(Token::Break),
         "case" => Some(Token::Case),
@@ -282,6 +624,7 @@ pub fn find_match(s: &str) -> Option<Token> {
         "enum" => Some(Token::Enum),
         "export" => Some(Token::Export),
         "extends" => Some(Token::Extends),
+        "false" => Some(Token::False),
         "finally" => Some(Token::Finally),
         "for" => Some(Token::For),
         "function" => Some(Token::Function),
@@ -291,11 +634,13 @@ pub fn find_match(s: &str) -> Option<Token> {
         "instanceof" => Some(Token::InstanceOf),
         "let" => Some(Token::Let),
         "new" => Some(Token::New),
+        "null" => Some(Token::Null),
         "return" => Some(Token::Return),
         "super" => Some(Token::Super),
         "switch" => Some(Token::Switch),
         "this" => Some(Token::This),
         "throw" => Some(Token::Throw),
+        "true" => Some(Token::True),
         "try" => Some(Token::Try),
         "typeof" => Some(Token::TypeOf),
         "var" => Some(Token::Var),
@@ -352,191 +697,130 @@ pub fn find_match(s: &str) -> Option<Token> {
     }
 }
 
-fn escape_string(value: &str) -> String {
-    value.chars().flat_map(|c| c.escape_default()).collect()
+/// Every punctuator the scanner produces in ordinary code, longest first so
+/// that the first entry matching the input is the longest match. `</` is
+/// left out: it is only a token inside JSX.
+pub const PUNCTUATORS: &[(&str, Token)] = &[
+    // 4 characters
+    (">>>=", Token::GreaterThanGreaterThanGreaterThanEquals),
+    // 3 characters
+    ("**=", Token::AsteriskAsteriskEquals),
+    ("===", Token::EqualsEqualsEquals),
+    ("!==", Token::ExclamationEqualsEquals),
+    ("...", Token::DotDotDot),
+    (">>>", Token::GreaterThanGreaterThanGreaterThan),
+    ("<<=", Token::LessThanLessThanEquals),
+    (">>=", Token::GreaterThanGreaterThanEquals),
+    ("||=", Token::BarBarEquals),
+    ("&&=", Token::AmpersandAmpersandEquals),
+    ("??=", Token::QuestionQuestionEquals),
+    // 2 characters
+    ("**", Token::AsteriskAsterisk),
+    ("++", Token::PlusPlus),
+    ("--", Token::MinusMinus),
+    ("+=", Token::PlusEquals),
+    ("-=", Token::MinusEquals),
+    ("*=", Token::AsteriskEquals),
+    ("/=", Token::SlashEquals),
+    ("%=", Token::PercentEquals),
+    ("&=", Token::AmpersandEquals),
+    ("|=", Token::BarEquals),
+    ("^=", Token::CaretEquals),
+    ("==", Token::EqualsEquals),
+    ("!=", Token::ExclamationEquals),
+    (">=", Token::GreaterThanEquals),
+    ("<=", Token::LessThanEquals),
+    ("&&", Token::AmpersandAmpersand),
+    ("||", Token::BarBar),
+    ("??", Token::QuestionQuestion),
+    ("=>", Token::EqualsGreaterThan),
+    ("?.", Token::QuestionDot),
+    ("<<", Token::LessThanLessThan),
+    (">>", Token::GreaterThanGreaterThan),
+    // 1 character
+    ("+", Token::Plus),
+    ("-", Token::Minus),
+    ("*", Token::Asterisk),
+    ("/", Token::Slash),
+    ("%", Token::Percent),
+    ("=", Token::Equals),
+    (">", Token::GreaterThan),
+    ("<", Token::LessThan),
+    ("!", Token::Bang),
+    ("&", Token::Ampersand),
+    ("|", Token::Bar),
+    ("^", Token::Caret),
+    ("~", Token::Tilde),
+    ("?", Token::Question),
+    (":", Token::Colon),
+    (",", Token::Comma),
+    (";", Token::Semicolon),
+    (".", Token::Dot),
+    ("(", Token::OpenParen),
+    (")", Token::CloseParen),
+    ("{", Token::OpenBrace),
+    ("}", Token::CloseBrace),
+    ("[", Token::OpenBracket),
+    ("]", Token::CloseBracket),
+    ("@", Token::At),
+    ("#", Token::Hash),
+];
+
+/// The punctuator spelled `s`, if any.
+pub fn punctuator_from_str(s: &str) -> Option<Token> {
+    PUNCTUATORS
+        .iter()
+        .find(|(text, _)| *text == s)
+        .map(|(_, token)| token.clone())
 }
 
-fn escape_template(value: &str) -> String {
-    value.replace('`', "\\`")
+/// The keyword or punctuator spelled `s`, if any.
+pub fn find_match(s: &str) -> Option<Token> {
+    keyword_from_str(s).or_else(|| punctuator_from_str(s))
 }
 
-fn escape_regex_body(value: &str) -> String {
-    value.replace('/', "\\/")
+/// The source text `token` was lexed from. Tokens whose payload is a slice
+/// of the source are rendered from the whole span it sits in, delimiters
+/// and all, so even unterminated literals come back exactly as written.
+/// `Eof` has no text.
+pub(crate) fn token_fragment(token: &SpannedToken) -> Option<Cow<'_, str>> {
+    match token.payload.source_text(token.start, token.end) {
+        Some(text) => Some(Cow::Borrowed(text)),
+        None => rebuilt_fragment(token),
+    }
 }
 
-fn token_fragment(token: &Token) -> Option<Cow<'static, str>> {
-    let fragment = match token {
-        Token::Illegal => Cow::Borrowed("/*illegal*/"),
-        Token::Eof => return None,
-        Token::SingleLineCommentTrivia(text) => Cow::Owned(text.clone()),
-        Token::MultiLineCommentTrivia(text) => Cow::Owned(text.clone()),
-        Token::NewLineTrivia => Cow::Borrowed("\n"),
-        Token::WhitespaceTrivia(text) => Cow::Owned(text.clone()),
-        Token::ShebangTrivia => Cow::Borrowed("#!"),
-        Token::ConflictMarkerTrivia => Cow::Borrowed("<<<<<<<"),
-        Token::Identifier(name) => Cow::Owned(name.clone()),
-        Token::PrivateIdentifier(name) => Cow::Owned(format!("#{name}")),
-        Token::NumericLiteral(value) => Cow::Owned(value.clone()),
-        Token::BigIntLiteral(value) => Cow::Owned(format!("{value}n")),
-        Token::StringLiteral(value) => Cow::Owned(format!("\"{}\"", escape_string(value))),
-        Token::RegularExpressionLiteral(body) => {
-            Cow::Owned(format!("/{}/", escape_regex_body(body)))
+/// The text of `token` rebuilt from its kind and payload, as for tokens
+/// built by hand rather than cut from a source. Delimiters a literal ended
+/// without are put back. `Eof` has no text.
+fn rebuilt_fragment(token: &SpannedToken) -> Option<Cow<'_, str>> {
+    let fragment = match &token.payload {
+        TokenPayload::None => match token.kind {
+            TokenKind::Eof => return None,
+            TokenKind::ByteOrderMark => Cow::Borrowed("\u{FEFF}"),
+            kind => Cow::Borrowed(kind.text().unwrap_or_default()),
+        },
+        TokenPayload::Text(text) => match token.kind {
+            TokenKind::BigIntLiteral => Cow::Owned(format!("{text}n")),
+            TokenKind::NoSubstitutionTemplateLiteral => Cow::Owned(format!("`{text}`")),
+            TokenKind::TemplateHead => Cow::Owned(format!("`{text}${{")),
+            TokenKind::TemplateMiddle => Cow::Owned(format!("}}{text}${{")),
+            TokenKind::TemplateTail => Cow::Owned(format!("}}{text}`")),
+            _ => Cow::Borrowed(text.as_str()),
+        },
+        TokenPayload::NewLine(terminator) => Cow::Borrowed(terminator.as_str()),
+        TokenPayload::Symbol { raw, .. } if token.kind == TokenKind::PrivateIdentifier => {
+            Cow::Owned(format!("#{raw}"))
         }
-        Token::NoSubstitutionTemplateLiteral(value) => {
-            Cow::Owned(format!("`{}`", escape_template(value)))
+        TokenPayload::Symbol { raw, .. } | TokenPayload::String { raw, .. } => {
+            Cow::Borrowed(raw.as_str())
         }
-        Token::TemplateHead(value) => Cow::Owned(format!("`{}${{", escape_template(value))),
-        Token::TemplateMiddle(value) => Cow::Owned(format!("}}{}${{", escape_template(value))),
-        Token::TemplateTail(value) => Cow::Owned(format!("}}{}`", escape_template(value))),
-        Token::JsxText(value) | Token::JsxTextAllWhiteSpaces(value) => Cow::Owned(value.clone()),
-        Token::DotDotDot => Cow::Borrowed("..."),
-        Token::QuestionDot => Cow::Borrowed("?."),
-        Token::LessThanSlash => Cow::Borrowed("</"),
-        Token::Plus => Cow::Borrowed("+"),
-        Token::Minus => Cow::Borrowed("-"),
-        Token::Asterisk => Cow::Borrowed("*"),
-        Token::AsteriskAsterisk => Cow::Borrowed("**"),
-        Token::Slash => Cow::Borrowed("/"),
-        Token::Percent => Cow::Borrowed("%"),
-        Token::PlusPlus => Cow::Borrowed("++"),
-        Token::MinusMinus => Cow::Borrowed("--"),
-        Token::LessThanLessThan => Cow::Borrowed("<<"),
-        Token::GreaterThanGreaterThan => Cow::Borrowed(">>"),
-        Token::GreaterThanGreaterThanGreaterThan => Cow::Borrowed(">>>"),
-        Token::Ampersand => Cow::Borrowed("&"),
-        Token::Bar => Cow::Borrowed("|"),
-        Token::Caret => Cow::Borrowed("^"),
-        Token::Bang => Cow::Borrowed("!"),
-        Token::Tilde => Cow::Borrowed("~"),
-        Token::AmpersandAmpersand => Cow::Borrowed("&&"),
-        Token::BarBar => Cow::Borrowed("||"),
-        Token::Question => Cow::Borrowed("?"),
-        Token::At => Cow::Borrowed("@"),
-        Token::QuestionQuestion => Cow::Borrowed("??"),
-        Token::Hash => Cow::Borrowed("#"),
-        Token::Equals => Cow::Borrowed("="),
-        Token::PlusEquals => Cow::Borrowed("+="),
-        Token::MinusEquals => Cow::Borrowed("-="),
-        Token::AsteriskEquals => Cow::Borrowed("*="),
-        Token::AsteriskAsteriskEquals => Cow::Borrowed("**="),
-        Token::SlashEquals => Cow::Borrowed("/="),
-        Token::PercentEquals => Cow::Borrowed("%="),
-        Token::LessThanLessThanEquals => Cow::Borrowed("<<="),
-        Token::GreaterThanGreaterThanEquals => Cow::Borrowed(">>="),
-        Token::GreaterThanGreaterThanGreaterThanEquals => Cow::Borrowed(">>>="),
-        Token::AmpersandEquals => Cow::Borrowed("&="),
-        Token::BarEquals => Cow::Borrowed("|="),
-        Token::CaretEquals => Cow::Borrowed("^="),
-        Token::BarBarEquals => Cow::Borrowed("||="),
-        Token::AmpersandAmpersandEquals => Cow::Borrowed("&&="),
-        Token::QuestionQuestionEquals => Cow::Borrowed("??="),
-        Token::EqualsEquals => Cow::Borrowed("=="),
-        Token::ExclamationEquals => Cow::Borrowed("!="),
-        Token::EqualsEqualsEquals => Cow::Borrowed("==="),
-        Token::ExclamationEqualsEquals => Cow::Borrowed("!=="),
-        Token::GreaterThan => Cow::Borrowed(">"),
-        Token::LessThan => Cow::Borrowed("<"),
-        Token::GreaterThanEquals => Cow::Borrowed(">="),
-        Token::LessThanEquals => Cow::Borrowed("<="),
-        Token::EqualsGreaterThan => Cow::Borrowed("=>"),
-        Token::Comma => Cow::Borrowed(","),
-        Token::Semicolon => Cow::Borrowed(";"),
-        Token::Colon => Cow::Borrowed(":"),
-        Token::Dot => Cow::Borrowed("."),
-        Token::OpenParen => Cow::Borrowed("("),
-        Token::CloseParen => Cow::Borrowed(")"),
-        Token::OpenBrace => Cow::Borrowed("{"),
-        Token::CloseBrace => Cow::Borrowed("}"),
-        Token::OpenBracket => Cow::Borrowed("["),
-        Token::CloseBracket => Cow::Borrowed("]"),
-        Token::Break => Cow::Borrowed("break"),
-        Token::Case => Cow::Borrowed("case"),
-        Token::Catch => Cow::Borrowed("catch"),
-        Token::Class => Cow::Borrowed("class"),
-        Token::Const => Cow::Borrowed("const"),
-        Token::Continue => Cow::Borrowed("continue"),
-        Token::Debugger => Cow::Borrowed("debugger"),
-        Token::Default => Cow::Borrowed("default"),
-        Token::Delete => Cow::Borrowed("delete"),
-        Token::Do => Cow::Borrowed("do"),
-        Token::Else => Cow::Borrowed("else"),
-        Token::Enum => Cow::Borrowed("enum"),
-        Token::Export => Cow::Borrowed("export"),
-        Token::Extends => Cow::Borrowed("extends"),
-        Token::False => Cow::Borrowed("false"),
-        Token::Finally => Cow::Borrowed("finally"),
-        Token::For => Cow::Borrowed("for"),
-        Token::Function => Cow::Borrowed("function"),
-        Token::If => Cow::Borrowed("if"),
-        Token::Import => Cow::Borrowed("import"),
-        Token::In => Cow::Borrowed("in"),
-        Token::InstanceOf => Cow::Borrowed("instanceof"),
-        Token::New => Cow::Borrowed("new"),
-        Token::Null => Cow::Borrowed("null"),
-        Token::Return => Cow::Borrowed("return"),
-        Token::Super => Cow::Borrowed("super"),
-        Token::Switch => Cow::Borrowed("switch"),
-        Token::This => Cow::Borrowed("this"),
-        Token::Throw => Cow::Borrowed("throw"),
-        Token::True => Cow::Borrowed("true"),
-        Token::Try => Cow::Borrowed("try"),
-        Token::TypeOf => Cow::Borrowed("typeof"),
-        Token::Var => Cow::Borrowed("var"),
-        Token::Void => Cow::Borrowed("void"),
-        Token::While => Cow::Borrowed("while"),
-        Token::With => Cow::Borrowed("with"),
-        Token::Implements => Cow::Borrowed("implements"),
-        Token::Interface => Cow::Borrowed("interface"),
-        Token::Let => Cow::Borrowed("let"),
-        Token::Package => Cow::Borrowed("package"),
-        Token::Private => Cow::Borrowed("private"),
-        Token::Protected => Cow::Borrowed("protected"),
-        Token::Public => Cow::Borrowed("public"),
-        Token::Static => Cow::Borrowed("static"),
-        Token::Yield => Cow::Borrowed("yield"),
-        Token::Abstract => Cow::Borrowed("abstract"),
-        Token::As => Cow::Borrowed("as"),
-        Token::Asserts => Cow::Borrowed("asserts"),
-        Token::Any => Cow::Borrowed("any"),
-        Token::Async => Cow::Borrowed("async"),
-        Token::Await => Cow::Borrowed("await"),
-        Token::Boolean => Cow::Borrowed("boolean"),
-        Token::Constructor => Cow::Borrowed("constructor"),
-        Token::Declare => Cow::Borrowed("declare"),
-        Token::Get => Cow::Borrowed("get"),
-        Token::Infer => Cow::Borrowed("infer"),
-        Token::Is => Cow::Borrowed("is"),
-        Token::KeyOf => Cow::Borrowed("keyof"),
-        Token::Module => Cow::Borrowed("module"),
-        Token::Namespace => Cow::Borrowed("namespace"),
-        Token::Never => Cow::Borrowed("never"),
-        Token::Readonly => Cow::Borrowed("readonly"),
-        Token::Require => Cow::Borrowed("require"),
-        Token::Number => Cow::Borrowed("number"),
-        Token::Object => Cow::Borrowed("object"),
-        Token::Set => Cow::Borrowed("set"),
-        Token::String => Cow::Borrowed("string"),
-        Token::Symbol => Cow::Borrowed("symbol"),
-        Token::Type => Cow::Borrowed("type"),
-        Token::Undefined => Cow::Borrowed("undefined"),
-        Token::Unique => Cow::Borrowed("unique"),
-        Token::Unknown => Cow::Borrowed("unknown"),
-        Token::From => Cow::Borrowed("from"),
-        Token::Global => Cow::Borrowed("global"),
-        Token::BigInt => Cow::Borrowed("bigint"),
-        Token::Of => Cow::Borrowed("of"),
-        Token::Satisfies => Cow::Borrowed("satisfies"),
-        Token::Override => Cow::Borrowed("override"),
-        Token::Using => Cow::Borrowed("using"),
+        TokenPayload::Regex { body, flags } => Cow::Owned(format!("/{body}/{flags}")),
     };
 
     Some(fragment)
 }
 
-fn is_identifier_char(c: char) -> bool {
-    c.is_ascii_alphanumeric() || matches!(c, '_' | '$')
-}
-
 fn needs_separator(prev_fragment: &str, next_fragment: &str) -> bool {
     if prev_fragment
         .chars()
@@ -560,21 +844,23 @@ fn needs_separator(prev_fragment: &str, next_fragment: &str) -> bool {
     let next_significant = next_fragment.chars().find(|c| !c.is_whitespace());
 
     match (prev_significant, next_significant) {
-        (Some(prev), Some(next)) => is_identifier_char(prev) && is_identifier_char(next),
+        (Some(prev), Some(next)) => is_identifier_part(prev) && is_identifier_part(next),
         _ => false,
     }
 }
 
 /// Render a sequence of tokens back into a source string suitable for tests.
 ///
-/// This function reconstructs the original source code exactly, preserving
-/// all whitespace, newlines, and comment content as stored in the tokens.
+/// Each token is rebuilt from its kind and payload, and a space is put
+/// between tokens that would otherwise run together, so the result lexes
+/// back to the same tokens. For the exact text lexed tokens came from, use
+/// [`tokens_to_source_exact`].
 pub fn tokens_to_source<'a, I>(tokens: I) -> String
 where
     I: IntoIterator<Item = &'a SpannedToken>,
 {
     tokens.into_iter().fold(String::new(), |mut acc, token| {
-        if let Some(fragment) = token_fragment(&token.value) {
+        if let Some(fragment) = rebuilt_fragment(token) {
             if !acc.is_empty() && needs_separator(&acc, &fragment) {
                 acc.push(' ');
             }
@@ -584,33 +870,45 @@ where
     })
 }
 
+/// Render lexed tokens back into the source they came from.
+///
+/// Lexed tokens tile their source, so rendering them all gives back the
+/// source exactly, whitespace, comments and malformed input included.
+pub fn tokens_to_source_exact<'a, I>(tokens: I) -> String
+where
+    I: IntoIterator<Item = &'a SpannedToken>,
+{
+    tokens
+        .into_iter()
+        .filter_map(token_fragment)
+        .fold(String::new(), |mut acc, fragment| {
+            acc.push_str(&fragment);
+            acc
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn spanned(token: Token, line: u32, column: u32, start: usize, end: usize) -> SpannedToken {
+        SpannedToken {
+            kind: token.kind(),
+            payload: TokenPayload::new(token, &Interner::new()),
+            line,
+            column,
+            start,
+            end,
+        }
+    }
+
     #[test]
     fn renders_identifier_numeric_and_operator() {
         let tokens = vec![
-            SpannedToken {
-                value: Token::Identifier("foo".into()),
-                line: 1,
-                column: 1,
-            },
-            SpannedToken {
-                value: Token::Plus,
-                line: 1,
-                column: 4,
-            },
-            SpannedToken {
-                value: Token::NumericLiteral("42".into()),
-                line: 1,
-                column: 5,
-            },
-            SpannedToken {
-                value: Token::Eof,
-                line: 1,
-                column: 7,
-            },
+            spanned(Token::Identifier("foo".into()), 1, 1, 0, 3),
+            spanned(Token::Plus, 1, 4, 3, 4),
+            spanned(Token::NumericLiteral("42".into()), 1, 5, 4, 6),
+            spanned(Token::Eof, 1, 7, 6, 6),
         ];
 
         assert_eq!(tokens_to_source(&tokens), "foo+42");
@@ -619,51 +917,135 @@ mod tests {
     #[test]
     fn emits_whitespace_token_verbatim() {
         let tokens = vec![
-            SpannedToken {
-                value: Token::Identifier("let".into()),
-                line: 1,
-                column: 1,
-            },
-            SpannedToken {
-                value: Token::WhitespaceTrivia(" ".into()),
-                line: 1,
-                column: 4,
-            },
-            SpannedToken {
-                value: Token::Identifier("x".into()),
-                line: 1,
-                column: 5,
-            },
-            SpannedToken {
-                value: Token::Equals,
-                line: 1,
-                column: 6,
-            },
-            SpannedToken {
-                value: Token::NumericLiteral("1".into()),
-                line: 1,
-                column: 7,
-            },
+            spanned(Token::Identifier("let".into()), 1, 1, 0, 3),
+            spanned(Token::WhitespaceTrivia(" ".into()), 1, 4, 3, 4),
+            spanned(Token::Identifier("x".into()), 1, 5, 4, 5),
+            spanned(Token::Equals, 1, 6, 5, 6),
+            spanned(Token::NumericLiteral("1".into()), 1, 7, 6, 7),
         ];
 
         assert_eq!(tokens_to_source(&tokens), "let x=1");
     }
 
+    #[test]
+    fn decodes_identifier_escapes() {
+        assert_eq!(
+            Token::Identifier(r"caf\u00e9".into()).identifier_name(),
+            Some(Cow::Borrowed("café"))
+        );
+        assert_eq!(
+            Token::Identifier(r"\u{1d4b3}x".into()).identifier_name(),
+            Some(Cow::Borrowed("\u{1d4b3}x"))
+        );
+        assert_eq!(
+            Token::Identifier("名前".into()).identifier_name(),
+            Some(Cow::Borrowed("名前"))
+        );
+        assert_eq!(Token::Comma.identifier_name(), None);
+    }
+
     #[test]
     fn separates_adjacent_identifiers() {
         let tokens = vec![
-            SpannedToken {
-                value: Token::Identifier("foo".into()),
-                line: 1,
-                column: 1,
-            },
-            SpannedToken {
-                value: Token::Identifier("bar".into()),
-                line: 1,
-                column: 5,
-            },
+            spanned(Token::Identifier("foo".into()), 1, 1, 0, 3),
+            spanned(Token::Identifier("bar".into()), 1, 5, 4, 7),
         ];
 
         assert_eq!(tokens_to_source(&tokens), "foo bar");
     }
+
+    #[test]
+    fn renders_lexed_tokens_exactly() {
+        // Unterminated literals keep their missing delimiters missing, and
+        // nothing is inserted between tokens the source runs together
+        for source in ["`abc", "`a${b", "'open", "/* open", "1in", "#\\u0061b"] {
+            let tokens = crate::lexer::Lexer::new(source).lex();
+            assert_eq!(tokens_to_source_exact(&tokens), source);
+        }
+        let tokens = crate::lexer::Lexer::new("`abc 1in").lex();
+        assert_eq!(tokens_to_source(&tokens), "`abc 1in`");
+    }
+
+    #[test]
+    fn separates_keywords_from_punctuators() {
+        assert_eq!(keyword_from_str("null"), Some(Token::Null));
+        assert_eq!(keyword_from_str("true"), Some(Token::True));
+        assert_eq!(keyword_from_str("=>"), None);
+        assert_eq!(punctuator_from_str("=>"), Some(Token::EqualsGreaterThan));
+        assert_eq!(punctuator_from_str("in"), None);
+        assert_eq!(find_match("satisfies"), Some(Token::Satisfies));
+    }
+
+    #[test]
+    fn classifies_keywords() {
+        let classes = |token: Token| {
+            (
+                token.is_reserved_word(),
+                token.is_future_reserved_word(),
+                token.is_contextual_keyword(),
+            )
+        };
+        assert_eq!(classes(Token::Null), (true, false, false));
+        assert_eq!(classes(Token::Yield), (false, true, false));
+        assert_eq!(classes(Token::Satisfies), (false, false, true));
+        assert_eq!(
+            classes(Token::Identifier("x".into())),
+            (false, false, false)
+        );
+
+        assert!(Token::Readonly.is_modifier() && Token::Readonly.is_keyword());
+        assert!(!Token::Type.is_modifier());
+        assert!(Token::Default.is_identifier_or_keyword());
+        assert!(!Token::Plus.is_keyword());
+    }
+
+    #[test]
+    fn classifies_literals_operators_and_trivia() {
+        assert!(Token::BigIntLiteral("1".into()).is_literal());
+        assert!(!Token::True.is_literal());
+        assert!(Token::TemplateMiddle("".into()).is_template_literal());
+        assert!(Token::NewLineTrivia(LineTerminator::CrLf).is_trivia());
+        assert!(!Token::JsxTextAllWhiteSpaces(" ".into()).is_trivia());
+
+        assert!(Token::QuestionQuestionEquals.is_compound_assignment_operator());
+        assert!(Token::Equals.is_assignment_operator());
+        assert!(!Token::Equals.is_compound_assignment_operator());
+        assert!(!Token::EqualsEquals.is_assignment_operator());
+        assert!(Token::GreaterThanGreaterThanGreaterThanEquals.is_punctuation());
+        assert!(!Token::In.is_punctuation());
+    }
+
+    #[test]
+    fn binary_precedence_matches_typescript() {
+        use OperatorPrecedence::*;
+
+        let ladder = [
+            (Token::QuestionQuestion, OperatorPrecedence::COALESCE),
+            (Token::BarBar, LogicalOr),
+            (Token::AmpersandAmpersand, LogicalAnd),
+            (Token::Bar, BitwiseOr),
+            (Token::Caret, BitwiseXor),
+            (Token::Ampersand, BitwiseAnd),
+            (Token::ExclamationEqualsEquals, Equality),
+            (Token::As, Relational),
+            (Token::GreaterThanGreaterThanGreaterThan, Shift),
+            (Token::Minus, Additive),
+            (Token::Percent, Multiplicative),
+            (Token::AsteriskAsterisk, Exponentiation),
+        ];
+        for pair in ladder.windows(2) {
+            assert!(pair[0].1 < pair[1].1);
+        }
+        for (token, precedence) in ladder {
+            assert_eq!(token.binary_operator_precedence(), Some(precedence));
+        }
+
+        assert_eq!(OperatorPrecedence::COALESCE, Conditional);
+        assert_eq!(
+            Token::InstanceOf.binary_operator_precedence(),
+            Some(Relational)
+        );
+        assert_eq!(Token::Equals.binary_operator_precedence(), None);
+        assert!(!Token::Comma.is_binary_operator());
+    }
 }
@@ -0,0 +1,964 @@
+//! Token kind module — fieldless token kinds and sets of them.
+
+use std::fmt;
+
+use crate::token::{OperatorPrecedence, SpannedToken, Token};
+
+/// The kind of a [`Token`] without its payload. Discriminants follow
+/// TypeScript's `SyntaxKind` numbering for tokens, so ranges such as "all
+/// keywords" are contiguous and kinds can be compared with `tsc` output.
+/// Numbers for kinds this lexer never produces are left unused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum TokenKind {
+    /// `Unknown` in TypeScript.
+    Illegal = 0,
+    Eof = 1,
+    SingleLineCommentTrivia = 2,
+    MultiLineCommentTrivia = 3,
+    NewLineTrivia = 4,
+    WhitespaceTrivia = 5,
+    ShebangTrivia = 6,
+    ConflictMarkerTrivia = 7,
+    // 8: NonTextFileMarkerTrivia, not produced by this lexer
+    NumericLiteral = 9,
+    BigIntLiteral = 10,
+    StringLiteral = 11,
+    JsxText = 12,
+    JsxTextAllWhiteSpaces = 13,
+    RegularExpressionLiteral = 14,
+    NoSubstitutionTemplateLiteral = 15,
+    TemplateHead = 16,
+    TemplateMiddle = 17,
+    TemplateTail = 18,
+    OpenBrace = 19,
+    CloseBrace = 20,
+    OpenParen = 21,
+    CloseParen = 22,
+    OpenBracket = 23,
+    CloseBracket = 24,
+    Dot = 25,
+    DotDotDot = 26,
+    Semicolon = 27,
+    Comma = 28,
+    QuestionDot = 29,
+    LessThan = 30,
+    LessThanSlash = 31,
+    GreaterThan = 32,
+    LessThanEquals = 33,
+    GreaterThanEquals = 34,
+    EqualsEquals = 35,
+    ExclamationEquals = 36,
+    EqualsEqualsEquals = 37,
+    ExclamationEqualsEquals = 38,
+    EqualsGreaterThan = 39,
+    Plus = 40,
+    Minus = 41,
+    Asterisk = 42,
+    AsteriskAsterisk = 43,
+    Slash = 44,
+    Percent = 45,
+    PlusPlus = 46,
+    MinusMinus = 47,
+    LessThanLessThan = 48,
+    GreaterThanGreaterThan = 49,
+    GreaterThanGreaterThanGreaterThan = 50,
+    Ampersand = 51,
+    Bar = 52,
+    Caret = 53,
+    Bang = 54,
+    Tilde = 55,
+    AmpersandAmpersand = 56,
+    BarBar = 57,
+    Question = 58,
+    Colon = 59,
+    At = 60,
+    QuestionQuestion = 61,
+    // 62: BacktickToken, not produced by this lexer
+    Hash = 63,
+    Equals = 64,
+    PlusEquals = 65,
+    MinusEquals = 66,
+    AsteriskEquals = 67,
+    AsteriskAsteriskEquals = 68,
+    SlashEquals = 69,
+    PercentEquals = 70,
+    LessThanLessThanEquals = 71,
+    GreaterThanGreaterThanEquals = 72,
+    GreaterThanGreaterThanGreaterThanEquals = 73,
+    AmpersandEquals = 74,
+    BarEquals = 75,
+    BarBarEquals = 76,
+    AmpersandAmpersandEquals = 77,
+    QuestionQuestionEquals = 78,
+    CaretEquals = 79,
+    Identifier = 80,
+    PrivateIdentifier = 81,
+    // 82: JSDocCommentTextToken, not produced by this lexer
+    Break = 83,
+    Case = 84,
+    Catch = 85,
+    Class = 86,
+    Const = 87,
+    Continue = 88,
+    Debugger = 89,
+    Default = 90,
+    Delete = 91,
+    Do = 92,
+    Else = 93,
+    Enum = 94,
+    Export = 95,
+    Extends = 96,
+    False = 97,
+    Finally = 98,
+    For = 99,
+    Function = 100,
+    If = 101,
+    Import = 102,
+    In = 103,
+    InstanceOf = 104,
+    New = 105,
+    Null = 106,
+    Return = 107,
+    Super = 108,
+    Switch = 109,
+    This = 110,
+    Throw = 111,
+    True = 112,
+    Try = 113,
+    TypeOf = 114,
+    Var = 115,
+    Void = 116,
+    While = 117,
+    With = 118,
+    Implements = 119,
+    Interface = 120,
+    Let = 121,
+    Package = 122,
+    Private = 123,
+    Protected = 124,
+    Public = 125,
+    Static = 126,
+    Yield = 127,
+    Abstract = 128,
+    // 129: AccessorKeyword, not produced by this lexer
+    As = 130,
+    Asserts = 131,
+    // 132: AssertKeyword, not produced by this lexer
+    Any = 133,
+    Async = 134,
+    Await = 135,
+    Boolean = 136,
+    Constructor = 137,
+    Declare = 138,
+    Get = 139,
+    Infer = 140,
+    // 141: IntrinsicKeyword, not produced by this lexer
+    Is = 142,
+    KeyOf = 143,
+    Module = 144,
+    Namespace = 145,
+    Never = 146,
+    // 147: OutKeyword, not produced by this lexer
+    Readonly = 148,
+    Require = 149,
+    Number = 150,
+    Object = 151,
+    Satisfies = 152,
+    Set = 153,
+    String = 154,
+    Symbol = 155,
+    Type = 156,
+    Undefined = 157,
+    Unique = 158,
+    Unknown = 159,
+    Using = 160,
+    From = 161,
+    Global = 162,
+    BigInt = 163,
+    Override = 164,
+    Of = 165,
+    /// Not a TypeScript kind; numbered after the last TypeScript token.
+    ByteOrderMark = 166,
+}
+
+impl TokenKind {
+    /// Every kind, in numbering order.
+    pub const ALL: [TokenKind; 160] = [
+        TokenKind::Illegal,
+        TokenKind::Eof,
+        TokenKind::SingleLineCommentTrivia,
+        TokenKind::MultiLineCommentTrivia,
+        TokenKind::NewLineTrivia,
+        TokenKind::WhitespaceTrivia,
+        TokenKind::ShebangTrivia,
+        TokenKind::ConflictMarkerTrivia,
+        TokenKind::NumericLiteral,
+        TokenKind::BigIntLiteral,
+        TokenKind::StringLiteral,
+        TokenKind::JsxText,
+        TokenKind::JsxTextAllWhiteSpaces,
+        TokenKind::RegularExpressionLiteral,
+        TokenKind::NoSubstitutionTemplateLiteral,
+        TokenKind::TemplateHead,
+        TokenKind::TemplateMiddle,
+        TokenKind::TemplateTail,
+        TokenKind::OpenBrace,
+        TokenKind::CloseBrace,
+        TokenKind::OpenParen,
+        TokenKind::CloseParen,
+        TokenKind::OpenBracket,
+        TokenKind::CloseBracket,
+        TokenKind::Dot,
+        TokenKind::DotDotDot,
+        TokenKind::Semicolon,
+        TokenKind::Comma,
+        TokenKind::QuestionDot,
+        TokenKind::LessThan,
+        TokenKind::LessThanSlash,
+        TokenKind::GreaterThan,
+        TokenKind::LessThanEquals,
+        TokenKind::GreaterThanEquals,
+        TokenKind::EqualsEquals,
+        TokenKind::ExclamationEquals,
+        TokenKind::EqualsEqualsEquals,
+        TokenKind::ExclamationEqualsEquals,
+        TokenKind::EqualsGreaterThan,
+        TokenKind::Plus,
+        TokenKind::Minus,
+        TokenKind::Asterisk,
+        TokenKind::AsteriskAsterisk,
+        TokenKind::Slash,
+        TokenKind::Percent,
+        TokenKind::PlusPlus,
+        TokenKind::MinusMinus,
+        TokenKind::LessThanLessThan,
+        TokenKind::GreaterThanGreaterThan,
+        TokenKind::GreaterThanGreaterThanGreaterThan,
+        TokenKind::Ampersand,
+        TokenKind::Bar,
+        TokenKind::Caret,
+        TokenKind::Bang,
+        TokenKind::Tilde,
+        TokenKind::AmpersandAmpersand,
+        TokenKind::BarBar,
+        TokenKind::Question,
+        TokenKind::Colon,
+        TokenKind::At,
+        TokenKind::QuestionQuestion,
+        TokenKind::Hash,
+        TokenKind::Equals,
+        TokenKind::PlusEquals,
+        TokenKind::MinusEquals,
+        TokenKind::AsteriskEquals,
+        TokenKind::AsteriskAsteriskEquals,
+        TokenKind::SlashEquals,
+        TokenKind::PercentEquals,
+        TokenKind::LessThanLessThanEquals,
+        TokenKind::GreaterThanGreaterThanEquals,
+        TokenKind::GreaterThanGreaterThanGreaterThanEquals,
+        TokenKind::AmpersandEquals,
+        TokenKind::BarEquals,
+        TokenKind::BarBarEquals,
+        TokenKind::AmpersandAmpersandEquals,
+        TokenKind::QuestionQuestionEquals,
+        TokenKind::CaretEquals,
+        TokenKind::Identifier,
+        TokenKind::PrivateIdentifier,
+        TokenKind::Break,
+        TokenKind::Case,
+        TokenKind::Catch,
+        TokenKind::Class,
+        TokenKind::Const,
+        TokenKind::Continue,
+        TokenKind::Debugger,
+        TokenKind::Default,
+        TokenKind::Delete,
+        TokenKind::Do,
+        TokenKind::Else,
+        TokenKind::Enum,
+        TokenKind::Export,
+        TokenKind::Extends,
+        TokenKind::False,
+        TokenKind::Finally,
+        TokenKind::For,
+        TokenKind::Function,
+        TokenKind::If,
+        TokenKind::Import,
+        TokenKind::In,
+        TokenKind::InstanceOf,
+        TokenKind::New,
+        TokenKind::Null,
+        TokenKind::Return,
+        TokenKind::Super,
+        TokenKind::Switch,
+        TokenKind::This,
+        TokenKind::Throw,
+        TokenKind::True,
+        TokenKind::Try,
+        TokenKind::TypeOf,
+        TokenKind::Var,
+        TokenKind::Void,
+        TokenKind::While,
+        TokenKind::With,
+        TokenKind::Implements,
+        TokenKind::Interface,
+        TokenKind::Let,
+        TokenKind::Package,
+        TokenKind::Private,
+        TokenKind::Protected,
+        TokenKind::Public,
+        TokenKind::Static,
+        TokenKind::Yield,
+        TokenKind::Abstract,
+        TokenKind::As,
+        TokenKind::Asserts,
+        TokenKind::Any,
+        TokenKind::Async,
+        TokenKind::Await,
+        TokenKind::Boolean,
+        TokenKind::Constructor,
+        TokenKind::Declare,
+        TokenKind::Get,
+        TokenKind::Infer,
+        TokenKind::Is,
+        TokenKind::KeyOf,
+        TokenKind::Module,
+        TokenKind::Namespace,
+        TokenKind::Never,
+        TokenKind::Readonly,
+        TokenKind::Require,
+        TokenKind::Number,
+        TokenKind::Object,
+        TokenKind::Satisfies,
+        TokenKind::Set,
+        TokenKind::String,
+        TokenKind::Symbol,
+        TokenKind::Type,
+        TokenKind::Undefined,
+        TokenKind::Unique,
+        TokenKind::Unknown,
+        TokenKind::Using,
+        TokenKind::From,
+        TokenKind::Global,
+        TokenKind::BigInt,
+        TokenKind::Override,
+        TokenKind::Of,
+        TokenKind::ByteOrderMark,
+    ];
+
+    pub const FIRST_KEYWORD: Self = Self::Break;
+    pub const LAST_KEYWORD: Self = Self::Of;
+
+    pub const fn as_u8(self) -> u8 {
+        self as u8
+    }
+
+    /// The fixed spelling of a punctuator or keyword kind. Kinds whose text
+    /// varies, like identifiers and literals, have none.
+    pub fn text(self) -> Option<&'static str> {
+        let text = match self {
+            TokenKind::OpenBrace => "{",
+            TokenKind::CloseBrace => "}",
+            TokenKind::OpenParen => "(",
+            TokenKind::CloseParen => ")",
+            TokenKind::OpenBracket => "[",
+            TokenKind::CloseBracket => "]",
+            TokenKind::Dot => ".",
+            TokenKind::DotDotDot => "...",
+            TokenKind::Semicolon => ";",
+            TokenKind::Comma => ",",
+            TokenKind::QuestionDot => "?.",
+            TokenKind::LessThan => "<",
+            TokenKind::LessThanSlash => "</",
+            TokenKind::GreaterThan => ">",
+            TokenKind::LessThanEquals => "<=",
+            TokenKind::GreaterThanEquals => ">=",
+            TokenKind::EqualsEquals => "==",
+            TokenKind::ExclamationEquals => "!=",
+            TokenKind::EqualsEqualsEquals => "===",
+            TokenKind::ExclamationEqualsEquals => "!==",
+            TokenKind::EqualsGreaterThan => "=>",
+            TokenKind::Plus => "+",
+            TokenKind::Minus => "-",
+            TokenKind::Asterisk => "*",
+            TokenKind::AsteriskAsterisk => "**",
+            TokenKind::Slash => "/",
+            TokenKind::Percent => "%",
+            TokenKind::PlusPlus => "++",
+            TokenKind::MinusMinus => "--",
+            TokenKind::LessThanLessThan => "<<",
+            TokenKind::GreaterThanGreaterThan => ">>",
+            TokenKind::GreaterThanGreaterThanGreaterThan => ">>>",
+            TokenKind::Ampersand => "&",
+            TokenKind::Bar => "|",
+            TokenKind::Caret => "^",
+            TokenKind::Bang => "!",
+            TokenKind::Tilde => "~",
+            TokenKind::AmpersandAmpersand => "&&",
+            TokenKind::BarBar => "||",
+            TokenKind::Question => "?",
+            TokenKind::Colon => ":",
+            TokenKind::At => "@",
+            TokenKind::QuestionQuestion => "??",
+            TokenKind::Hash => "#",
+            TokenKind::Equals => "=",
+            TokenKind::PlusEquals => "+=",
+            TokenKind::MinusEquals => "-=",
+            TokenKind::AsteriskEquals => "*=",
+            TokenKind::AsteriskAsteriskEquals => "**=",
+            TokenKind::SlashEquals => "/=",
+            TokenKind::PercentEquals => "%=",
+            TokenKind::LessThanLessThanEquals => "<<=",
+            TokenKind::GreaterThanGreaterThanEquals => ">>=",
+            TokenKind::GreaterThanGreaterThanGreaterThanEquals => ">>>=",
+            TokenKind::AmpersandEquals => "&=",
+            TokenKind::BarEquals => "|=",
+            TokenKind::BarBarEquals => "||=",
+            TokenKind::AmpersandAmpersandEquals => "&&=",
+            TokenKind::QuestionQuestionEquals => "??=",
+            TokenKind::CaretEquals => "^=",
+            TokenKind::Break => "break",
+            TokenKind::Case => "case",
+            TokenKind::Catch => "catch",
+            TokenKind::Class => "class",
+            TokenKind::Const => "const",
+            TokenKind::Continue => "continue",
+            TokenKind::Debugger => "debugger",
+            TokenKind::Default => "default",
+            TokenKind::Delete => "delete",
+            TokenKind::Do => "do",
+            TokenKind::Else => "else",
+            TokenKind::Enum => "enum",
+            TokenKind::Export => "export",
+            TokenKind::Extends => "extends",
+            TokenKind::False => "false",
+            TokenKind::Finally => "finally",
+            TokenKind::For => "for",
+            TokenKind::Function => "function",
+            TokenKind::If => "if",
+            TokenKind::Import => "import",
+            TokenKind::In => "in",
+            TokenKind::InstanceOf => "instanceof",
+            TokenKind::New => "new",
+            TokenKind::Null => "null",
+            TokenKind::Return => "return",
+            TokenKind::Super => "super",
+            TokenKind::Switch => "switch",
+            TokenKind::This => "this",
+            TokenKind::Throw => "throw",
+            TokenKind::True => "true",
+            TokenKind::Try => "try",
+            TokenKind::TypeOf => "typeof",
+            TokenKind::Var => "var",
+            TokenKind::Void => "void",
+            TokenKind::While => "while",
+            TokenKind::With => "with",
+            TokenKind::Implements => "implements",
+            TokenKind::Interface => "interface",
+            TokenKind::Let => "let",
+            TokenKind::Package => "package",
+            TokenKind::Private => "private",
+            TokenKind::Protected => "protected",
+            TokenKind::Public => "public",
+            TokenKind::Static => "static",
+            TokenKind::Yield => "yield",
+            TokenKind::Abstract => "abstract",
+            TokenKind::As => "as",
+            TokenKind::Asserts => "asserts",
+            TokenKind::Any => "any",
+            TokenKind::Async => "async",
+            TokenKind::Await => "await",
+            TokenKind::Boolean => "boolean",
+            TokenKind::Constructor => "constructor",
+            TokenKind::Declare => "declare",
+            TokenKind::Get => "get",
+            TokenKind::Infer => "infer",
+            TokenKind::Is => "is",
+            TokenKind::KeyOf => "keyof",
+            TokenKind::Module => "module",
+            TokenKind::Namespace => "namespace",
+            TokenKind::Never => "never",
+            TokenKind::Readonly => "readonly",
+            TokenKind::Require => "require",
+            TokenKind::Number => "number",
+            TokenKind::Object => "object",
+            TokenKind::Satisfies => "satisfies",
+            TokenKind::Set => "set",
+            TokenKind::String => "string",
+            TokenKind::Symbol => "symbol",
+            TokenKind::Type => "type",
+            TokenKind::Undefined => "undefined",
+            TokenKind::Unique => "unique",
+            TokenKind::Unknown => "unknown",
+            TokenKind::Using => "using",
+            TokenKind::From => "from",
+            TokenKind::Global => "global",
+            TokenKind::BigInt => "bigint",
+            TokenKind::Override => "override",
+            TokenKind::Of => "of",
+            _ => return None,
+        };
+        Some(text)
+    }
+
+    pub fn is_keyword(self) -> bool {
+        (Self::FIRST_KEYWORD..=Self::LAST_KEYWORD).contains(&self)
+    }
+
+    pub fn is_reserved_word(self) -> bool {
+        (Self::Break..=Self::With).contains(&self)
+    }
+
+    pub fn is_future_reserved_word(self) -> bool {
+        (Self::Implements..=Self::Yield).contains(&self)
+    }
+
+    pub fn is_contextual_keyword(self) -> bool {
+        (Self::Abstract..=Self::Of).contains(&self)
+    }
+
+    pub fn is_modifier(self) -> bool {
+        matches!(
+            self,
+            Self::Abstract
+                | Self::Async
+                | Self::Const
+                | Self::Declare
+                | Self::Default
+                | Self::Export
+                | Self::In
+                | Self::Override
+                | Self::Private
+                | Self::Protected
+                | Self::Public
+                | Self::Readonly
+                | Self::Static
+        )
+    }
+
+    pub fn is_literal(self) -> bool {
+        (Self::NumericLiteral..=Self::NoSubstitutionTemplateLiteral).contains(&self)
+    }
+
+    pub fn is_template_literal(self) -> bool {
+        (Self::NoSubstitutionTemplateLiteral..=Self::TemplateTail).contains(&self)
+    }
+
+    pub fn is_trivia(self) -> bool {
+        (Self::SingleLineCommentTrivia..=Self::ConflictMarkerTrivia).contains(&self)
+            || self == Self::ByteOrderMark
+    }
+
+    pub fn is_punctuation(self) -> bool {
+        (Self::OpenBrace..=Self::CaretEquals).contains(&self)
+    }
+
+    pub fn is_assignment_operator(self) -> bool {
+        (Self::Equals..=Self::CaretEquals).contains(&self)
+    }
+
+    pub fn is_compound_assignment_operator(self) -> bool {
+        (Self::PlusEquals..=Self::CaretEquals).contains(&self)
+    }
+
+    /// See [`Token::binary_operator_precedence`].
+    pub fn binary_operator_precedence(self) -> Option<OperatorPrecedence> {
+        let precedence = match self {
+            Self::QuestionQuestion => OperatorPrecedence::COALESCE,
+            Self::BarBar => OperatorPrecedence::LogicalOr,
+            Self::AmpersandAmpersand => OperatorPrecedence::LogicalAnd,
+            Self::Bar => OperatorPrecedence::BitwiseOr,
+            Self::Caret => OperatorPrecedence::BitwiseXor,
+            Self::Ampersand => OperatorPrecedence::BitwiseAnd,
+            Self::EqualsEquals
+            | Self::ExclamationEquals
+            | Self::EqualsEqualsEquals
+            | Self::ExclamationEqualsEquals => OperatorPrecedence::Equality,
+            Self::LessThan
+            | Self::GreaterThan
+            | Self::LessThanEquals
+            | Self::GreaterThanEquals
+            | Self::InstanceOf
+            | Self::In
+            | Self::As
+            | Self::Satisfies => OperatorPrecedence::Relational,
+            Self::LessThanLessThan
+            | Self::GreaterThanGreaterThan
+            | Self::GreaterThanGreaterThanGreaterThan => OperatorPrecedence::Shift,
+            Self::Plus | Self::Minus => OperatorPrecedence::Additive,
+            Self::Asterisk | Self::Slash | Self::Percent => OperatorPrecedence::Multiplicative,
+            Self::AsteriskAsterisk => OperatorPrecedence::Exponentiation,
+            _ => return None,
+        };
+        Some(precedence)
+    }
+}
+
+impl From<&Token> for TokenKind {
+    fn from(token: &Token) -> Self {
+        match token {
+            Token::Illegal(..) => TokenKind::Illegal,
+            Token::Eof => TokenKind::Eof,
+            Token::SingleLineCommentTrivia(..) => TokenKind::SingleLineCommentTrivia,
+            Token::MultiLineCommentTrivia(..) => TokenKind::MultiLineCommentTrivia,
+            Token::NewLineTrivia(..) => TokenKind::NewLineTrivia,
+            Token::WhitespaceTrivia(..) => TokenKind::WhitespaceTrivia,
+            Token::ShebangTrivia(..) => TokenKind::ShebangTrivia,
+            Token::ConflictMarkerTrivia(..) => TokenKind::ConflictMarkerTrivia,
+            Token::NumericLiteral(..) => TokenKind::NumericLiteral,
+            Token::BigIntLiteral(..) => TokenKind::BigIntLiteral,
+            Token::StringLiteral { .. } => TokenKind::StringLiteral,
+            Token::JsxText(..) => TokenKind::JsxText,
+            Token::JsxTextAllWhiteSpaces(..) => TokenKind::JsxTextAllWhiteSpaces,
+            Token::RegularExpressionLiteral { .. } => TokenKind::RegularExpressionLiteral,
+            Token::NoSubstitutionTemplateLiteral(..) => TokenKind::NoSubstitutionTemplateLiteral,
+            Token::TemplateHead(..) => TokenKind::TemplateHead,
+            Token::TemplateMiddle(..) => TokenKind::TemplateMiddle,
+            Token::TemplateTail(..) => TokenKind::TemplateTail,
+            Token::OpenBrace => TokenKind::OpenBrace,
+            Token::CloseBrace => TokenKind::CloseBrace,
+            Token::OpenParen => TokenKind::OpenParen,
+            Token::CloseParen => TokenKind::CloseParen,
+            Token::OpenBracket => TokenKind::OpenBracket,
+            Token::CloseBracket => TokenKind::CloseBracket,
+            Token::Dot => TokenKind::Dot,
+            Token::DotDotDot => TokenKind::DotDotDot,
+            Token::Semicolon => TokenKind::Semicolon,
+            Token::Comma => TokenKind::Comma,
+            Token::QuestionDot => TokenKind::QuestionDot,
+            Token::LessThan => TokenKind::LessThan,
+            Token::LessThanSlash => TokenKind::LessThanSlash,
+            Token::GreaterThan => TokenKind::GreaterThan,
+            Token::LessThanEquals => TokenKind::LessThanEquals,
+            Token::GreaterThanEquals => TokenKind::GreaterThanEquals,
+            Token::EqualsEquals => TokenKind::EqualsEquals,
+            Token::ExclamationEquals => TokenKind::ExclamationEquals,
+            Token::EqualsEqualsEquals => TokenKind::EqualsEqualsEquals,
+            Token::ExclamationEqualsEquals => TokenKind::ExclamationEqualsEquals,
+            Token::EqualsGreaterThan => TokenKind::EqualsGreaterThan,
+            Token::Plus => TokenKind::Plus,
+            Token::Minus => TokenKind::Minus,
+            Token::Asterisk => TokenKind::Asterisk,
+            Token::AsteriskAsterisk => TokenKind::AsteriskAsterisk,
+            Token::Slash => TokenKind::Slash,
+            Token::Percent => TokenKind::Percent,
+            Token::PlusPlus => TokenKind::PlusPlus,
+            Token::MinusMinus => TokenKind::MinusMinus,
+            Token::LessThanLessThan => TokenKind::LessThanLessThan,
+            Token::GreaterThanGreaterThan => TokenKind::GreaterThanGreaterThan,
+            Token::GreaterThanGreaterThanGreaterThan => {
+                TokenKind::GreaterThanGreaterThanGreaterThan
+            }
+            Token::Ampersand => TokenKind::Ampersand,
+            Token::Bar => TokenKind::Bar,
+            Token::Caret => TokenKind::Caret,
+            Token::Bang => TokenKind::Bang,
+            Token::Tilde => TokenKind::Tilde,
+            Token::AmpersandAmpersand => TokenKind::AmpersandAmpersand,
+            Token::BarBar => TokenKind::BarBar,
+            Token::Question => TokenKind::Question,
+            Token::Colon => TokenKind::Colon,
+            Token::At => TokenKind::At,
+            Token::QuestionQuestion => TokenKind::QuestionQuestion,
+            Token::Hash => TokenKind::Hash,
+            Token::Equals => TokenKind::Equals,
+            Token::PlusEquals => TokenKind::PlusEquals,
+            Token::MinusEquals => TokenKind::MinusEquals,
+            Token::AsteriskEquals => TokenKind::AsteriskEquals,
+            Token::AsteriskAsteriskEquals => TokenKind::AsteriskAsteriskEquals,
+            Token::SlashEquals => TokenKind::SlashEquals,
+            Token::PercentEquals => TokenKind::PercentEquals,
+            Token::LessThanLessThanEquals => TokenKind::LessThanLessThanEquals,
+            Token::GreaterThanGreaterThanEquals => TokenKind::GreaterThanGreaterThanEquals,
+            Token::GreaterThanGreaterThanGreaterThanEquals => {
+                TokenKind::GreaterThanGreaterThanGreaterThanEquals
+            }
+            Token::AmpersandEquals => TokenKind::AmpersandEquals,
+            Token::BarEquals => TokenKind::BarEquals,
+            Token::BarBarEquals => TokenKind::BarBarEquals,
+            Token::AmpersandAmpersandEquals => TokenKind::AmpersandAmpersandEquals,
+            Token::QuestionQuestionEquals => TokenKind::QuestionQuestionEquals,
+            Token::CaretEquals => TokenKind::CaretEquals,
+            Token::Identifier(..) => TokenKind::Identifier,
+            Token::PrivateIdentifier(..) => TokenKind::PrivateIdentifier,
+            Token::Break => TokenKind::Break,
+            Token::Case => TokenKind::Case,
+            Token::Catch => TokenKind::Catch,
+            Token::Class => TokenKind::Class,
+            Token::Const => TokenKind::Const,
+            Token::Continue => TokenKind::Continue,
+            Token::Debugger => TokenKind::Debugger,
+            Token::Default => TokenKind::Default,
+            Token::Delete => TokenKind::Delete,
+            Token::Do => TokenKind::Do,
+            Token::Else => TokenKind::Else,
+            Token::Enum => TokenKind::Enum,
+            Token::Export => TokenKind::Export,
+            Token::Extends => TokenKind::Extends,
+            Token::False => TokenKind::False,
+            Token::Finally => TokenKind::Finally,
+            Token::For => TokenKind::For,
+            Token::Function => TokenKind::Function,
+            Token::If => TokenKind::If,
+            Token::Import => TokenKind::Import,
+            Token::In => TokenKind::In,
+            Token::InstanceOf => TokenKind::InstanceOf,
+            Token::New => TokenKind::New,
+            Token::Null => TokenKind::Null,
+            Token::Return => TokenKind::Return,
+            Token::Super => TokenKind::Super,
+            Token::Switch => TokenKind::Switch,
+            Token::This => TokenKind::This,
+            Token::Throw => TokenKind::Throw,
+            Token::True => TokenKind::True,
+            Token::Try => TokenKind::Try,
+            Token::TypeOf => TokenKind::TypeOf,
+            Token::Var => TokenKind::Var,
+            Token::Void => TokenKind::Void,
+            Token::While => TokenKind::While,
+            Token::With => TokenKind::With,
+            Token::Implements => TokenKind::Implements,
+            Token::Interface => TokenKind::Interface,
+            Token::Let => TokenKind::Let,
+            Token::Package => TokenKind::Package,
+            Token::Private => TokenKind::Private,
+            Token::Protected => TokenKind::Protected,
+            Token::Public => TokenKind::Public,
+            Token::Static => TokenKind::Static,
+            Token::Yield => TokenKind::Yield,
+            Token::Abstract => TokenKind::Abstract,
+            Token::As => TokenKind::As,
+            Token::Asserts => TokenKind::Asserts,
+            Token::Any => TokenKind::Any,
+            Token::Async => TokenKind::Async,
+            Token::Await => TokenKind::Await,
+            Token::Boolean => TokenKind::Boolean,
+            Token::Constructor => TokenKind::Constructor,
+            Token::Declare => TokenKind::Declare,
+            Token::Get => TokenKind::Get,
+            Token::Infer => TokenKind::Infer,
+            Token::Is => TokenKind::Is,
+            Token::KeyOf => TokenKind::KeyOf,
+            Token::Module => TokenKind::Module,
+            Token::Namespace => TokenKind::Namespace,
+            Token::Never => TokenKind::Never,
+            Token::Readonly => TokenKind::Readonly,
+            Token::Require => TokenKind::Require,
+            Token::Number => TokenKind::Number,
+            Token::Object => TokenKind::Object,
+            Token::Satisfies => TokenKind::Satisfies,
+            Token::Set => TokenKind::Set,
+            Token::String => TokenKind::String,
+            Token::Symbol => TokenKind::Symbol,
+            Token::Type => TokenKind::Type,
+            Token::Undefined => TokenKind::Undefined,
+            Token::Unique => TokenKind::Unique,
+            Token::Unknown => TokenKind::Unknown,
+            Token::Using => TokenKind::Using,
+            Token::From => TokenKind::From,
+            Token::Global => TokenKind::Global,
+            Token::BigInt => TokenKind::BigInt,
+            Token::Override => TokenKind::Override,
+            Token::Of => TokenKind::Of,
+            Token::ByteOrderMark => TokenKind::ByteOrderMark,
+        }
+    }
+}
+
+impl From<&SpannedToken> for TokenKind {
+    fn from(token: &SpannedToken) -> Self {
+        token.kind
+    }
+}
+
+impl TryFrom<u8> for TokenKind {
+    type Error = u8;
+
+    /// The kind numbered `value`, or the value back if no kind has it.
+    fn try_from(value: u8) -> Result<Self, u8> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|kind| kind.as_u8() == value)
+            .ok_or(value)
+    }
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.text() {
+            Some(text) => write!(f, "'{text}'"),
+            None => fmt::Debug::fmt(self, f),
+        }
+    }
+}
+
+/// A set of [`TokenKind`]s stored as a 256-bit bitset, for questions like
+/// "can this token start a statement?" and for the tokens a parser expected
+/// when it reports an error. Sets are `Copy` and can be built in `const`s.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct TokenKindSet([u64; 4]);
+
+impl TokenKindSet {
+    pub const EMPTY: Self = Self([0; 4]);
+
+    pub const fn new(kinds: &[TokenKind]) -> Self {
+        let mut set = Self::EMPTY;
+        let mut index = 0;
+        while index < kinds.len() {
+            set = set.with(kinds[index]);
+            index += 1;
+        }
+        set
+    }
+
+    /// This set with `kind` added.
+    pub const fn with(self, kind: TokenKind) -> Self {
+        let mut words = self.0;
+        let bit = kind as usize;
+        words[bit / 64] |= 1 << (bit % 64);
+        Self(words)
+    }
+
+    pub const fn union(self, other: Self) -> Self {
+        let mut words = self.0;
+        let mut index = 0;
+        while index < words.len() {
+            words[index] |= other.0[index];
+            index += 1;
+        }
+        Self(words)
+    }
+
+    pub const fn contains(&self, kind: TokenKind) -> bool {
+        let bit = kind as usize;
+        self.0[bit / 64] & (1 << (bit % 64)) != 0
+    }
+
+    pub fn insert(&mut self, kind: TokenKind) {
+        *self = self.with(kind);
+    }
+
+    pub fn remove(&mut self, kind: TokenKind) {
+        let bit = kind as usize;
+        self.0[bit / 64] &= !(1 << (bit % 64));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::EMPTY
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// The kinds in the set, in numbering order.
+    pub fn iter(&self) -> impl Iterator<Item = TokenKind> + '_ {
+        TokenKind::ALL
+            .into_iter()
+            .filter(|&kind| self.contains(kind))
+    }
+}
+
+impl FromIterator<TokenKind> for TokenKindSet {
+    fn from_iter<I: IntoIterator<Item = TokenKind>>(kinds: I) -> Self {
+        let mut set = Self::EMPTY;
+        for kind in kinds {
+            set.insert(kind);
+        }
+        set
+    }
+}
+
+impl fmt::Debug for TokenKindSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::token::LineTerminator;
+
+    #[test]
+    fn numbering_follows_typescript() {
+        assert_eq!(TokenKind::Eof.as_u8(), 1);
+        assert_eq!(TokenKind::OpenBrace.as_u8(), 19);
+        assert_eq!(TokenKind::Identifier.as_u8(), 80);
+        assert_eq!(TokenKind::Break.as_u8(), 83);
+        assert_eq!(TokenKind::Of.as_u8(), 165);
+        assert_eq!(TokenKind::try_from(64), Ok(TokenKind::Equals));
+        assert_eq!(TokenKind::try_from(62), Err(62));
+        assert!(TokenKind::ALL.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn converts_tokens_to_kinds() {
+        assert_eq!(Token::Identifier("x".into()).kind(), TokenKind::Identifier);
+        assert_eq!(
+            Token::NewLineTrivia(LineTerminator::Cr).kind(),
+            TokenKind::NewLineTrivia
+        );
+        let kinds: Vec<TokenKind> = Lexer::new("let x = `a${b}`;")
+            .lex()
+            .iter()
+            .map(TokenKind::from)
+            .filter(|kind| !kind.is_trivia())
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                TokenKind::Let,
+                TokenKind::Identifier,
+                TokenKind::Equals,
+                TokenKind::TemplateHead,
+                TokenKind::Identifier,
+                TokenKind::TemplateTail,
+                TokenKind::Semicolon,
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn fixed_text_round_trips_through_find_match() {
+        for kind in TokenKind::ALL {
+            if let Some(text) = kind.text()
+                && kind != TokenKind::LessThanSlash
+            {
+                let token = crate::token::find_match(text);
+                assert_eq!(token.map(|token| token.kind()), Some(kind), "{text}");
+            }
+        }
+        assert_eq!(TokenKind::CloseParen.to_string(), "')'");
+        assert_eq!(TokenKind::Identifier.to_string(), "Identifier");
+    }
+
+    #[test]
+    fn sets_hold_kinds_across_words() {
+        const STARTS: TokenKindSet =
+            TokenKindSet::new(&[TokenKind::Eof, TokenKind::Identifier, TokenKind::Of]);
+        let mut set = STARTS.union(TokenKindSet::new(&[TokenKind::ByteOrderMark]));
+
+        assert_eq!(set.len(), 4);
+        assert!(set.contains(TokenKind::Of) && set.contains(TokenKind::ByteOrderMark));
+        assert!(!set.contains(TokenKind::Break));
+        set.remove(TokenKind::Eof);
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            [
+                TokenKind::Identifier,
+                TokenKind::Of,
+                TokenKind::ByteOrderMark
+            ]
+        );
+        assert_eq!(
+            format!("{:?}", TokenKindSet::new(&[TokenKind::Comma])),
+            "{Comma}"
+        );
+        assert!(TokenKindSet::EMPTY.is_empty());
+    }
+}
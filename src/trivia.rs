@@ -0,0 +1,230 @@
+//! Trivia module — a view of a token stream that skips trivia while keeping
+//! track of where it was.
+
+use std::ops::Range;
+
+use crate::token::{SpannedToken, TokenPayload};
+use crate::token_kind::TokenKind;
+
+/// A significant (non-trivia) token and the trivia attached to it.
+///
+/// Trivia between two significant tokens is split at the first line break:
+/// comments and whitespace on the same line as the previous token are its
+/// trailing trivia, and everything from the line break on is leading trivia
+/// of the next token. The ranges index into [`TokenList::all`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignificantToken {
+    /// Index of the token itself in [`TokenList::all`].
+    pub index: usize,
+    /// Whether a line break (a newline or a comment spanning lines) occurs
+    /// between the previous significant token and this one.
+    pub has_preceding_line_break: bool,
+    pub leading_trivia: Range<usize>,
+    pub trailing_trivia: Range<usize>,
+}
+
+/// The tokens of a document, trivia included, together with an index of the
+/// significant ones. A parser walks [`TokenList::significant`] and never
+/// sees trivia; formatters and doc-comment extraction reach the trivia
+/// around a token through its ranges.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TokenList {
+    tokens: Vec<SpannedToken>,
+    significant: Vec<SignificantToken>,
+}
+
+impl TokenList {
+    /// Build the view over `tokens`, a complete lexer output ending in `Eof`.
+    /// `Eof` counts as significant, so trivia at the end of the file is its
+    /// leading trivia.
+    pub fn new(tokens: Vec<SpannedToken>) -> Self {
+        let mut list = Self {
+            tokens,
+            significant: Vec::new(),
+        };
+        list.index_significant();
+        list
+    }
+
+    /// Change the tokens with `edit`, such as [`crate::lexer::Lexer::relex`],
+    /// and index them again. The tokens must still be a complete lexer
+    /// output afterwards. Both vectors are updated in place, so an edit
+    /// that leaves the token count alone allocates nothing.
+    pub fn update(&mut self, edit: impl FnOnce(&mut Vec<SpannedToken>)) {
+        edit(&mut self.tokens);
+        self.index_significant();
+    }
+
+    fn index_significant(&mut self) {
+        let tokens = &self.tokens;
+        let significant = &mut self.significant;
+        significant.clear();
+        let mut index = 0;
+        while index < tokens.len() {
+            // Trailing trivia of the previous token runs up to the next line break
+            let trailing_start = index;
+            if !significant.is_empty() {
+                while index < tokens.len()
+                    && tokens[index].is_trivia()
+                    && !is_line_break(&tokens[index])
+                {
+                    index += 1;
+                }
+            }
+            if let Some(previous) = significant.last_mut() {
+                previous.trailing_trivia = trailing_start..index;
+            }
+
+            let leading_start = index;
+            let mut has_preceding_line_break = false;
+            while index < tokens.len() && tokens[index].is_trivia() {
+                has_preceding_line_break |= is_line_break(&tokens[index]);
+                index += 1;
+            }
+            if index == tokens.len() {
+                break;
+            }
+
+            significant.push(SignificantToken {
+                index,
+                has_preceding_line_break,
+                leading_trivia: leading_start..index,
+                trailing_trivia: index + 1..index + 1,
+            });
+            index += 1;
+        }
+    }
+
+    /// Every token, trivia included, in source order.
+    pub fn all(&self) -> &[SpannedToken] {
+        &self.tokens
+    }
+
+    /// The significant tokens, in source order.
+    pub fn significant(&self) -> &[SignificantToken] {
+        &self.significant
+    }
+
+    pub fn token(&self, significant: &SignificantToken) -> &SpannedToken {
+        &self.tokens[significant.index]
+    }
+
+    pub fn leading_trivia(&self, significant: &SignificantToken) -> &[SpannedToken] {
+        &self.tokens[significant.leading_trivia.clone()]
+    }
+
+    pub fn trailing_trivia(&self, significant: &SignificantToken) -> &[SpannedToken] {
+        &self.tokens[significant.trailing_trivia.clone()]
+    }
+
+    /// Iterate over the significant tokens themselves.
+    pub fn iter(&self) -> impl Iterator<Item = &SpannedToken> {
+        self.significant
+            .iter()
+            .map(|significant| self.token(significant))
+    }
+}
+
+impl From<Vec<SpannedToken>> for TokenList {
+    fn from(tokens: Vec<SpannedToken>) -> Self {
+        Self::new(tokens)
+    }
+}
+
+/// Whether `token` is trivia that breaks the line: a newline, or a comment
+/// or conflict marker that spans lines.
+pub(crate) fn is_line_break(token: &SpannedToken) -> bool {
+    match (token.kind, &token.payload) {
+        (TokenKind::NewLineTrivia, _) => true,
+        (
+            TokenKind::MultiLineCommentTrivia | TokenKind::ConflictMarkerTrivia,
+            TokenPayload::Text(text),
+        ) => text.contains(['\n', '\r', '\u{2028}', '\u{2029}']),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn token_list(input: &str) -> TokenList {
+        TokenList::new(Lexer::new(input).lex())
+    }
+
+    fn texts(tokens: &[SpannedToken], input: &str) -> Vec<String> {
+        tokens
+            .iter()
+            .map(|token| input[token.start..token.end].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn skips_trivia_and_flags_line_breaks() {
+        let input = "return\nx; a /* one\ntwo */ ++b";
+        let list = token_list(input);
+
+        let summary: Vec<(String, bool)> = list
+            .significant()
+            .iter()
+            .map(|significant| {
+                let token = list.token(significant);
+                (
+                    input[token.start..token.end].to_string(),
+                    significant.has_preceding_line_break,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("return".to_string(), false),
+                ("x".to_string(), true),
+                (";".to_string(), false),
+                ("a".to_string(), false),
+                ("++".to_string(), true),
+                ("b".to_string(), false),
+                (String::new(), false),
+            ]
+        );
+        assert_eq!(
+            list.iter().last().map(|token| token.kind),
+            Some(TokenKind::Eof)
+        );
+    }
+
+    #[test]
+    fn splits_trivia_at_first_line_break() {
+        let input = "a; // note\n  /** doc */\nb";
+        let list = token_list(input);
+        let significant = list.significant();
+
+        let semicolon = &significant[1];
+        assert_eq!(
+            texts(list.trailing_trivia(semicolon), input),
+            [" ", "// note"]
+        );
+
+        let b = &significant[2];
+        assert_eq!(
+            texts(list.leading_trivia(b), input),
+            ["\n", "  ", "/** doc */", "\n"]
+        );
+        assert!(b.has_preceding_line_break);
+    }
+
+    #[test]
+    fn ranges_cover_every_token() {
+        let input = "#!/usr/bin/env node\n  let x = 1; /* a */ // b\n\n";
+        let list = token_list(input);
+
+        let mut covered = Vec::new();
+        for significant in list.significant() {
+            covered.extend(significant.leading_trivia.clone());
+            covered.push(significant.index);
+            covered.extend(significant.trailing_trivia.clone());
+        }
+        assert_eq!(covered, (0..list.all().len()).collect::<Vec<_>>());
+    }
+}